@@ -0,0 +1,308 @@
+//! Import of Artisan roast logs. Artisan saves `.alog` files as a Python
+//! dictionary literal, so they are parsed into JSON values first.
+
+use crate::models::{ProfileSample, RoastEvent, RoastEventKind};
+use serde_json::{Map, Number, Value};
+
+/// Seconds of bean temperature history used for each rate of rise reading.
+const RATE_OF_RISE_WINDOW_SECONDS: f64 = 30.0;
+
+/// Positions in Artisan's `timeindex` list.
+const CHARGE: usize = 0;
+const DRY_END: usize = 1;
+const FIRST_CRACK: usize = 2;
+const DROP: usize = 6;
+
+/// Deepest nesting of dicts and lists accepted, as in serde_json. Each level
+/// is a recursive call, so without a limit a crafted file overflows the stack.
+const MAX_NESTING: usize = 128;
+
+#[derive(Debug)]
+pub struct ArtisanProfile {
+    pub temperature_unit: String,
+    pub samples: Vec<ProfileSample>,
+    pub events: Vec<RoastEvent>,
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((at, c)) => Err(format!("expected '{}' at {}, found '{}'", expected, at, c)),
+            None => Err(format!("expected '{}' but the file ended", expected)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some((_, '{')) => self.nested(Self::dict),
+            Some((_, '[')) => self.nested(|parser| parser.sequence(']')),
+            Some((_, '(')) => self.nested(|parser| parser.sequence(')')),
+            Some((_, '\'' | '"')) => self.string().map(Value::String),
+            Some((_, 'u')) => {
+                // Python 2 unicode literal, e.g. u'Ethiopia'
+                self.chars.next();
+                self.string().map(Value::String)
+            }
+            Some(_) => self.scalar(),
+            None => Err("unexpected end of file".to_string()),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<Value, String>,
+    ) -> Result<Value, String> {
+        if self.depth >= MAX_NESTING {
+            return Err(format!("nested more than {} levels deep", MAX_NESTING));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn dict(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut map = Map::new();
+        loop {
+            self.skip_whitespace();
+            if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+                return Ok(Value::Object(map));
+            }
+            let key = match self.value()? {
+                Value::String(key) => key,
+                other => other.to_string(),
+            };
+            self.expect(':')?;
+            map.insert(key, self.value()?);
+            self.skip_whitespace();
+            if self.chars.next_if(|(_, c)| *c == ',').is_none() {
+                self.expect('}')?;
+                return Ok(Value::Object(map));
+            }
+        }
+    }
+
+    fn sequence(&mut self, close: char) -> Result<Value, String> {
+        self.chars.next();
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.chars.next_if(|(_, c)| *c == close).is_some() {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_whitespace();
+            if self.chars.next_if(|(_, c)| *c == ',').is_none() {
+                self.expect(close)?;
+                return Ok(Value::Array(items));
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let (start, quote) = self.chars.next().ok_or("unexpected end of file")?;
+        let mut out = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, c)) if c == quote => return Ok(out),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, 'n')) => out.push('\n'),
+                    Some((_, 't')) => out.push('\t'),
+                    Some((_, 'r')) => out.push('\r'),
+                    Some((_, 'x')) => out.push(self.escaped_char(2)?),
+                    Some((_, 'u')) => out.push(self.escaped_char(4)?),
+                    Some((_, c)) => out.push(c),
+                    None => break,
+                },
+                Some((_, c)) => out.push(c),
+                None => break,
+            }
+        }
+        Err(format!("string starting at {} is not closed", start))
+    }
+
+    fn escaped_char(&mut self, digits: usize) -> Result<char, String> {
+        let hex: String = (0..digits)
+            .filter_map(|_| self.chars.next())
+            .map(|(_, c)| c)
+            .collect();
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("invalid escape '{}'", hex))
+    }
+
+    fn scalar(&mut self) -> Result<Value, String> {
+        let mut token = String::new();
+        while let Some((_, c)) = self
+            .chars
+            .next_if(|(_, c)| c.is_alphanumeric() || matches!(c, '-' | '+' | '.' | '_'))
+        {
+            token.push(c);
+        }
+
+        match token.as_str() {
+            "True" | "true" => Ok(Value::Bool(true)),
+            "False" | "false" => Ok(Value::Bool(false)),
+            // NaN and infinities have no JSON form and mean "no reading" anyway
+            "None" | "null" | "nan" | "inf" | "-inf" => Ok(Value::Null),
+            "" => Err(match self.chars.peek() {
+                Some((at, c)) => format!("unexpected '{}' at {}", c, at),
+                None => "unexpected end of file".to_string(),
+            }),
+            _ => token
+                .parse::<i64>()
+                .map(Value::from)
+                .or_else(|_| token.parse::<f64>().map(|f| Number::from_f64(f).into()))
+                .map_err(|_| format!("unexpected '{}'", token)),
+        }
+    }
+}
+
+/// Parses a Python literal (or JSON) into a JSON value.
+fn parse_literal(input: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        chars: input.char_indices().peekable(),
+        depth: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    match parser.chars.next() {
+        Some((at, _)) => Err(format!("unexpected content after the profile at {}", at)),
+        None => Ok(value),
+    }
+}
+
+fn numbers(log: &Map<String, Value>, key: &str) -> Result<Vec<Option<f64>>, String> {
+    log.get(key)
+        .and_then(Value::as_array)
+        .map(|values| values.iter().map(Value::as_f64).collect())
+        .ok_or_else(|| format!("missing '{}' readings", key))
+}
+
+/// Artisan records -1 (or 0 for an unused probe) when there is no reading.
+fn reading(value: Option<f64>) -> Option<f64> {
+    value.filter(|t| *t > 0.0)
+}
+
+/// Reads the samples and event markers from the text of an `.alog` file.
+pub fn parse_alog(input: &str) -> Result<ArtisanProfile, String> {
+    let log = match parse_literal(input)? {
+        Value::Object(log) => log,
+        _ => return Err("an Artisan profile must be a dictionary".to_string()),
+    };
+
+    let times = numbers(&log, "timex")?;
+    let env_temps = numbers(&log, "temp1")?;
+    let bean_temps = numbers(&log, "temp2")?;
+    if times.is_empty() {
+        return Err("the profile has no readings".to_string());
+    }
+    if env_temps.len() != times.len() || bean_temps.len() != times.len() {
+        return Err("'timex', 'temp1' and 'temp2' must have the same length".to_string());
+    }
+    let times = times
+        .into_iter()
+        .collect::<Option<Vec<f64>>>()
+        .ok_or("'timex' must only contain numbers")?;
+
+    let timeindex: Vec<i64> = log
+        .get("timeindex")
+        .and_then(Value::as_array)
+        .map(|indexes| indexes.iter().map(|i| i.as_i64().unwrap_or(0)).collect())
+        .unwrap_or_default();
+    let marker = |position: usize| {
+        timeindex
+            .get(position)
+            .copied()
+            .and_then(|i| usize::try_from(i).ok())
+            .filter(|i| *i < times.len())
+    };
+
+    let charge = marker(CHARGE).unwrap_or(0);
+    let charge_time = times[charge];
+
+    let mut samples: Vec<ProfileSample> = times
+        .iter()
+        .zip(&bean_temps)
+        .zip(&env_temps)
+        .map(|((time, bean_temp), env_temp)| ProfileSample {
+            time_seconds: time - charge_time,
+            bean_temp: reading(*bean_temp),
+            env_temp: reading(*env_temp),
+            rate_of_rise: None,
+        })
+        .collect();
+    // Readings are in time order, so the window's start only moves forward
+    let mut window_start = 0;
+    for i in 0..samples.len() {
+        let now = &samples[i];
+        while window_start < i
+            && now.time_seconds - samples[window_start].time_seconds > RATE_OF_RISE_WINDOW_SECONDS
+        {
+            window_start += 1;
+        }
+        let earlier = samples[window_start..i]
+            .iter()
+            .find(|sample| sample.bean_temp.is_some());
+        samples[i].rate_of_rise = match (now.bean_temp, earlier) {
+            (Some(bean_temp), Some(earlier)) if now.time_seconds > earlier.time_seconds => {
+                let rise = bean_temp - earlier.bean_temp.unwrap_or(bean_temp);
+                Some(rise / (now.time_seconds - earlier.time_seconds) * 60.0)
+            }
+            _ => None,
+        };
+    }
+
+    let event = |kind: RoastEventKind, index: usize| RoastEvent {
+        kind,
+        time_seconds: samples[index].time_seconds,
+        bean_temp: samples[index].bean_temp,
+    };
+    let dry_end = marker(DRY_END).filter(|i| *i > charge);
+    let first_crack = marker(FIRST_CRACK).filter(|i| *i > charge);
+    let drop = marker(DROP).filter(|i| *i > charge);
+
+    // The turning point is the lowest bean temperature after charge
+    let tp_search_end = dry_end.or(first_crack).or(drop).unwrap_or(samples.len());
+    let turning_point = (charge + 1..tp_search_end)
+        .filter_map(|i| Some((i, samples[i].bean_temp?)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i);
+
+    let mut events = vec![event(RoastEventKind::Charge, charge)];
+    for (kind, index) in [
+        (RoastEventKind::TurningPoint, turning_point),
+        (RoastEventKind::DryEnd, dry_end),
+        (RoastEventKind::FirstCrack, first_crack),
+        (RoastEventKind::Drop, drop),
+    ] {
+        if let Some(index) = index {
+            events.push(event(kind, index));
+        }
+    }
+
+    let temperature_unit = match log.get("mode").and_then(Value::as_str) {
+        Some("F") => "F",
+        _ => "C",
+    };
+
+    Ok(ArtisanProfile {
+        temperature_unit: temperature_unit.to_string(),
+        samples,
+        events,
+    })
+}
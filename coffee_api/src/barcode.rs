@@ -0,0 +1,202 @@
+//! GTIN check digits and EAN-13 barcode rendering for bag labels.
+
+use std::fmt::Write;
+
+/// Left-hand odd parity patterns for digits 0-9. Even parity patterns are
+/// these read backwards and inverted, right-hand patterns are these inverted.
+const L_CODES: [&str; 10] = [
+    "0001101", "0011001", "0010011", "0111101", "0100011", "0110001", "0101111", "0111011",
+    "0110111", "0001011",
+];
+
+/// Which of the six left-hand digits use even parity, chosen by the first
+/// digit of the code, which is not drawn as bars.
+const PARITY: [&str; 10] = [
+    "LLLLLL", "LLGLGG", "LLGGLG", "LLGGGL", "LGLLGG", "LGGLLG", "LGGGLL", "LGLGLG", "LGLGGL",
+    "LGGLGL",
+];
+
+/// Bars of an EAN-13 symbol, excluding quiet zones.
+const SYMBOL_MODULES: usize = 95;
+/// Blank modules required either side of the symbol.
+const LEFT_QUIET_ZONE: usize = 11;
+const RIGHT_QUIET_ZONE: usize = 7;
+const BAR_HEIGHT: usize = 60;
+/// Guard bars extend below the others, into the row of digits.
+const GUARD_HEIGHT: usize = 65;
+const TEXT_HEIGHT: usize = 10;
+
+/// The GTIN check digit for `digits`, which are every digit but the check
+/// digit itself. Digits are weighted 3 and 1 alternately from the right.
+pub fn gtin_check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| u32::from(digit) * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+/// Validates a GTIN-12 (UPC-A), GTIN-13 (EAN-13) or GTIN-14 and returns it
+/// as the 13 digits of an EAN-13 barcode. Spaces and hyphens are ignored.
+pub fn normalize_gtin(code: &str) -> Result<String, String> {
+    let code: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect();
+    if !code.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("GTIN '{}' must only contain digits", code));
+    }
+    let ean = match code.len() {
+        12 => format!("0{}", code),
+        13 => code.clone(),
+        14 if code.starts_with('0') => code[1..].to_string(),
+        14 => {
+            return Err(format!(
+                "GTIN '{}' identifies a case, not a bag that can carry an EAN-13",
+                code
+            ));
+        }
+        _ => return Err(format!("GTIN '{}' must have 12, 13 or 14 digits", code)),
+    };
+
+    let digits: Vec<u8> = ean.bytes().map(|b| b - b'0').collect();
+    let (payload, check) = digits.split_at(12);
+    if gtin_check_digit(payload) != check[0] {
+        return Err(format!("GTIN '{}' has the wrong check digit", code));
+    }
+    Ok(ean)
+}
+
+/// The 95 modules of the EAN-13 symbol for `ean`, true where a bar is drawn.
+/// `ean` must already be normalized.
+pub fn ean13_modules(ean: &str) -> Vec<bool> {
+    let digits: Vec<usize> = ean.bytes().map(|b| usize::from(b - b'0')).collect();
+    let mut pattern = String::with_capacity(SYMBOL_MODULES);
+
+    pattern.push_str("101");
+    for (&digit, parity) in digits[1..7].iter().zip(PARITY[digits[0]].chars()) {
+        let code = L_CODES[digit];
+        if parity == 'L' {
+            pattern.push_str(code);
+        } else {
+            pattern.extend(code.chars().rev().map(invert));
+        }
+    }
+    pattern.push_str("01010");
+    for &digit in &digits[7..] {
+        pattern.extend(L_CODES[digit].chars().map(invert));
+    }
+    pattern.push_str("101");
+
+    pattern.chars().map(|c| c == '1').collect()
+}
+
+fn invert(module: char) -> char {
+    if module == '1' { '0' } else { '1' }
+}
+
+/// Whether module `index` belongs to the start, centre or end guard bars.
+fn is_guard(index: usize) -> bool {
+    index < 3 || (45..50).contains(&index) || index >= 92
+}
+
+/// Renders `ean` as an SVG with its digits printed underneath. One module is
+/// one user unit; the SVG scales to whatever size the label needs.
+pub fn render_svg(ean: &str) -> String {
+    let width = LEFT_QUIET_ZONE + SYMBOL_MODULES + RIGHT_QUIET_ZONE;
+    let height = BAR_HEIGHT + TEXT_HEIGHT;
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {width} {height}" width="{}" height="{}">"#,
+        width * 2,
+        height * 2
+    );
+    let _ = write!(
+        svg,
+        r#"<rect width="{width}" height="{height}" fill="white"/><g fill="black">"#
+    );
+
+    // Runs of adjacent bars are drawn as one rectangle
+    let modules = ean13_modules(ean);
+    let mut index = 0;
+    while index < modules.len() {
+        if !modules[index] {
+            index += 1;
+            continue;
+        }
+        let start = index;
+        while index < modules.len() && modules[index] {
+            index += 1;
+        }
+        let bar_height = if is_guard(start) {
+            GUARD_HEIGHT
+        } else {
+            BAR_HEIGHT
+        };
+        let _ = write!(
+            svg,
+            r#"<rect x="{}" y="0" width="{}" height="{}"/>"#,
+            LEFT_QUIET_ZONE + start,
+            index - start,
+            bar_height
+        );
+    }
+
+    svg.push_str(r#"</g><g font-family="monospace" font-size="9" text-anchor="middle">"#);
+    let baseline = height - 1;
+    let _ = write!(
+        svg,
+        r#"<text x="{}" y="{baseline}">{}</text>"#,
+        LEFT_QUIET_ZONE - 5,
+        &ean[..1]
+    );
+    let _ = write!(
+        svg,
+        r#"<text x="{}" y="{baseline}" textLength="38">{}</text>"#,
+        LEFT_QUIET_ZONE + 24,
+        &ean[1..7]
+    );
+    let _ = write!(
+        svg,
+        r#"<text x="{}" y="{baseline}" textLength="38">{}</text>"#,
+        LEFT_QUIET_ZONE + 71,
+        &ean[7..]
+    );
+    svg.push_str("</g></svg>");
+    svg
+}
+
+/// Renders the bars of `ean` as a greyscale PNG, `scale` pixels per module.
+/// Printers that need the digits print them from the label text instead.
+pub fn render_png(ean: &str, scale: u32) -> Result<Vec<u8>, String> {
+    let scale = scale as usize;
+    let width = (LEFT_QUIET_ZONE + SYMBOL_MODULES + RIGHT_QUIET_ZONE) * scale;
+    let height = GUARD_HEIGHT * scale;
+    let modules = ean13_modules(ean);
+
+    let mut pixels = vec![u8::MAX; width * height];
+    for (row, line) in pixels.chunks_mut(width).enumerate() {
+        for (index, _) in modules.iter().enumerate().filter(|(_, bar)| **bar) {
+            let bar_height = if is_guard(index) {
+                GUARD_HEIGHT
+            } else {
+                BAR_HEIGHT
+            };
+            if row < bar_height * scale {
+                let x = (LEFT_QUIET_ZONE + index) * scale;
+                line[x..x + scale].fill(0);
+            }
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(|e| format!("Failed to encode barcode PNG: {}", e))?;
+    Ok(png)
+}
@@ -0,0 +1,97 @@
+use crate::db::Db;
+use crate::error::ApiResult;
+use crate::models::{Certification, CertificationType, GreenCoffee, Product, Roast};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use surrealdb::sql::Thing;
+
+/// Optional `?certification=organic` for lists that can be narrowed to
+/// certified coffees.
+#[derive(Debug, Deserialize)]
+pub struct CertificationQuery {
+    pub certification: Option<CertificationType>,
+}
+
+pub fn validate_certifications(certifications: &[Certification]) -> Result<(), String> {
+    for (i, certification) in certifications.iter().enumerate() {
+        if certification.certificate_number.trim().is_empty() {
+            return Err("Certificate number must not be empty".to_string());
+        }
+        if certifications[..i]
+            .iter()
+            .any(|other| other.certification_type == certification.certification_type)
+        {
+            return Err(format!(
+                "Certificate '{}' repeats a certification the lot already has",
+                certification.certificate_number
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Every green lot in `roast`, following post-roast blend components that
+/// were themselves blends back to their own greens.
+fn roast_greens(roast: &Roast, roasts: &[Roast], greens: &mut Vec<Thing>) {
+    for green in roast.greens() {
+        if !greens.contains(green) {
+            greens.push(green.clone());
+        }
+    }
+    for component in &roast.components {
+        if component.green_coffee.is_some() || component.roast.is_none() {
+            continue;
+        }
+        if let Some(component_roast) = roasts
+            .iter()
+            .find(|other| other.id.is_some() && other.id == component.roast)
+        {
+            roast_greens(component_roast, roasts, greens);
+        }
+    }
+}
+
+/// The certifications every green in `product` holds at `at`. A product
+/// without a roast, or whose roast has no greens, holds none.
+pub fn product_certifications(
+    product: &Product,
+    roasts: &[Roast],
+    greens: &[GreenCoffee],
+    at: DateTime<Utc>,
+) -> Vec<CertificationType> {
+    let Some(roast) = roasts
+        .iter()
+        .find(|roast| roast.id.is_some() && roast.id == product.roast)
+    else {
+        return Vec::new();
+    };
+    let mut green_ids = Vec::new();
+    roast_greens(roast, roasts, &mut green_ids);
+    if green_ids.is_empty() {
+        return Vec::new();
+    }
+
+    CertificationType::ALL
+        .into_iter()
+        .filter(|&certification_type| {
+            green_ids.iter().all(|green_id| {
+                greens
+                    .iter()
+                    .find(|green| green.id.as_ref() == Some(green_id))
+                    .is_some_and(|green| green.is_certified(certification_type, at))
+            })
+        })
+        .collect()
+}
+
+/// Fills in the certifications of each of `products` from its greens.
+pub async fn certify_products(db: &Db, products: &mut [Product]) -> ApiResult<()> {
+    let roasts: Vec<Roast> = db.select("roast").await?;
+    let greens: Vec<GreenCoffee> = db.select("green_coffee").await?;
+    let now = Utc::now();
+
+    for product in products {
+        product.certifications = product_certifications(product, &roasts, &greens, now);
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::fx::FxTable;
+use crate::models::{
+    BlendMethod, CostSettings, GreenCoffee, Product, ProductCost, ProductVariant, Roast, RoastCost,
+};
+use coffee_shared::{Money, MoneyError};
+use rust_decimal::Decimal;
+use surrealdb::sql::Thing;
+
+/// Key of the single cost settings record.
+pub const COST_SETTINGS_KEY: (&str, &str) = ("cost_settings", "current");
+
+/// The stored cost settings, or the defaults if none have been saved.
+pub async fn load_settings(db: &Db) -> ApiResult<CostSettings> {
+    let settings: Option<CostSettings> = db.select(COST_SETTINGS_KEY).await?;
+
+    Ok(settings.unwrap_or_default())
+}
+
+pub fn grams_to_kg(grams: f64) -> ApiResult<Decimal> {
+    Decimal::try_from(grams)
+        .map(|grams| grams / Decimal::ONE_THOUSAND)
+        .map_err(|_| ApiError::BadRequest {
+            message: format!("{} grams is not a usable weight", grams),
+        })
+}
+
+/// Costs a roast batch: the green coffee that went in plus labour and energy,
+/// spread over the roasted weight that came out. Pre-roast blends cost each
+/// green by its share of the batch.
+async fn batch_cost(
+    db: &Db,
+    settings: &CostSettings,
+    fx: &FxTable,
+    roast: &Roast,
+    roast_id: Thing,
+) -> ApiResult<RoastCost> {
+    let loss_percent = roast
+        .loss_percent()
+        .filter(|_| roast.yield_grams > 0.0)
+        .ok_or_else(|| ApiError::BadRequest {
+            message: format!("Roast '{}' needs a batch size and yield to cost", roast_id),
+        })?;
+
+    let usage = roast.green_usage();
+    if usage.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: format!("Roast '{}' has no green coffee to cost", roast_id),
+        });
+    }
+    let mut green_cost = Money::zero(settings.currency);
+    for (green_id, grams) in usage {
+        let green: Option<GreenCoffee> = db.select(record_key(&green_id)).await?;
+        let price_per_kg =
+            green
+                .and_then(|green| green.price_per_kg)
+                .ok_or_else(|| ApiError::BadRequest {
+                    message: format!("Green coffee '{}' has no price per kg", green_id),
+                })?;
+        let cost = fx
+            .convert(&price_per_kg, settings.currency)?
+            .times(grams_to_kg(grams)?)?;
+        green_cost = green_cost.checked_add(&cost)?;
+    }
+    let overheads = Money::new(settings.labour_per_batch, settings.currency)
+        .checked_add(&Money::new(settings.energy_per_batch, settings.currency))?;
+    let total_cost = green_cost.checked_add(&overheads)?;
+    let roasted_kg = grams_to_kg(roast.yield_grams)?;
+
+    Ok(RoastCost {
+        roast: roast_id,
+        green_cost: green_cost.rounded(),
+        overheads,
+        total_cost: total_cost.rounded(),
+        loss_percent,
+        cost_per_roasted_kg: total_cost.divided_by(roasted_kg)?.rounded(),
+    })
+}
+
+/// Costs a post-roast blend from the share of each component roast's cost
+/// that went into it. Its loss is the weighted loss of the components.
+async fn blend_cost(
+    db: &Db,
+    settings: &CostSettings,
+    fx: &FxTable,
+    roast: &Roast,
+    roast_id: Thing,
+) -> ApiResult<RoastCost> {
+    if roast.yield_grams <= 0.0 {
+        return Err(ApiError::BadRequest {
+            message: format!("Roast '{}' needs a yield to cost", roast_id),
+        });
+    }
+    let blended_kg = grams_to_kg(roast.yield_grams)?;
+
+    let mut green_cost = Money::zero(settings.currency);
+    let mut overheads = Money::zero(settings.currency);
+    let mut loss_percent = 0.0;
+    for component in &roast.components {
+        let part_id = component.roast.clone().ok_or_else(|| ApiError::Internal {
+            message: format!("Blend '{}' has a component without a roast", roast_id),
+        })?;
+        let part: Option<Roast> = db.select(record_key(&part_id)).await?;
+        let part = part.ok_or_else(|| ApiError::NotFound {
+            message: format!("Roast '{}' not found", part_id),
+        })?;
+        let part_cost = batch_cost(db, settings, fx, &part, part_id).await?;
+
+        // The fraction of the component batch that went into this blend
+        let share =
+            Decimal::try_from(component.percent / 100.0).map_err(|_| ApiError::BadRequest {
+                message: format!("{}% is not a usable share", component.percent),
+            })?;
+        let part_kg = grams_to_kg(part.yield_grams)?;
+        let scale = blended_kg
+            .checked_mul(share)
+            .and_then(|kg| kg.checked_div(part_kg))
+            .ok_or(MoneyError::Overflow)?;
+        green_cost = green_cost.checked_add(&part_cost.green_cost.times(scale)?)?;
+        overheads = overheads.checked_add(&part_cost.overheads.times(scale)?)?;
+        loss_percent += part_cost.loss_percent * component.percent / 100.0;
+    }
+    let total_cost = green_cost.checked_add(&overheads)?;
+
+    Ok(RoastCost {
+        roast: roast_id,
+        green_cost: green_cost.rounded(),
+        overheads: overheads.rounded(),
+        total_cost: total_cost.rounded(),
+        loss_percent,
+        cost_per_roasted_kg: total_cost.divided_by(blended_kg)?.rounded(),
+    })
+}
+
+/// Costs a roast, whether a single batch or a blend of other roasts.
+pub async fn roast_cost(
+    db: &Db,
+    settings: &CostSettings,
+    fx: &FxTable,
+    roast: &Roast,
+) -> ApiResult<RoastCost> {
+    let roast_id = roast.id.clone().ok_or_else(|| ApiError::Internal {
+        message: "Roast record has no id".to_string(),
+    })?;
+
+    match roast.blend {
+        Some(BlendMethod::PostRoast) => blend_cost(db, settings, fx, roast, roast_id).await,
+        _ => batch_cost(db, settings, fx, roast, roast_id).await,
+    }
+}
+
+/// Costs one unit of a product variant from the roast it is packed from, and
+/// works out its margin against the variant's price.
+pub async fn product_cost(
+    db: &Db,
+    settings: &CostSettings,
+    fx: &FxTable,
+    product: &Product,
+    variant: &ProductVariant,
+) -> ApiResult<ProductCost> {
+    let product_id = product.id.clone().ok_or_else(|| ApiError::Internal {
+        message: "Product record has no id".to_string(),
+    })?;
+    let roast_id = product.roast.clone().ok_or_else(|| ApiError::BadRequest {
+        message: format!("Product '{}' has no roast to cost", product_id),
+    })?;
+    let roast: Option<Roast> = db.select(record_key(&roast_id)).await?;
+    let roast = roast.ok_or_else(|| ApiError::NotFound {
+        message: format!("Roast '{}' not found", roast_id),
+    })?;
+    let cost_per_roasted_kg = roast_cost(db, settings, fx, &roast)
+        .await?
+        .cost_per_roasted_kg;
+
+    let unit_cost = cost_per_roasted_kg
+        .times(grams_to_kg(variant.package_size_grams)?)?
+        .checked_add(&Money::new(settings.packaging_per_unit, settings.currency))?
+        .rounded();
+    let price = fx.convert(&variant.price, settings.currency)?;
+    let margin = price.checked_sub(&unit_cost)?;
+    let margin_percent = if price.amount.is_zero() {
+        Decimal::ZERO
+    } else {
+        margin
+            .divided_by(price.amount)?
+            .times(Decimal::ONE_HUNDRED)?
+            .amount
+            .round_dp(2)
+    };
+
+    Ok(ProductCost {
+        product: product_id,
+        variant: variant.key.clone(),
+        roast: roast_id,
+        cost_per_roasted_kg,
+        unit_cost,
+        price,
+        margin,
+        margin_percent,
+        below_threshold: margin_percent < settings.min_margin_percent,
+    })
+}
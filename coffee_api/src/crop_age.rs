@@ -0,0 +1,95 @@
+use crate::db::Db;
+use crate::error::ApiResult;
+use crate::models::{CropAge, CropAgeSettings, CropAgeStatus, CropRule, GreenCoffee};
+use chrono::{DateTime, Duration, Utc};
+
+/// Key of the single crop age settings record.
+pub const CROP_AGE_SETTINGS_KEY: (&str, &str) = ("crop_age_settings", "current");
+
+/// Longest crop life accepted, in months.
+pub const MAX_CROP_MONTHS: u32 = 120;
+
+/// Longest past-crop warning window accepted, in days. Like crop life it is
+/// added to dates, which must stay within chrono's range.
+pub const MAX_WARNING_DAYS: i64 = 3650;
+
+/// The stored crop age settings, or the defaults if none have been saved.
+pub async fn load_crop_age_settings(db: &Db) -> ApiResult<CropAgeSettings> {
+    let settings: Option<CropAgeSettings> = db.select(CROP_AGE_SETTINGS_KEY).await?;
+
+    Ok(settings.unwrap_or_default())
+}
+
+pub fn validate_warning_days(days: i64) -> Result<(), String> {
+    if !(0..=MAX_WARNING_DAYS).contains(&days) {
+        return Err(format!(
+            "Warning days must be between 0 and {}",
+            MAX_WARNING_DAYS
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn origin_key(origin_country: &str) -> String {
+    origin_country.trim().to_lowercase()
+}
+
+pub fn validate_rule(rule: &CropRule) -> Result<(), String> {
+    if !(1..=12).contains(&rule.harvest_month) {
+        return Err("Harvest month must be between 1 and 12".to_string());
+    }
+    if rule.past_crop_months == 0 || rule.past_crop_months > MAX_CROP_MONTHS {
+        return Err(format!(
+            "Crop life must be between 1 and {} months",
+            MAX_CROP_MONTHS
+        ));
+    }
+    Ok(())
+}
+
+/// The rule for lots from `origin_country`: its own if one is set, else the
+/// default.
+pub fn rule_for(settings: &CropAgeSettings, origin_country: &str) -> CropRule {
+    let origin = origin_key(origin_country);
+    settings
+        .origins
+        .iter()
+        .find(|entry| origin_key(&entry.origin_country) == origin)
+        .map(|entry| entry.rule)
+        .unwrap_or(settings.default_rule)
+}
+
+/// The crop age of `green` at `at`, or None if it has neither a harvest year
+/// nor an arrival date.
+pub fn crop_age_of(
+    settings: &CropAgeSettings,
+    green: &GreenCoffee,
+    at: DateTime<Utc>,
+) -> Option<CropAge> {
+    let green_id = green.id.clone()?;
+    let rule = rule_for(settings, &green.origin_country);
+    let crop_date = rule.crop_date(green.harvest_year, green.arrival_date)?;
+    let past_crop_date = rule.past_crop_date(crop_date)?;
+
+    let status = if at >= past_crop_date {
+        CropAgeStatus::PastCrop
+    } else if at + Duration::days(settings.warning_days) >= past_crop_date {
+        CropAgeStatus::NearingPastCrop
+    } else {
+        CropAgeStatus::Current
+    };
+
+    Some(CropAge {
+        green_coffee: green_id,
+        name: green.name.clone(),
+        origin_country: green.origin_country.clone(),
+        harvest_year: green.harvest_year,
+        arrival_date: green.arrival_date,
+        stock_grams: green.stock_grams,
+        crop_date,
+        age_days: (at - crop_date).num_days(),
+        past_crop_date,
+        days_until_past_crop: (past_crop_date - at).num_days(),
+        status,
+    })
+}
@@ -1,10 +1,19 @@
-use crate::error::ApiResult;
-use std::path::Path;
-use surrealdb::{Surreal, engine::any::Any};
+use crate::error::{ApiError, ApiResult};
+use std::path::{Path, PathBuf};
+use surrealdb::{Surreal, engine::any::Any, sql::Thing};
 use tokio::fs;
 
 pub type Db = Surreal<Any>;
 
+/// Where the server looks for migrations, relative to the workspace root.
+pub const MIGRATIONS_DIR: &str = "./coffee_shared/migrations";
+
+/// Turns a stored record link back into a `(table, id)` pair usable with
+/// `select`, `update` and `delete`.
+pub fn record_key(thing: &Thing) -> (String, String) {
+    (thing.tb.clone(), thing.id.to_raw())
+}
+
 #[cfg(not(feature = "test-db"))]
 pub async fn connect() -> ApiResult<Db> {
     use std::env;
@@ -36,17 +45,92 @@ pub async fn connect() -> ApiResult<Db> {
     Ok(db)
 }
 
-pub async fn apply_migrations(db: &Db) -> ApiResult<()> {
-    let migrations_path = Path::new("./coffee_shared/migrations");
-
-    let mut entries = fs::read_dir(migrations_path).await?;
+/// The `.surql` files in `dir`, in filename order. Later migrations redefine
+/// fields from earlier ones, so they must run in this order rather than
+/// whatever order the filesystem returns.
+pub async fn migration_files(dir: &Path) -> ApiResult<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    let mut entries = fs::read_dir(dir).await?;
     while let Some(entry) = entries.next_entry().await? {
         let path = entry.path();
         if path.is_file() && path.extension().is_some_and(|ext| ext == "surql") {
-            let query = fs::read_to_string(&path).await?;
-            db.query(query).await?;
-            println!("Applied migration: {}", path.display());
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Whether `err` only says a table, field, index or event is already
+/// defined. Databases set up before migrations were recorded already hold
+/// the definitions from the early migrations, which are applied once more
+/// the first time they start with the recording in place.
+fn already_defined(err: &surrealdb::Error) -> bool {
+    use surrealdb::error::Db;
+
+    matches!(
+        err,
+        surrealdb::Error::Db(
+            Db::TbAlreadyExists { .. }
+                | Db::FdAlreadyExists { .. }
+                | Db::IxAlreadyExists { .. }
+                | Db::EvAlreadyExists { .. }
+                | Db::AzAlreadyExists { .. }
+                | Db::FcAlreadyExists { .. }
+                | Db::PaAlreadyExists { .. }
+        )
+    )
+}
+
+/// Applies the migration at `path`, failing on any statement that errors
+/// for a reason other than its definition already existing.
+pub async fn apply_migration(db: &Db, path: &Path) -> ApiResult<()> {
+    let query = fs::read_to_string(path).await?;
+    let mut response = db.query(query).await?;
+
+    let mut errors: Vec<(usize, surrealdb::Error)> = response
+        .take_errors()
+        .into_iter()
+        .filter(|(_, err)| !already_defined(err))
+        .collect();
+    errors.sort_by_key(|(statement, _)| *statement);
+    if let Some((statement, err)) = errors.first() {
+        return Err(ApiError::Internal {
+            message: format!(
+                "Migration {} failed at statement {}: {}",
+                path.display(),
+                statement + 1,
+                err
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Applies every migration in `dir` that has not been applied yet, recording
+/// each in the `migration` table once it succeeds. Later migrations remove
+/// fields that earlier ones define, so running a migration a second time
+/// would check data against definitions it has long since outgrown.
+pub async fn apply_migrations(db: &Db, dir: &Path) -> ApiResult<()> {
+    let applied: Vec<String> = db
+        .query("SELECT VALUE meta::id(id) FROM migration")
+        .await?
+        .check()?
+        .take(0)?;
+
+    for path in migration_files(dir).await? {
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if applied.iter().any(|done| done == name) {
+            continue;
         }
+        apply_migration(db, &path).await?;
+        db.query("CREATE type::thing('migration', $name) SET applied_at = time::now()")
+            .bind(("name", name.to_string()))
+            .await?
+            .check()?;
+        println!("Applied migration: {}", path.display());
     }
     Ok(())
 }
@@ -1,21 +1,39 @@
+use crate::notifications::NotifyError;
+use crate::payments::PaymentError;
 use axum::{
     Json,
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use coffee_shared::MoneyError;
 use serde_json::json;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ApiError {
+    // Boxed because `surrealdb::Error` would otherwise make every `ApiResult`
+    // very large
     #[error("Database error: {0}")]
-    Database(#[from] surrealdb::Error),
+    Database(Box<surrealdb::Error>),
 
     #[error("Not found: {message}")]
     NotFound { message: String },
 
-    // #[error("Bad request: {message}")]
-    // BadRequest { message: String },
+    #[error("Bad request: {message}")]
+    BadRequest { message: String },
+
+    #[error("Conflict: {message}")]
+    Conflict { message: String },
+
+    #[error("Payment error: {0}")]
+    Payment(#[from] PaymentError),
+
+    #[error("Notification error: {0}")]
+    Notify(#[from] NotifyError),
+
+    #[error("Money error: {0}")]
+    Money(#[from] MoneyError),
+
     #[error("Internal server error: {message}")]
     Internal { message: String },
 
@@ -26,6 +44,12 @@ pub enum ApiError {
     Io(#[from] std::io::Error),
 }
 
+impl From<surrealdb::Error> for ApiError {
+    fn from(err: surrealdb::Error) -> Self {
+        ApiError::Database(Box::new(err))
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
@@ -37,7 +61,28 @@ impl IntoResponse for ApiError {
                 )
             }
             ApiError::NotFound { message } => (StatusCode::NOT_FOUND, message),
-            // ApiError::BadRequest { message } => (StatusCode::BAD_REQUEST, message),
+            ApiError::BadRequest { message } => (StatusCode::BAD_REQUEST, message),
+            ApiError::Conflict { message } => (StatusCode::CONFLICT, message),
+            ApiError::Payment(err) => {
+                eprintln!("Payment error: {:?}", err);
+                let status = match err {
+                    PaymentError::InvalidSignature | PaymentError::InvalidPayload(_) => {
+                        StatusCode::BAD_REQUEST
+                    }
+                    PaymentError::Declined(_) => StatusCode::PAYMENT_REQUIRED,
+                    PaymentError::UnknownReference(_) => StatusCode::NOT_FOUND,
+                    PaymentError::Provider(_) => StatusCode::BAD_GATEWAY,
+                };
+                (status, format!("Payment error: {}", err))
+            }
+            ApiError::Notify(err) => {
+                eprintln!("Notification error: {:?}", err);
+                (
+                    StatusCode::BAD_GATEWAY,
+                    format!("Notification error: {}", err),
+                )
+            }
+            ApiError::Money(err) => (StatusCode::BAD_REQUEST, err.to_string()),
             ApiError::Internal { message } => {
                 eprintln!("Internal error: {}", message);
                 (StatusCode::INTERNAL_SERVER_ERROR, message)
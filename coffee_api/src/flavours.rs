@@ -0,0 +1,349 @@
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{FLAVOUR_PATH_SEPARATOR, FlavourAlias, FlavourCategory, flavour_key};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Optional `?flavour=fruity > citrus` for lists that can be narrowed to
+/// coffees with a note in a flavour category.
+#[derive(Debug, Deserialize)]
+pub struct FlavourQuery {
+    pub flavour: Option<String>,
+}
+
+impl FlavourQuery {
+    /// The wheel and the category asked for, or `None` when not filtering.
+    pub async fn load(&self, db: &Db) -> ApiResult<Option<(FlavourWheel, String)>> {
+        let Some(flavour) = &self.flavour else {
+            return Ok(None);
+        };
+        let wheel = FlavourWheel::load(db).await?;
+        let category = wheel.resolve(flavour).ok_or_else(|| ApiError::BadRequest {
+            message: format!("Unknown flavour category '{}'", flavour),
+        })?;
+
+        Ok(Some((wheel, category)))
+    }
+}
+
+struct Node {
+    name: &'static str,
+    children: &'static [Node],
+}
+
+const fn leaf(name: &'static str) -> Node {
+    Node {
+        name,
+        children: &[],
+    }
+}
+
+const fn node(name: &'static str, children: &'static [Node]) -> Node {
+    Node { name, children }
+}
+
+/// The SCA coffee taster's flavour wheel, inner ring first.
+const WHEEL: &[Node] = &[
+    node(
+        "Fruity",
+        &[
+            node(
+                "Berry",
+                &[
+                    leaf("Blackberry"),
+                    leaf("Raspberry"),
+                    leaf("Blueberry"),
+                    leaf("Strawberry"),
+                ],
+            ),
+            node("Dried fruit", &[leaf("Raisin"), leaf("Prune")]),
+            node(
+                "Other fruit",
+                &[
+                    leaf("Coconut"),
+                    leaf("Cherry"),
+                    leaf("Pomegranate"),
+                    leaf("Pineapple"),
+                    leaf("Grape"),
+                    leaf("Apple"),
+                    leaf("Peach"),
+                    leaf("Pear"),
+                ],
+            ),
+            node(
+                "Citrus",
+                &[
+                    leaf("Grapefruit"),
+                    leaf("Orange"),
+                    leaf("Lemon"),
+                    leaf("Lime"),
+                ],
+            ),
+        ],
+    ),
+    node(
+        "Sour/Fermented",
+        &[
+            node(
+                "Sour",
+                &[
+                    leaf("Sour aromatics"),
+                    leaf("Acetic acid"),
+                    leaf("Butyric acid"),
+                    leaf("Isovaleric acid"),
+                    leaf("Citric acid"),
+                    leaf("Malic acid"),
+                ],
+            ),
+            node(
+                "Alcohol/Fermented",
+                &[
+                    leaf("Winey"),
+                    leaf("Whiskey"),
+                    leaf("Fermented"),
+                    leaf("Overripe"),
+                ],
+            ),
+        ],
+    ),
+    node(
+        "Green/Vegetative",
+        &[
+            leaf("Olive oil"),
+            leaf("Raw"),
+            node(
+                "Green/Vegetative",
+                &[
+                    leaf("Under-ripe"),
+                    leaf("Peapod"),
+                    leaf("Fresh"),
+                    leaf("Dark green"),
+                    leaf("Vegetative"),
+                    leaf("Hay-like"),
+                    leaf("Herb-like"),
+                ],
+            ),
+            leaf("Beany"),
+        ],
+    ),
+    node(
+        "Other",
+        &[
+            node(
+                "Papery/Musty",
+                &[
+                    leaf("Stale"),
+                    leaf("Cardboard"),
+                    leaf("Papery"),
+                    leaf("Woody"),
+                    leaf("Moldy/Damp"),
+                    leaf("Musty/Dusty"),
+                    leaf("Musty/Earthy"),
+                    leaf("Animalic"),
+                    leaf("Meaty brothy"),
+                    leaf("Phenolic"),
+                ],
+            ),
+            node(
+                "Chemical",
+                &[
+                    leaf("Bitter"),
+                    leaf("Salty"),
+                    leaf("Medicinal"),
+                    leaf("Petroleum"),
+                    leaf("Skunky"),
+                    leaf("Rubber"),
+                ],
+            ),
+        ],
+    ),
+    node(
+        "Roasted",
+        &[
+            leaf("Pipe tobacco"),
+            leaf("Tobacco"),
+            node(
+                "Burnt",
+                &[
+                    leaf("Acrid"),
+                    leaf("Ashy"),
+                    leaf("Smoky"),
+                    leaf("Brown, roast"),
+                ],
+            ),
+            node("Cereal", &[leaf("Grain"), leaf("Malt")]),
+        ],
+    ),
+    node(
+        "Spices",
+        &[
+            leaf("Pungent"),
+            leaf("Pepper"),
+            node(
+                "Brown spice",
+                &[
+                    leaf("Anise"),
+                    leaf("Nutmeg"),
+                    leaf("Cinnamon"),
+                    leaf("Clove"),
+                ],
+            ),
+        ],
+    ),
+    node(
+        "Nutty/Cocoa",
+        &[
+            node(
+                "Nutty",
+                &[leaf("Peanuts"), leaf("Hazelnut"), leaf("Almond")],
+            ),
+            node("Cocoa", &[leaf("Chocolate"), leaf("Dark chocolate")]),
+        ],
+    ),
+    node(
+        "Sweet",
+        &[
+            node(
+                "Brown sugar",
+                &[
+                    leaf("Molasses"),
+                    leaf("Maple syrup"),
+                    leaf("Caramelized"),
+                    leaf("Honey"),
+                ],
+            ),
+            leaf("Vanilla"),
+            leaf("Vanillin"),
+            leaf("Overall sweet"),
+            leaf("Sweet aromatics"),
+        ],
+    ),
+    node(
+        "Floral",
+        &[
+            leaf("Black tea"),
+            node(
+                "Floral",
+                &[leaf("Chamomile"), leaf("Rose"), leaf("Jasmine")],
+            ),
+        ],
+    ),
+];
+
+/// Endings tried when a note does not name a category outright, so
+/// "chocolatey" finds "chocolate".
+const SUFFIXES: &[&str] = &["y", "ey", "ish", "s", "es"];
+
+fn tree(nodes: &[Node], parent: Option<&str>) -> Vec<FlavourCategory> {
+    nodes
+        .iter()
+        .map(|node| {
+            let key = flavour_key(node.name);
+            let path = match parent {
+                Some(parent) => format!("{}{}{}", parent, FLAVOUR_PATH_SEPARATOR, key),
+                None => key,
+            };
+            FlavourCategory {
+                name: node.name.to_string(),
+                children: tree(node.children, Some(&path)),
+                path,
+            }
+        })
+        .collect()
+}
+
+/// The flavour wheel as a tree of categories.
+pub fn flavour_tree() -> Vec<FlavourCategory> {
+    tree(WHEEL, None)
+}
+
+/// Whether a note in category `path` belongs under `category`.
+pub fn within(path: &str, category: &str) -> bool {
+    path.strip_prefix(category)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(FLAVOUR_PATH_SEPARATOR))
+}
+
+/// The flavour wheel together with the stored aliases, for mapping free-text
+/// notes onto categories.
+pub struct FlavourWheel {
+    /// Every category path; a parent comes before its children.
+    paths: Vec<String>,
+    /// Category names to the shallowest category by that name.
+    names: HashMap<String, String>,
+    aliases: HashMap<String, String>,
+}
+
+impl FlavourWheel {
+    pub async fn load(db: &Db) -> ApiResult<Self> {
+        let aliases: Vec<FlavourAlias> = db.select("flavour_alias").await?;
+
+        Ok(Self::new(aliases))
+    }
+
+    pub fn new(aliases: Vec<FlavourAlias>) -> Self {
+        let mut levels = vec![flavour_tree()];
+        let mut paths = Vec::new();
+        let mut names = HashMap::new();
+        while let Some(level) = levels.pop() {
+            for category in level {
+                let name = flavour_key(&category.name);
+                let depth = category.path.matches(FLAVOUR_PATH_SEPARATOR).count();
+                let shallower = names.get(&name).is_some_and(|existing: &String| {
+                    existing.matches(FLAVOUR_PATH_SEPARATOR).count() <= depth
+                });
+                if !shallower {
+                    names.insert(name, category.path.clone());
+                }
+                paths.push(category.path);
+                levels.push(category.children);
+            }
+        }
+
+        let aliases = aliases
+            .into_iter()
+            .map(|alias| (flavour_key(&alias.alias), alias.category))
+            .collect();
+
+        Self {
+            paths,
+            names,
+            aliases,
+        }
+    }
+
+    /// The category path `query` refers to, accepting any capitalisation or
+    /// punctuation of the names along it.
+    pub fn resolve(&self, query: &str) -> Option<String> {
+        let path = query
+            .split('>')
+            .map(flavour_key)
+            .collect::<Vec<_>>()
+            .join(FLAVOUR_PATH_SEPARATOR);
+
+        self.paths.iter().find(|known| **known == path).cloned()
+    }
+
+    /// The category a tasting note belongs to: an alias first, then a
+    /// category of that name, then the name with a common ending removed.
+    pub fn categorise(&self, note: &str) -> Option<String> {
+        let key = flavour_key(note);
+        if let Some(category) = self.aliases.get(&key) {
+            return Some(category.clone());
+        }
+        if let Some(category) = self.names.get(&key) {
+            return Some(category.clone());
+        }
+        SUFFIXES
+            .iter()
+            .filter_map(|suffix| key.strip_suffix(suffix))
+            .find_map(|stem| self.names.get(stem).cloned())
+    }
+
+    /// Whether any of `notes` falls under `category`.
+    pub fn matches(&self, notes: &[String], category: &str) -> bool {
+        notes.iter().any(|note| {
+            self.categorise(note)
+                .is_some_and(|path| within(&path, category))
+        })
+    }
+}
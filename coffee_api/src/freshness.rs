@@ -0,0 +1,72 @@
+use crate::db::Db;
+use crate::error::ApiResult;
+use crate::models::{Freshness, FreshnessRule, FreshnessSettings, Product, Roast};
+use crate::routes::analytics::level_key;
+use chrono::{DateTime, Utc};
+
+/// Key of the single freshness settings record.
+pub const FRESHNESS_SETTINGS_KEY: (&str, &str) = ("freshness_settings", "current");
+
+/// Longest shelf life or look-ahead accepted, in days. Dates are worked out
+/// by adding days to a roast date, which must stay within chrono's range.
+pub const MAX_FRESHNESS_DAYS: i64 = 3650;
+
+/// The stored freshness settings, or the defaults if none have been saved.
+pub async fn load_freshness_settings(db: &Db) -> ApiResult<FreshnessSettings> {
+    let settings: Option<FreshnessSettings> = db.select(FRESHNESS_SETTINGS_KEY).await?;
+
+    Ok(settings.unwrap_or_default())
+}
+
+pub fn validate_rule(rule: &FreshnessRule) -> Result<(), String> {
+    if rule.rest_days < 0 {
+        return Err("Rest period cannot be negative".to_string());
+    }
+    if rule.peak_days < rule.rest_days {
+        return Err("The peak cannot end before the rest period does".to_string());
+    }
+    if rule.shelf_life_days <= 0 || rule.shelf_life_days < rule.peak_days {
+        return Err(
+            "Shelf life must be positive and last at least to the end of the peak".to_string(),
+        );
+    }
+    if rule.shelf_life_days > MAX_FRESHNESS_DAYS {
+        return Err(format!(
+            "Shelf life cannot be more than {} days",
+            MAX_FRESHNESS_DAYS
+        ));
+    }
+    Ok(())
+}
+
+/// The rule for `roast`, packed as `product` if given: the product's own
+/// rule, else its roast level's, else the default.
+pub fn rule_for(
+    settings: &FreshnessSettings,
+    product: Option<&Product>,
+    roast: &Roast,
+) -> FreshnessRule {
+    if let Some(rule) = product.and_then(|product| product.freshness) {
+        return rule;
+    }
+    let level = level_key(&roast.roast_level);
+    settings
+        .roast_levels
+        .iter()
+        .find(|entry| level_key(&entry.roast_level) == level)
+        .map(|entry| entry.rule)
+        .unwrap_or(settings.default_rule)
+}
+
+/// The freshness of `roast` at `at`, or None if it has no roast date.
+pub fn freshness_of(
+    settings: &FreshnessSettings,
+    product: Option<&Product>,
+    roast: &Roast,
+    at: DateTime<Utc>,
+) -> Option<Freshness> {
+    let roast_id = roast.id.clone()?;
+    let roast_date = roast.date_roasted?;
+
+    Some(rule_for(settings, product, roast).freshness(roast_id, roast_date, at))
+}
@@ -0,0 +1,69 @@
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::FxRate;
+use coffee_shared::{Currency, Money};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+/// Optional `?currency=XXX` for endpoints that can show prices converted into
+/// another currency.
+#[derive(Debug, Deserialize)]
+pub struct CurrencyQuery {
+    pub currency: Option<Currency>,
+}
+
+/// Exchange rates loaded from the `fx_rate` table. Only the most recent rate
+/// for each pair is used.
+pub struct FxTable {
+    rates: Vec<FxRate>,
+}
+
+impl FxTable {
+    pub async fn load(db: &Db) -> ApiResult<Self> {
+        let mut response = db
+            .query("SELECT * FROM fx_rate ORDER BY effective_at DESC")
+            .await?;
+        let rates: Vec<FxRate> = response.take(0)?;
+
+        Ok(Self { rates })
+    }
+
+    /// Units of `to` bought by one unit of `from`, using the inverse of a
+    /// stored `to`/`from` rate when there is no direct one.
+    pub fn rate(&self, from: Currency, to: Currency) -> Option<Decimal> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+
+        let latest = |base: Currency, quote: Currency| {
+            self.rates
+                .iter()
+                .filter(|r| r.base == base && r.quote == quote)
+                .max_by_key(|r| r.effective_at)
+        };
+        match (latest(from, to), latest(to, from)) {
+            (Some(direct), Some(inverse)) if inverse.effective_at > direct.effective_at => {
+                Decimal::ONE.checked_div(inverse.rate)
+            }
+            (Some(direct), _) => Some(direct.rate),
+            (None, Some(inverse)) => Decimal::ONE.checked_div(inverse.rate),
+            (None, None) => None,
+        }
+    }
+
+    /// Converts `money` into `to`, rounded to the target currency's minor unit.
+    pub fn convert(&self, money: &Money, to: Currency) -> ApiResult<Money> {
+        if money.currency == to {
+            return Ok(*money);
+        }
+
+        let rate = self
+            .rate(money.currency, to)
+            .ok_or_else(|| ApiError::BadRequest {
+                message: format!("No exchange rate from {} to {}", money.currency, to),
+            })?;
+
+        let converted = money.times(rate)?;
+        Ok(Money::new(converted.amount, to).rounded())
+    }
+}
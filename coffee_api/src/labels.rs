@@ -0,0 +1,477 @@
+//! Bag labels: what goes on them, and layouts as PDF pages or ZPL for
+//! thermal printers.
+
+use crate::barcode::ean13_modules;
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::freshness::rule_for;
+use crate::models::{
+    BagLabel, FreshnessSettings, GreenCoffee, Grind, LabelItem, LabelTemplate, Product, Roast,
+};
+use crate::routes::products::find_variant;
+use chrono::Duration;
+use std::fmt::Write;
+
+const PLACEHOLDERS: [&str; 10] = [
+    "name",
+    "origin",
+    "process",
+    "notes",
+    "net_weight",
+    "grind",
+    "roast_level",
+    "roast_date",
+    "best_before",
+    "sku",
+];
+
+const MARGIN_MM: f64 = 4.0;
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+/// The first line, normally the coffee's name, is printed larger.
+const TITLE_SIZE_PT: f64 = 12.0;
+const TEXT_SIZE_PT: f64 = 8.0;
+const LINE_SPACING: f64 = 1.25;
+/// Rough average glyph width as a share of the font size, for wrapping.
+const GLYPH_WIDTH: f64 = 0.5;
+/// EAN-13 modules at their nominal width, with the bars cut shorter than
+/// nominal to fit small labels.
+const MODULE_MM: f64 = 0.33;
+const BARCODE_HEIGHT_MM: f64 = 15.0;
+
+/// Most bags one item of a print run may ask labels for.
+pub const MAX_LABELS_PER_ITEM: i32 = 500;
+/// Most labels one print run may produce.
+pub const MAX_LABELS_PER_RUN: i64 = 2000;
+
+/// Placeholders in `line`, without their braces.
+fn placeholders(line: &str) -> Result<Vec<&str>, String> {
+    let mut found = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            return Err(format!("Label line '{}' has an unclosed '{{'", line));
+        };
+        found.push(&rest[start + 1..start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    Ok(found)
+}
+
+pub fn validate_template(template: &LabelTemplate) -> Result<(), String> {
+    if [template.width_mm, template.height_mm]
+        .iter()
+        .any(|mm| !mm.is_finite() || *mm <= 0.0)
+    {
+        return Err("Label width and height must be positive".to_string());
+    }
+    if !(100..=600).contains(&template.dpi) {
+        return Err("Printer dpi must be between 100 and 600".to_string());
+    }
+    if template.lines.is_empty() {
+        return Err("Label template needs at least one line".to_string());
+    }
+    for line in &template.lines {
+        if let Some(unknown) = placeholders(line)?
+            .into_iter()
+            .find(|placeholder| !PLACEHOLDERS.contains(placeholder))
+        {
+            return Err(format!(
+                "Label line '{}' has unknown placeholder '{{{}}}'",
+                line, unknown
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn grind_text(grind: Grind) -> &'static str {
+    match grind {
+        Grind::WholeBean => "Whole bean",
+        Grind::Espresso => "Ground for espresso",
+        Grind::Filter => "Ground for filter",
+        Grind::Cafetiere => "Ground for cafetière",
+    }
+}
+
+fn net_weight_text(grams: f64) -> String {
+    if grams >= 1000.0 {
+        format!("{} kg", grams / 1000.0)
+    } else {
+        format!("{} g", grams)
+    }
+}
+
+/// Values in the order they first appear, without repeats.
+fn distinct(values: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut distinct: Vec<String> = Vec::new();
+    for value in values {
+        if !distinct.contains(&value) {
+            distinct.push(value);
+        }
+    }
+    distinct
+}
+
+/// Fills in `template`'s lines for `label`, leaving out lines whose
+/// placeholders all came out empty.
+fn fill_lines(template: &LabelTemplate, label: &BagLabel, roast_level: &str) -> Vec<String> {
+    let values = [
+        ("name", label.name.clone()),
+        ("origin", label.origin.clone()),
+        ("process", label.process.clone().unwrap_or_default()),
+        ("notes", label.tasting_notes.join(", ")),
+        ("net_weight", net_weight_text(label.net_weight_grams)),
+        ("grind", grind_text(label.grind).to_string()),
+        ("roast_level", roast_level.to_string()),
+        (
+            "roast_date",
+            label.roast_date.format("%d %b %Y").to_string(),
+        ),
+        (
+            "best_before",
+            label.best_before.format("%d %b %Y").to_string(),
+        ),
+        ("sku", label.sku.clone().unwrap_or_default()),
+    ];
+
+    template
+        .lines
+        .iter()
+        .filter_map(|line| {
+            let mut filled = line.clone();
+            let mut used = 0;
+            let mut empty = 0;
+            for (placeholder, value) in &values {
+                let pattern = format!("{{{}}}", placeholder);
+                if filled.contains(&pattern) {
+                    used += 1;
+                    if value.is_empty() {
+                        empty += 1;
+                    }
+                    filled = filled.replace(&pattern, value);
+                }
+            }
+            (used == 0 || empty < used).then(|| filled.trim().to_string())
+        })
+        .collect()
+}
+
+/// Gathers what goes on the bags of `item` from its product, the roast they
+/// were packed from and the greens in that roast. The best-before date
+/// follows the product's shelf life.
+pub async fn build_label(
+    db: &Db,
+    template: &LabelTemplate,
+    freshness: &FreshnessSettings,
+    item: &LabelItem,
+) -> ApiResult<BagLabel> {
+    if item.quantity <= 0 || item.quantity > MAX_LABELS_PER_ITEM {
+        return Err(ApiError::BadRequest {
+            message: format!(
+                "Label quantity must be between 1 and {}",
+                MAX_LABELS_PER_ITEM
+            ),
+        });
+    }
+    let product: Option<Product> = db.select(record_key(&item.product)).await?;
+    let product = product.ok_or_else(|| ApiError::NotFound {
+        message: format!("Product '{}' not found", item.product),
+    })?;
+    let variant = find_variant(&product, item.variant.as_deref())?;
+
+    let roast_id = item
+        .roast
+        .as_ref()
+        .or(product.roast.as_ref())
+        .ok_or_else(|| ApiError::BadRequest {
+            message: format!("Product '{}' has no roast to label", item.product),
+        })?;
+    let roast: Option<Roast> = db.select(record_key(roast_id)).await?;
+    let roast = roast.ok_or_else(|| ApiError::NotFound {
+        message: format!("Roast '{}' not found", roast_id),
+    })?;
+    let roast_date = roast.date_roasted.ok_or_else(|| ApiError::BadRequest {
+        message: format!("Roast '{}' has no roast date to print", roast_id),
+    })?;
+
+    let mut green_ids = Vec::new();
+    for green_id in roast.greens() {
+        if !green_ids.contains(&green_id) {
+            green_ids.push(green_id);
+        }
+    }
+    let mut greens = Vec::new();
+    for green_id in green_ids {
+        let green: Option<GreenCoffee> = db.select(record_key(green_id)).await?;
+        greens.extend(green);
+    }
+    let origin = match greens.as_slice() {
+        [green] => match &green.region {
+            Some(region) => format!("{}, {}", green.origin_country, region),
+            None => green.origin_country.clone(),
+        },
+        _ => distinct(greens.iter().map(|green| green.origin_country.clone())).join(" / "),
+    };
+    let processes = distinct(
+        greens
+            .iter()
+            .filter_map(|green| green.processing_method.clone()),
+    );
+    let tasting_notes = match roast.notes.as_ref().filter(|notes| !notes.is_empty()) {
+        Some(notes) => notes.clone(),
+        None => distinct(
+            greens
+                .iter()
+                .flat_map(|green| green.cupping_notes.iter().flatten().cloned()),
+        ),
+    };
+
+    let mut label = BagLabel {
+        product: item.product.clone(),
+        variant: variant.key.clone(),
+        roast: roast_id.clone(),
+        name: product.name.clone(),
+        origin,
+        process: (!processes.is_empty()).then(|| processes.join(" / ")),
+        tasting_notes,
+        net_weight_grams: variant.package_size_grams,
+        grind: variant.grind,
+        roast_date,
+        best_before: roast_date
+            + Duration::days(rule_for(freshness, Some(&product), &roast).shelf_life_days),
+        sku: variant.sku.clone(),
+        gtin: variant.gtin.clone(),
+        lines: Vec::new(),
+        quantity: item.quantity,
+    };
+    label.lines = fill_lines(template, &label, &roast.roast_level);
+    Ok(label)
+}
+
+/// Splits `text` into lines of at most `width` characters, at spaces where
+/// possible.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Each printed line of `label` with its font size in points.
+fn layout(template: &LabelTemplate, label: &BagLabel) -> Vec<(f64, String)> {
+    let text_width_pt = (template.width_mm - 2.0 * MARGIN_MM) * POINTS_PER_MM;
+    label
+        .lines
+        .iter()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            let size = if i == 0 { TITLE_SIZE_PT } else { TEXT_SIZE_PT };
+            let width = (text_width_pt / (size * GLYPH_WIDTH)).max(1.0) as usize;
+            wrap(line, width).into_iter().map(move |line| (size, line))
+        })
+        .collect()
+}
+
+/// Escapes `text` for a PDF string in WinAnsi encoding, which matches
+/// Latin-1 for the accented letters coffee names use.
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len() + 2);
+    bytes.push(b'(');
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => bytes.extend([b'\\', c as u8]),
+            c if (c as u32) < 0x100 => bytes.push(c as u8),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes.push(b')');
+    bytes
+}
+
+fn pdf_page(template: &LabelTemplate, label: &BagLabel) -> Vec<u8> {
+    let height_pt = template.height_mm * POINTS_PER_MM;
+    let margin_pt = MARGIN_MM * POINTS_PER_MM;
+    let mut content = Vec::new();
+
+    let mut y = height_pt - margin_pt;
+    for (size, line) in layout(template, label) {
+        y -= size * LINE_SPACING;
+        let font = if size == TITLE_SIZE_PT { "F2" } else { "F1" };
+        content.extend(format!("BT /{} {} Tf {:.2} {:.2} Td ", font, size, margin_pt, y).bytes());
+        content.extend(pdf_string(&line));
+        content.extend(b" Tj ET\n");
+    }
+
+    // The barcode sits in the bottom right corner, its digits underneath
+    if template.show_barcode
+        && let Some(gtin) = &label.gtin
+    {
+        let module_pt = MODULE_MM * POINTS_PER_MM;
+        let bars_height_pt = BARCODE_HEIGHT_MM * POINTS_PER_MM;
+        let left = template.width_mm * POINTS_PER_MM - margin_pt - 95.0 * module_pt;
+        let bottom = margin_pt + TEXT_SIZE_PT;
+        for (index, _) in ean13_modules(gtin)
+            .iter()
+            .enumerate()
+            .filter(|(_, bar)| **bar)
+        {
+            content.extend(
+                format!(
+                    "{:.2} {:.2} {:.2} {:.2} re f\n",
+                    left + index as f64 * module_pt,
+                    bottom,
+                    module_pt,
+                    bars_height_pt
+                )
+                .bytes(),
+            );
+        }
+        content.extend(
+            format!(
+                "BT /F1 {} Tf {:.2} {:.2} Td ",
+                TEXT_SIZE_PT, left, margin_pt
+            )
+            .bytes(),
+        );
+        content.extend(pdf_string(gtin));
+        content.extend(b" Tj ET\n");
+    }
+    content
+}
+
+/// A PDF with one page per bag, each the size of the label. Bags with the
+/// same label share one content stream.
+pub fn render_pdf(template: &LabelTemplate, labels: &[BagLabel]) -> Vec<u8> {
+    let pages: Vec<usize> = labels
+        .iter()
+        .enumerate()
+        .flat_map(|(i, label)| std::iter::repeat_n(i, label.quantity.max(0) as usize))
+        .collect();
+
+    // Objects 1-4 are the catalog, page tree and fonts, then the content
+    // stream of each label and last the pages
+    let content_id = |label: usize| 5 + label;
+    let page_id = |i: usize| 5 + labels.len() + i;
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", page_id(i)))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    ];
+    for label in labels {
+        let content = pdf_page(template, label);
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+    for label in pages {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                template.width_mm * POINTS_PER_MM,
+                template.height_mm * POINTS_PER_MM,
+                content_id(label)
+            )
+            .into_bytes(),
+        );
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(trailer, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.extend(trailer.bytes());
+    pdf
+}
+
+/// ZPL for a thermal printer, one format per label variant, each printed as
+/// many times as there are bags.
+pub fn render_zpl(template: &LabelTemplate, labels: &[BagLabel]) -> String {
+    let dots_per_mm = f64::from(template.dpi) / 25.4;
+    let dots = |mm: f64| (mm * dots_per_mm).round() as i64;
+    let points_to_dots = |pt: f64| dots(pt / POINTS_PER_MM);
+    let margin = dots(MARGIN_MM);
+
+    let mut zpl = String::new();
+    for label in labels {
+        let _ = write!(
+            zpl,
+            "^XA^CI28^PW{}^LL{}",
+            dots(template.width_mm),
+            dots(template.height_mm)
+        );
+        let mut y = margin;
+        for (size, line) in layout(template, label) {
+            let height = points_to_dots(size);
+            // ^ and ~ start commands, so they cannot appear in field data
+            let text: String = line
+                .chars()
+                .map(|c| if c == '^' || c == '~' { ' ' } else { c })
+                .collect();
+            let _ = write!(
+                zpl,
+                "^FO{},{}^A0N,{},{}^FD{}^FS",
+                margin, y, height, height, text
+            );
+            y += points_to_dots(size * LINE_SPACING);
+        }
+        if template.show_barcode
+            && let Some(gtin) = &label.gtin
+        {
+            let module = dots(MODULE_MM).max(1);
+            let bars_height = dots(BARCODE_HEIGHT_MM);
+            let x = dots(template.width_mm) - margin - 95 * module;
+            let y = dots(template.height_mm) - margin - bars_height - points_to_dots(TEXT_SIZE_PT);
+            // ^BE adds the check digit itself
+            let _ = write!(
+                zpl,
+                "^FO{},{}^BY{}^BEN,{},Y,N^FD{}^FS",
+                x,
+                y,
+                module,
+                bars_height,
+                &gtin[..12]
+            );
+        }
+        let _ = writeln!(zpl, "^PQ{}^XZ", label.quantity);
+    }
+    zpl
+}
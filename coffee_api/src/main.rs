@@ -1,14 +1,36 @@
+mod artisan;
+mod barcode;
+mod certifications;
+mod costing;
+mod crop_age;
 mod db;
 mod error;
+mod flavours;
+mod freshness;
+mod fx;
+mod labels;
+mod notifications;
+mod payments;
+mod pricing;
+mod profile_comparison;
 mod routes;
+mod scheduler;
+mod sku;
+mod state;
 #[cfg(test)]
 mod tests;
+mod trace;
 
 use coffee_shared::models;
 use routes::*;
+use state::AppState;
 
-use axum::{Router, routing::get};
+use axum::{
+    Router,
+    routing::{delete, get, post, put},
+};
 use std::env;
+use std::path::Path;
 use tower_http::cors::CorsLayer;
 
 #[tokio::main]
@@ -19,7 +41,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         e
     })?;
 
-    db::apply_migrations(&db).await?;
+    db::apply_migrations(&db, Path::new(db::MIGRATIONS_DIR)).await?;
+
+    let payments = payments::from_env()?;
+    let notifier = notifications::from_env()?;
+
+    scheduler::spawn(db.clone(), notifier.clone());
 
     // Get port from environment or default to 8080
     let port = env::var("PORT")
@@ -35,18 +62,172 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "/greens/{id}",
             get(get_green).put(update_green).delete(delete_green),
         )
+        .route("/greens/quality-warnings", get(list_quality_warnings))
+        .route("/greens/past-crop", get(list_past_crop_greens))
+        .route("/greens/use-first", get(list_use_first_greens))
+        .route("/greens/{id}/crop-age", get(get_green_crop_age))
+        .route("/greens/{id}/cupping-scores", get(get_green_cupping_scores))
+        .route(
+            "/greens/{id}/measurements",
+            get(list_green_measurements).post(create_green_measurement),
+        )
+        .route(
+            "/quality-ranges",
+            get(get_quality_ranges).put(update_quality_ranges),
+        )
+        .route(
+            "/crop-age-settings",
+            get(get_crop_age_settings).put(update_crop_age_settings),
+        )
+        .route("/suppliers", get(list_suppliers).post(create_supplier))
+        .route("/suppliers/migrate", post(migrate_legacy_suppliers))
+        .route(
+            "/suppliers/{id}",
+            get(get_supplier)
+                .put(update_supplier)
+                .delete(delete_supplier),
+        )
+        .route(
+            "/purchase-orders",
+            get(list_purchase_orders).post(create_purchase_order),
+        )
+        .route("/purchase-orders/outstanding", get(list_outstanding_green))
+        .route("/purchase-orders/{id}", get(get_purchase_order))
+        .route(
+            "/purchase-orders/{id}/receipts",
+            post(receive_purchase_order),
+        )
+        .route("/purchase-orders/{id}/cancel", post(cancel_purchase_order))
         .route("/roasts", get(list_roasts).post(create_roast))
         .route(
             "/roasts/{id}",
             get(get_roast).put(update_roast).delete(delete_roast),
         )
+        .route("/roasts/{id}/cost", get(get_roast_cost))
+        .route("/roasts/{id}/cupping-scores", get(get_roast_cupping_scores))
+        .route(
+            "/roasts/{id}/profile",
+            get(get_roast_profile).post(import_roast_profile),
+        )
+        .route("/roasts/{id}/profile/compare", post(compare_roast_profile))
+        .route("/analytics/roast-loss", get(roast_loss_report))
+        .route("/flavours", get(list_flavours))
+        .route("/flavours/notes", get(list_flavour_notes))
+        .route(
+            "/flavour-aliases",
+            get(list_flavour_aliases).post(create_flavour_alias),
+        )
+        .route("/flavour-aliases/{id}", delete(delete_flavour_alias))
+        .route(
+            "/cupping-sessions",
+            get(list_cupping_sessions).post(create_cupping_session),
+        )
+        .route(
+            "/cupping-sessions/{id}",
+            get(get_cupping_session).delete(delete_cupping_session),
+        )
+        .route("/cupping-sessions/{id}/forms", post(submit_cupping_form))
         .route("/products", get(list_products).post(create_product))
         .route(
             "/products/{id}",
             get(get_product).put(update_product).delete(delete_product),
         )
+        .route("/products/by-barcode/{code}", get(get_product_by_barcode))
+        .route("/products/{id}/variants", post(create_product_variant))
+        .route(
+            "/products/{id}/variants/{variant}",
+            put(update_product_variant).delete(delete_product_variant),
+        )
+        .route(
+            "/products/{id}/variants/{variant}/barcode",
+            get(get_variant_barcode),
+        )
+        .route(
+            "/sku-settings",
+            get(get_sku_settings).put(update_sku_settings),
+        )
+        .route("/products/{id}/cost", get(get_product_cost))
+        .route("/products/margins", get(list_product_margins))
+        .route(
+            "/cost-settings",
+            get(get_cost_settings).put(update_cost_settings),
+        )
+        .route("/customers", get(list_customers).post(create_customer))
+        .route(
+            "/customers/{id}",
+            get(get_customer)
+                .put(update_customer)
+                .delete(delete_customer),
+        )
+        .route("/customers/{id}/orders", get(list_customer_orders))
+        .route("/customers/{id}/export", get(export_customer))
+        .route("/customers/{id}/erase", post(erase_customer))
+        .route(
+            "/price-lists",
+            get(list_price_lists).post(create_price_list),
+        )
+        .route(
+            "/price-lists/{id}",
+            get(get_price_list)
+                .put(update_price_list)
+                .delete(delete_price_list),
+        )
+        .route("/price-quote", post(quote_price))
+        .route("/fx-rates", get(list_fx_rates).post(create_fx_rate))
+        .route("/fx-rates/import", post(import_fx_rates))
+        .route(
+            "/subscriptions",
+            get(list_subscriptions).post(create_subscription),
+        )
+        .route("/subscriptions/forecast", get(subscription_forecast))
+        .route("/subscriptions/run", post(run_subscriptions))
+        .route(
+            "/subscriptions/{id}",
+            get(get_subscription).put(update_subscription),
+        )
+        .route("/subscriptions/{id}/pause", post(pause_subscription))
+        .route("/subscriptions/{id}/resume", post(resume_subscription))
+        .route("/subscriptions/{id}/skip", post(skip_subscription))
+        .route("/subscriptions/{id}/cancel", post(cancel_subscription))
+        .route("/orders", get(list_orders).post(create_order))
+        .route("/orders/{id}", get(get_order))
+        .route("/orders/{id}/pay", post(pay_order))
+        .route("/orders/{id}/refund", post(refund_order))
+        .route("/orders/{id}/status", put(update_order_status))
+        .route("/pick-list", get(get_pick_list))
+        .route("/pick-list/labels", get(print_pick_list_labels))
+        .route(
+            "/freshness-settings",
+            get(get_freshness_settings).put(update_freshness_settings),
+        )
+        .route("/freshness/fifo", get(get_fifo_recommendations))
+        .route("/freshness/expiring", get(get_expiring_stock))
+        .route("/roasts/{id}/freshness", get(get_roast_freshness))
+        .route("/products/{id}/freshness", get(get_product_freshness))
+        .route("/labels", post(print_labels))
+        .route(
+            "/label-templates",
+            get(list_label_templates).post(create_label_template),
+        )
+        .route(
+            "/label-templates/{id}",
+            get(get_label_template)
+                .put(update_label_template)
+                .delete(delete_label_template),
+        )
+        .route("/production-plan", get(get_production_plan))
+        .route("/alerts", get(list_alerts))
+        .route("/alerts/notify", post(notify_alerts))
+        .route("/trace/{record}", get(get_trace))
+        .route("/inventory-movements", get(list_inventory_movements))
+        .route("/payments", get(list_payments))
+        .route("/payments/webhook", post(payment_webhook))
         .layer(CorsLayer::permissive())
-        .with_state(db);
+        .with_state(AppState {
+            db,
+            payments,
+            notifier,
+        });
 
     // Run our app with hyper
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
@@ -0,0 +1,20 @@
+use super::{Notifier, NotifyError, describe};
+use crate::models::StockAlert;
+use async_trait::async_trait;
+
+/// Writes alerts to the server log.
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    fn name(&self) -> &'static str {
+        "log"
+    }
+
+    async fn notify(&self, alerts: &[StockAlert]) -> Result<(), NotifyError> {
+        for alert in alerts {
+            println!("Low stock: {}", describe(alert));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+pub mod log;
+#[cfg(feature = "smtp")]
+pub mod smtp;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+pub use log::LogNotifier;
+#[cfg(feature = "smtp")]
+pub use smtp::SmtpNotifier;
+#[cfg(feature = "webhook")]
+pub use webhook::WebhookNotifier;
+
+use crate::models::{StockAlert, StockItemKind};
+use async_trait::async_trait;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum NotifyError {
+    #[error("notifier is misconfigured: {0}")]
+    Config(String),
+
+    #[error("delivery failed: {0}")]
+    Delivery(String),
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Short identifier for logs, e.g. `"log"`.
+    fn name(&self) -> &'static str;
+
+    /// Tells staff about every alert in `alerts`. Never called with an
+    /// empty list.
+    async fn notify(&self, alerts: &[StockAlert]) -> Result<(), NotifyError>;
+}
+
+/// One line per alert, shared by the plain text notifiers.
+pub fn describe(alert: &StockAlert) -> String {
+    let unit = match alert.kind {
+        StockItemKind::GreenCoffee => "g",
+        StockItemKind::Product => " units",
+    };
+    let cover = match alert.days_of_cover {
+        Some(days) => format!("{:.1} days of cover", days),
+        None => "no recent use".to_string(),
+    };
+
+    format!(
+        "{} ({}): {}{} left, reorder at {}{}, {}",
+        alert.name, alert.item, alert.stock, unit, alert.reorder_point, unit, cover
+    )
+}
+
+/// Picks the notifier from `ALERT_NOTIFIER`, falling back to logging so
+/// alerts are never silently dropped.
+pub fn from_env() -> Result<Arc<dyn Notifier>, NotifyError> {
+    let notifier = std::env::var("ALERT_NOTIFIER").unwrap_or_else(|_| "log".to_string());
+
+    match notifier.as_str() {
+        "log" => Ok(Arc::new(LogNotifier)),
+        #[cfg(feature = "smtp")]
+        "smtp" => Ok(Arc::new(SmtpNotifier::from_env()?)),
+        #[cfg(feature = "webhook")]
+        "webhook" => Ok(Arc::new(WebhookNotifier::from_env()?)),
+        other => Err(NotifyError::Config(format!(
+            "unsupported notifier '{}'",
+            other
+        ))),
+    }
+}
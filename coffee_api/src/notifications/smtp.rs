@@ -0,0 +1,71 @@
+use super::{Notifier, NotifyError, describe};
+use crate::models::StockAlert;
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+/// Emails alerts through an SMTP relay. The relay is expected to be local,
+/// so the connection is neither encrypted nor authenticated.
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Mailbox,
+}
+
+fn mailbox(var: &str) -> Result<Mailbox, NotifyError> {
+    std::env::var(var)
+        .map_err(|_| NotifyError::Config(format!("{} is not set", var)))?
+        .parse()
+        .map_err(|e| NotifyError::Config(format!("{} is not a valid address: {}", var, e)))
+}
+
+impl SmtpNotifier {
+    pub fn new(host: &str, port: u16, from: Mailbox, to: Mailbox) -> Self {
+        Self {
+            transport: AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+                .port(port)
+                .build(),
+            from,
+            to,
+        }
+    }
+
+    /// Reads `SMTP_HOST` and `SMTP_PORT` (default `localhost:25`) and the
+    /// `ALERT_EMAIL_FROM` and `ALERT_EMAIL_TO` addresses.
+    pub fn from_env() -> Result<Self, NotifyError> {
+        let host = std::env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string());
+        let port = std::env::var("SMTP_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(25);
+        Ok(Self::new(
+            &host,
+            port,
+            mailbox("ALERT_EMAIL_FROM")?,
+            mailbox("ALERT_EMAIL_TO")?,
+        ))
+    }
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+
+    async fn notify(&self, alerts: &[StockAlert]) -> Result<(), NotifyError> {
+        let body: Vec<String> = alerts.iter().map(describe).collect();
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(format!("{} item(s) below reorder point", alerts.len()))
+            .body(body.join("\n"))
+            .map_err(|e| NotifyError::Delivery(e.to_string()))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| NotifyError::Delivery(e.to_string()))?;
+        Ok(())
+    }
+}
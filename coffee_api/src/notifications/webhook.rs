@@ -0,0 +1,59 @@
+use super::{Notifier, NotifyError};
+use crate::models::StockAlert;
+use async_trait::async_trait;
+use serde_json::json;
+use std::time::Duration;
+
+/// Posts alerts as JSON, `{"alerts": [...]}`, to a configured URL.
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String,
+}
+
+/// How long a delivery may take before it is abandoned, so a hung endpoint
+/// cannot stall the alert loop.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>) -> Result<Self, NotifyError> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| NotifyError::Config(e.to_string()))?;
+        Ok(Self {
+            client,
+            url: url.into(),
+        })
+    }
+
+    pub fn from_env() -> Result<Self, NotifyError> {
+        let url = std::env::var("ALERT_WEBHOOK_URL")
+            .map_err(|_| NotifyError::Config("ALERT_WEBHOOK_URL is not set".to_string()))?;
+        Self::new(url)
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn notify(&self, alerts: &[StockAlert]) -> Result<(), NotifyError> {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "alerts": alerts }))
+            .send()
+            .await
+            .map_err(|e| NotifyError::Delivery(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(NotifyError::Delivery(format!(
+                "webhook responded with {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use super::{PaymentError, PaymentEvent, PaymentProvider};
+use crate::models::PaymentStatus;
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use coffee_shared::Money;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const SIGNATURE_HEADER: &str = "x-fake-signature";
+
+#[derive(Debug)]
+struct FakeCharge {
+    amount: Money,
+    captured: bool,
+    refunded: Money,
+}
+
+/// In-process payment provider for development and tests. Every positive
+/// amount is authorized, and webhooks are accepted when the
+/// `x-fake-signature` header matches the configured secret.
+pub struct FakePaymentProvider {
+    webhook_secret: String,
+    next_id: AtomicU64,
+    charges: Mutex<HashMap<String, FakeCharge>>,
+}
+
+impl FakePaymentProvider {
+    pub fn new(webhook_secret: impl Into<String>) -> Self {
+        Self {
+            webhook_secret: webhook_secret.into(),
+            next_id: AtomicU64::new(1),
+            charges: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("FAKE_PAYMENT_WEBHOOK_SECRET").unwrap_or_else(|_| "fake".to_string()),
+        )
+    }
+}
+
+#[async_trait]
+impl PaymentProvider for FakePaymentProvider {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    async fn authorize(
+        &self,
+        amount: &Money,
+        _order_reference: &str,
+    ) -> Result<PaymentEvent, PaymentError> {
+        if amount.is_negative() || amount.amount.is_zero() {
+            return Err(PaymentError::Declined(format!(
+                "amount {} must be positive",
+                amount
+            )));
+        }
+
+        let reference = format!("fake_{}", self.next_id.fetch_add(1, Ordering::SeqCst));
+        self.charges.lock().unwrap().insert(
+            reference.clone(),
+            FakeCharge {
+                amount: *amount,
+                captured: false,
+                refunded: Money::zero(amount.currency),
+            },
+        );
+
+        Ok(PaymentEvent {
+            reference,
+            status: PaymentStatus::Authorized,
+        })
+    }
+
+    async fn capture(&self, reference: &str) -> Result<PaymentEvent, PaymentError> {
+        let mut charges = self.charges.lock().unwrap();
+        let charge = charges
+            .get_mut(reference)
+            .ok_or_else(|| PaymentError::UnknownReference(reference.to_string()))?;
+        charge.captured = true;
+
+        Ok(PaymentEvent {
+            reference: reference.to_string(),
+            status: PaymentStatus::Captured,
+        })
+    }
+
+    async fn refund(&self, reference: &str, amount: &Money) -> Result<PaymentEvent, PaymentError> {
+        let mut charges = self.charges.lock().unwrap();
+        let charge = charges
+            .get_mut(reference)
+            .ok_or_else(|| PaymentError::UnknownReference(reference.to_string()))?;
+
+        if !charge.captured {
+            return Err(PaymentError::Declined(
+                "cannot refund an uncaptured payment".to_string(),
+            ));
+        }
+        let refunded = charge
+            .refunded
+            .checked_add(amount)
+            .map_err(|e| PaymentError::Declined(e.to_string()))?;
+        if refunded.amount > charge.amount.amount {
+            return Err(PaymentError::Declined(format!(
+                "refund of {} exceeds the captured amount",
+                amount
+            )));
+        }
+        charge.refunded = refunded;
+
+        Ok(PaymentEvent {
+            reference: reference.to_string(),
+            status: PaymentStatus::Refunded,
+        })
+    }
+
+    fn verify_webhook(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<Option<PaymentEvent>, PaymentError> {
+        let signature = headers
+            .get(SIGNATURE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(PaymentError::InvalidSignature)?;
+        if signature != self.webhook_secret {
+            return Err(PaymentError::InvalidSignature);
+        }
+
+        serde_json::from_slice(body)
+            .map(Some)
+            .map_err(|e| PaymentError::InvalidPayload(e.to_string()))
+    }
+}
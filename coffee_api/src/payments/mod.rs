@@ -0,0 +1,84 @@
+pub mod fake;
+#[cfg(feature = "stripe")]
+pub mod stripe;
+
+pub use fake::FakePaymentProvider;
+#[cfg(feature = "stripe")]
+pub use stripe::StripePaymentProvider;
+
+use crate::models::PaymentStatus;
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use coffee_shared::Money;
+use serde::Deserialize;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PaymentError {
+    #[error("payment declined: {0}")]
+    Declined(String),
+
+    #[error("unknown payment reference '{0}'")]
+    UnknownReference(String),
+
+    #[error("webhook signature verification failed")]
+    InvalidSignature,
+
+    #[error("invalid webhook payload: {0}")]
+    InvalidPayload(String),
+
+    #[error("provider error: {0}")]
+    Provider(String),
+}
+
+/// Something that happened to a payment at the provider, either returned
+/// directly from a provider call or delivered later through a webhook.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PaymentEvent {
+    pub reference: String,
+    pub status: PaymentStatus,
+}
+
+#[async_trait]
+pub trait PaymentProvider: Send + Sync {
+    /// Short identifier stored on payment records, e.g. `"fake"`.
+    fn name(&self) -> &'static str;
+
+    /// Reserves `amount` on the customer's payment method without taking it.
+    async fn authorize(
+        &self,
+        amount: &Money,
+        order_reference: &str,
+    ) -> Result<PaymentEvent, PaymentError>;
+
+    /// Takes a previously authorized amount.
+    async fn capture(&self, reference: &str) -> Result<PaymentEvent, PaymentError>;
+
+    /// Returns `amount` of a captured payment to the customer.
+    async fn refund(&self, reference: &str, amount: &Money) -> Result<PaymentEvent, PaymentError>;
+
+    /// Checks the webhook signature and decodes the event it carries, or
+    /// `None` for a genuine event that does not change a payment's status.
+    fn verify_webhook(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<Option<PaymentEvent>, PaymentError>;
+}
+
+/// Picks the payment provider from `PAYMENT_PROVIDER`, falling back to the
+/// in-process fake so the API can run offline.
+pub fn from_env() -> Result<Arc<dyn PaymentProvider>, PaymentError> {
+    let provider = std::env::var("PAYMENT_PROVIDER").unwrap_or_else(|_| "fake".to_string());
+
+    match provider.as_str() {
+        "fake" => Ok(Arc::new(FakePaymentProvider::from_env())),
+        #[cfg(feature = "stripe")]
+        "stripe" => Ok(Arc::new(StripePaymentProvider::from_env()?)),
+        other => Err(PaymentError::Provider(format!(
+            "unsupported payment provider '{}'",
+            other
+        ))),
+    }
+}
@@ -0,0 +1,206 @@
+use super::{PaymentError, PaymentEvent, PaymentProvider};
+use crate::models::PaymentStatus;
+use async_trait::async_trait;
+use axum::http::HeaderMap;
+use chrono::Utc;
+use coffee_shared::Money;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+
+const API_BASE: &str = "https://api.stripe.com/v1";
+const SIGNATURE_HEADER: &str = "stripe-signature";
+/// Webhooks older than this are rejected to limit replay attacks.
+const SIGNATURE_TOLERANCE_SECS: i64 = 300;
+
+/// Stripe adapter using manually captured PaymentIntents.
+pub struct StripePaymentProvider {
+    client: reqwest::Client,
+    secret_key: String,
+    webhook_secret: String,
+}
+
+impl StripePaymentProvider {
+    pub fn new(secret_key: impl Into<String>, webhook_secret: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            secret_key: secret_key.into(),
+            webhook_secret: webhook_secret.into(),
+        }
+    }
+
+    pub fn from_env() -> Result<Self, PaymentError> {
+        let secret_key = std::env::var("STRIPE_SECRET_KEY")
+            .map_err(|_| PaymentError::Provider("STRIPE_SECRET_KEY is not set".to_string()))?;
+        let webhook_secret = std::env::var("STRIPE_WEBHOOK_SECRET")
+            .map_err(|_| PaymentError::Provider("STRIPE_WEBHOOK_SECRET is not set".to_string()))?;
+        Ok(Self::new(secret_key, webhook_secret))
+    }
+
+    async fn post(&self, path: &str, form: &[(&str, String)]) -> Result<Value, PaymentError> {
+        let response = self
+            .client
+            .post(format!("{}{}", API_BASE, path))
+            .basic_auth(&self.secret_key, None::<&str>)
+            .form(form)
+            .send()
+            .await
+            .map_err(|e| PaymentError::Provider(e.to_string()))?;
+
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| PaymentError::Provider(e.to_string()))?;
+
+        if status.is_success() {
+            return Ok(body);
+        }
+
+        let message = body["error"]["message"]
+            .as_str()
+            .unwrap_or("unknown Stripe error")
+            .to_string();
+        if body["error"]["type"] == "card_error" {
+            Err(PaymentError::Declined(message))
+        } else {
+            Err(PaymentError::Provider(message))
+        }
+    }
+}
+
+/// Stripe amounts are integers in the currency's minor unit.
+fn to_minor_units(amount: &Money) -> Result<String, PaymentError> {
+    amount
+        .to_minor_units()
+        .map(|units| units.to_string())
+        .ok_or_else(|| PaymentError::Declined(format!("amount {} is out of range", amount)))
+}
+
+fn payment_intent_id(body: &Value) -> Result<String, PaymentError> {
+    body["id"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| PaymentError::Provider("response is missing an id".to_string()))
+}
+
+#[async_trait]
+impl PaymentProvider for StripePaymentProvider {
+    fn name(&self) -> &'static str {
+        "stripe"
+    }
+
+    async fn authorize(
+        &self,
+        amount: &Money,
+        order_reference: &str,
+    ) -> Result<PaymentEvent, PaymentError> {
+        let body = self
+            .post(
+                "/payment_intents",
+                &[
+                    ("amount", to_minor_units(amount)?),
+                    ("currency", amount.currency.code().to_lowercase()),
+                    ("capture_method", "manual".to_string()),
+                    ("metadata[order]", order_reference.to_string()),
+                ],
+            )
+            .await?;
+
+        Ok(PaymentEvent {
+            reference: payment_intent_id(&body)?,
+            status: PaymentStatus::Authorized,
+        })
+    }
+
+    async fn capture(&self, reference: &str) -> Result<PaymentEvent, PaymentError> {
+        let body = self
+            .post(&format!("/payment_intents/{}/capture", reference), &[])
+            .await?;
+
+        Ok(PaymentEvent {
+            reference: payment_intent_id(&body)?,
+            status: PaymentStatus::Captured,
+        })
+    }
+
+    async fn refund(&self, reference: &str, amount: &Money) -> Result<PaymentEvent, PaymentError> {
+        self.post(
+            "/refunds",
+            &[
+                ("payment_intent", reference.to_string()),
+                ("amount", to_minor_units(amount)?),
+            ],
+        )
+        .await?;
+
+        Ok(PaymentEvent {
+            reference: reference.to_string(),
+            status: PaymentStatus::Refunded,
+        })
+    }
+
+    fn verify_webhook(
+        &self,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<Option<PaymentEvent>, PaymentError> {
+        let header = headers
+            .get(SIGNATURE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(PaymentError::InvalidSignature)?;
+
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in header.split(',') {
+            match part.split_once('=') {
+                Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+                Some(("v1", value)) => signatures.push(value),
+                _ => {}
+            }
+        }
+        let timestamp = timestamp.ok_or(PaymentError::InvalidSignature)?;
+        if (Utc::now().timestamp() - timestamp).abs() > SIGNATURE_TOLERANCE_SECS {
+            return Err(PaymentError::InvalidSignature);
+        }
+
+        let verified = signatures.iter().any(|signature| {
+            let Ok(expected) = hex::decode(signature) else {
+                return false;
+            };
+            let mut mac = Hmac::<Sha256>::new_from_slice(self.webhook_secret.as_bytes())
+                .expect("HMAC accepts keys of any length");
+            mac.update(timestamp.to_string().as_bytes());
+            mac.update(b".");
+            mac.update(body);
+            mac.verify_slice(&expected).is_ok()
+        });
+        if !verified {
+            return Err(PaymentError::InvalidSignature);
+        }
+
+        let event: Value = serde_json::from_slice(body)
+            .map_err(|e| PaymentError::InvalidPayload(e.to_string()))?;
+        let object = &event["data"]["object"];
+        let (status, reference) = match event["type"].as_str() {
+            Some("payment_intent.amount_capturable_updated") => {
+                (PaymentStatus::Authorized, &object["id"])
+            }
+            Some("payment_intent.succeeded") => (PaymentStatus::Captured, &object["id"]),
+            Some("payment_intent.payment_failed") => (PaymentStatus::Failed, &object["id"]),
+            // Also sent for partial refunds, which leave the order as it is
+            Some("charge.refunded") if object["refunded"].as_bool() == Some(true) => {
+                (PaymentStatus::Refunded, &object["payment_intent"])
+            }
+            _ => return Ok(None),
+        };
+        let reference = reference
+            .as_str()
+            .ok_or_else(|| PaymentError::InvalidPayload("missing payment intent id".to_string()))?;
+
+        Ok(Some(PaymentEvent {
+            reference: reference.to_string(),
+            status,
+        }))
+    }
+}
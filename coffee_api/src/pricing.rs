@@ -0,0 +1,72 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{Customer, PriceList, PriceListKind, Product, ProductVariant};
+use coffee_shared::Money;
+use rust_decimal::Decimal;
+use surrealdb::sql::Thing;
+
+/// Works out what `customer` pays per unit for `quantity` of `variant` of
+/// `product`, and which price list the price came from. List prices are in
+/// the variant's currency. The first list that prices the
+/// variant wins, checked in this order:
+///
+/// 1. the customer's own override lists
+/// 2. the wholesale tier assigned to the customer
+/// 3. retail quantity breaks, taking the cheapest if several apply
+/// 4. the variant's base price
+pub async fn resolve_price(
+    db: &Db,
+    customer: Option<&Thing>,
+    product: &Product,
+    variant: &ProductVariant,
+    quantity: i32,
+) -> ApiResult<(Money, Option<Thing>)> {
+    let currency = variant.price.currency;
+    let key = variant.key.as_str();
+    let product_id = product.id.as_ref().ok_or_else(|| ApiError::Internal {
+        message: "Product record has no id".to_string(),
+    })?;
+
+    if let Some(customer_id) = customer {
+        let mut response = db
+            .query("SELECT * FROM price_list WHERE kind = $kind AND customer = $customer")
+            .bind(("kind", PriceListKind::Customer))
+            .bind(("customer", customer_id.clone()))
+            .await?;
+        let overrides: Vec<PriceList> = response.take(0)?;
+        if let Some((price, list)) = cheapest(&overrides, product_id, key, quantity) {
+            return Ok((Money::new(price, currency), list));
+        }
+
+        let customer: Option<Customer> = db.select(record_key(customer_id)).await?;
+        if let Some(tier_id) = customer.and_then(|c| c.price_list) {
+            let tier: Option<PriceList> = db.select(record_key(&tier_id)).await?;
+            if let Some(price) = tier.and_then(|t| t.price_for(product_id, key, quantity)) {
+                return Ok((Money::new(price, currency), Some(tier_id)));
+            }
+        }
+    }
+
+    let mut response = db
+        .query("SELECT * FROM price_list WHERE kind = $kind")
+        .bind(("kind", PriceListKind::Retail))
+        .await?;
+    let retail: Vec<PriceList> = response.take(0)?;
+    if let Some((price, list)) = cheapest(&retail, product_id, key, quantity) {
+        return Ok((Money::new(price, currency), list));
+    }
+
+    Ok((variant.price, None))
+}
+
+fn cheapest(
+    lists: &[PriceList],
+    product: &Thing,
+    variant: &str,
+    quantity: i32,
+) -> Option<(Decimal, Option<Thing>)> {
+    lists
+        .iter()
+        .filter_map(|list| Some((list.price_for(product, variant, quantity)?, list.id.clone())))
+        .min_by_key(|(price, _)| *price)
+}
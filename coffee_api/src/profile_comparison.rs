@@ -0,0 +1,94 @@
+use crate::models::{ProfileComparison, ProfileVerdict, RoastEventKind, RoastProfile};
+use chrono::Utc;
+use surrealdb::sql::Thing;
+
+/// Warn and fail limits for the bean temperature RMSE, in °C.
+const BEAN_TEMP_RMSE_LIMITS: (f64, f64) = (3.0, 6.0);
+/// Warn and fail limits for the development time ratio difference.
+const DEVELOPMENT_TIME_RATIO_LIMITS: (f64, f64) = (0.02, 0.04);
+/// Warn and fail limits for the drop temperature difference, in °C.
+const DROP_TEMP_LIMITS: (f64, f64) = (3.0, 6.0);
+
+fn convert_temp(temp: f64, from: &str, to: &str) -> f64 {
+    match (from, to) {
+        ("F", "C") => (temp - 32.0) / 1.8,
+        ("C", "F") => temp * 1.8 + 32.0,
+        _ => temp,
+    }
+}
+
+fn verdict(deviation: f64, (warn, fail): (f64, f64)) -> ProfileVerdict {
+    if deviation > fail {
+        ProfileVerdict::Fail
+    } else if deviation > warn {
+        ProfileVerdict::Warn
+    } else {
+        ProfileVerdict::Pass
+    }
+}
+
+/// When the roast ended, or its last reading if drop wasn't marked.
+fn end_time(profile: &RoastProfile) -> Option<f64> {
+    profile
+        .event(RoastEventKind::Drop)
+        .map(|drop| drop.time_seconds)
+        .or_else(|| profile.samples.last().map(|s| s.time_seconds))
+}
+
+/// Compares `profile` with `reference` from charge until the earlier of the
+/// two drops. Returns `None` if the curves have no readings in common.
+pub fn compare_profiles(
+    profile: &RoastProfile,
+    reference: &RoastProfile,
+    reference_roast: Thing,
+) -> Option<ProfileComparison> {
+    let unit = profile.temperature_unit.as_str();
+    let to_unit = |temp: f64| convert_temp(temp, &reference.temperature_unit, unit);
+    // Limits are in °C; a Fahrenheit degree is smaller
+    let scale = if unit == "F" { 1.8 } else { 1.0 };
+    let scaled = |(warn, fail): (f64, f64)| (warn * scale, fail * scale);
+
+    let end = end_time(profile)?.min(end_time(reference)?);
+    let squared_errors: Vec<f64> = profile
+        .samples
+        .iter()
+        .filter(|s| (0.0..=end).contains(&s.time_seconds))
+        .filter_map(|s| {
+            let expected = to_unit(reference.bean_temp_at(s.time_seconds)?);
+            Some((s.bean_temp? - expected).powi(2))
+        })
+        .collect();
+    if squared_errors.is_empty() {
+        return None;
+    }
+    let bean_temp_rmse = (squared_errors.iter().sum::<f64>() / squared_errors.len() as f64).sqrt();
+
+    let development_time_ratio_diff = profile
+        .development_time_ratio()
+        .zip(reference.development_time_ratio())
+        .map(|(ratio, reference)| ratio - reference);
+
+    let drop_temp = |p: &RoastProfile| p.event(RoastEventKind::Drop)?.bean_temp;
+    let drop_temp_delta = drop_temp(profile)
+        .zip(drop_temp(reference).map(to_unit))
+        .map(|(temp, reference)| temp - reference);
+
+    let verdict = [
+        Some(verdict(bean_temp_rmse, scaled(BEAN_TEMP_RMSE_LIMITS))),
+        development_time_ratio_diff.map(|d| verdict(d.abs(), DEVELOPMENT_TIME_RATIO_LIMITS)),
+        drop_temp_delta.map(|d| verdict(d.abs(), scaled(DROP_TEMP_LIMITS))),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+    .unwrap_or(ProfileVerdict::Pass);
+
+    Some(ProfileComparison {
+        reference: reference_roast,
+        bean_temp_rmse,
+        development_time_ratio_diff,
+        drop_temp_delta,
+        verdict,
+        compared_at: Utc::now(),
+    })
+}
@@ -0,0 +1,156 @@
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{GreenCoffee, InventoryMovement, Product, Roast, StockAlert, StockItemKind};
+use crate::notifications::Notifier;
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+
+#[derive(Debug, Deserialize)]
+pub struct AlertQuery {
+    /// Days of history used to estimate consumption. Defaults to 30.
+    pub days: Option<i64>,
+}
+
+/// Longest consumption history accepted, in days, keeping the start of the
+/// window within chrono's range.
+pub const MAX_CONSUMPTION_DAYS: i64 = 3650;
+
+fn days_of_cover(stock: f64, daily_consumption: f64) -> Option<f64> {
+    (daily_consumption > 0.0).then(|| stock.max(0.0) / daily_consumption)
+}
+
+/// Items at or below their reorder point, those running out soonest first.
+/// Green coffee use is taken from roasts, product use from shipments.
+async fn low_stock(db: &Db, days: i64) -> ApiResult<Vec<StockAlert>> {
+    if days <= 0 {
+        return Err(ApiError::BadRequest {
+            message: "Consumption must be measured over at least one day".to_string(),
+        });
+    }
+    if days > MAX_CONSUMPTION_DAYS {
+        return Err(ApiError::BadRequest {
+            message: format!(
+                "Consumption is measured over at most {} days",
+                MAX_CONSUMPTION_DAYS
+            ),
+        });
+    }
+    let since = Utc::now() - Duration::days(days);
+
+    let greens: Vec<GreenCoffee> = db.select("green_coffee").await?;
+    let products: Vec<Product> = db.select("product").await?;
+    let roasts: Vec<Roast> = db.select("roast").await?;
+    let mut response = db
+        .query("SELECT * FROM inventory_movement WHERE quantity < 0")
+        .await?;
+    let movements: Vec<InventoryMovement> = response.take(0)?;
+
+    let mut alerts = Vec::new();
+    for green in greens {
+        let (Some(id), Some(reorder_point)) = (green.id, green.reorder_point_grams) else {
+            continue;
+        };
+        if green.stock_grams > reorder_point {
+            continue;
+        }
+        let used: f64 = roasts
+            .iter()
+            .filter(|roast| roast.date_roasted.or(roast.created_at) >= Some(since))
+            .flat_map(Roast::green_usage)
+            .filter(|(green, _)| *green == id)
+            .map(|(_, grams)| grams)
+            .sum();
+        let daily_consumption = used / days as f64;
+        alerts.push(StockAlert {
+            item: id,
+            variant: None,
+            kind: StockItemKind::GreenCoffee,
+            name: green.name,
+            stock: green.stock_grams,
+            reorder_point,
+            daily_consumption,
+            days_of_cover: days_of_cover(green.stock_grams, daily_consumption),
+        });
+    }
+
+    for product in &products {
+        let Some(id) = &product.id else {
+            continue;
+        };
+        for variant in &product.variants {
+            let stock = variant.available_units();
+            let Some(reorder_point) = variant.reorder_point_units else {
+                continue;
+            };
+            if stock > reorder_point {
+                continue;
+            }
+            let used: f64 = movements
+                .iter()
+                .filter(|movement| movement.item == *id)
+                .filter(|movement| movement.variant.as_ref() == Some(&variant.key))
+                .filter(|movement| movement.created_at >= Some(since))
+                .map(|movement| -movement.quantity)
+                .sum();
+            let daily_consumption = used / days as f64;
+            alerts.push(StockAlert {
+                item: id.clone(),
+                variant: Some(variant.key.clone()),
+                kind: StockItemKind::Product,
+                name: product.variant_name(variant),
+                stock: stock as f64,
+                reorder_point: reorder_point as f64,
+                daily_consumption,
+                days_of_cover: days_of_cover(stock as f64, daily_consumption),
+            });
+        }
+    }
+
+    alerts.sort_by(|a, b| match (a.days_of_cover, b.days_of_cover) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.name.cmp(&b.name),
+    });
+    Ok(alerts)
+}
+
+/// Sends the current alerts through `notifier`, skipping it when nothing is
+/// low, and returns what was sent.
+pub async fn send_low_stock_alerts(
+    db: &Db,
+    notifier: &dyn Notifier,
+    days: i64,
+) -> ApiResult<Vec<StockAlert>> {
+    let alerts = low_stock(db, days).await?;
+    if !alerts.is_empty() {
+        notifier.notify(&alerts).await?;
+    }
+
+    Ok(alerts)
+}
+
+// GET /alerts?days=N - Green lots and products at or below their reorder point
+pub async fn list_alerts(
+    State(db): State<Db>,
+    Query(query): Query<AlertQuery>,
+) -> ApiResult<Json<Vec<StockAlert>>> {
+    Ok(Json(low_stock(&db, query.days.unwrap_or(30)).await?))
+}
+
+// POST /alerts/notify?days=N - Send current alerts through the configured notifier
+pub async fn notify_alerts(
+    State(db): State<Db>,
+    State(notifier): State<Arc<dyn Notifier>>,
+    Query(query): Query<AlertQuery>,
+) -> ApiResult<Json<Vec<StockAlert>>> {
+    let days = query.days.unwrap_or(30);
+    Ok(Json(
+        send_low_stock_alerts(&db, notifier.as_ref(), days).await?,
+    ))
+}
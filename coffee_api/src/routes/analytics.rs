@@ -0,0 +1,130 @@
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{BlendMethod, Roast, RoastLoss, RoastLossReport, RoastLossSummary};
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Roast levels need this many roasts before any of them is judged an outlier.
+const MIN_ROASTS_FOR_OUTLIERS: usize = 3;
+
+/// Losses outside this range are almost certainly weighing mistakes.
+pub(crate) const PLAUSIBLE_LOSS_PERCENT: std::ops::RangeInclusive<f64> = 0.0..=30.0;
+
+#[derive(Debug, Deserialize)]
+pub struct RoastLossQuery {
+    /// How many percentage points a roast may sit from its level's median
+    /// loss before it is flagged. Defaults to 3.
+    pub tolerance: Option<f64>,
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+fn summarise<'a>(
+    roasts: impl IntoIterator<Item = &'a RoastLoss>,
+    key: impl Fn(&RoastLoss) -> Option<String>,
+) -> Vec<RoastLossSummary> {
+    let mut groups: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for roast in roasts {
+        if let Some(key) = key(roast) {
+            groups.entry(key).or_default().push(roast.loss_percent);
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(key, losses)| RoastLossSummary {
+            key,
+            roasts: losses.len(),
+            average_loss_percent: losses.iter().sum::<f64>() / losses.len() as f64,
+            min_loss_percent: losses.iter().copied().fold(f64::INFINITY, f64::min),
+            max_loss_percent: losses.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+        .collect()
+}
+
+/// Roast levels are free text, so "Medium" and " medium" count as one level.
+pub(crate) fn level_key(roast_level: &str) -> String {
+    roast_level.trim().to_lowercase()
+}
+
+// GET /analytics/roast-loss?tolerance=N - Weight loss per roast, by level, lot and month
+pub async fn roast_loss_report(
+    State(db): State<Db>,
+    Query(query): Query<RoastLossQuery>,
+) -> ApiResult<Json<RoastLossReport>> {
+    let tolerance = query.tolerance.unwrap_or(3.0);
+    if tolerance.is_nan() || tolerance < 0.0 {
+        return Err(ApiError::BadRequest {
+            message: "Tolerance cannot be negative".to_string(),
+        });
+    }
+
+    let mut response = db
+        .query("SELECT * FROM roast ORDER BY date_roasted")
+        .await?;
+    let roasts: Vec<Roast> = response.take(0)?;
+
+    // Post-roast blends lose nothing of their own
+    let mut losses: Vec<RoastLoss> = roasts
+        .into_iter()
+        .filter(|roast| roast.blend != Some(BlendMethod::PostRoast))
+        .filter_map(|roast| {
+            Some(RoastLoss {
+                loss_percent: roast.loss_percent()?,
+                roast: roast.id?,
+                name: roast.name,
+                roast_level: roast.roast_level,
+                green_coffee: roast.green_coffee,
+                date_roasted: roast.date_roasted.or(roast.created_at),
+                expected_loss_percent: None,
+                outlier: false,
+            })
+        })
+        .collect();
+
+    let mut by_level: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for loss in &losses {
+        by_level
+            .entry(level_key(&loss.roast_level))
+            .or_default()
+            .push(loss.loss_percent);
+    }
+    let medians: BTreeMap<String, f64> = by_level
+        .into_iter()
+        .filter(|(_, values)| values.len() >= MIN_ROASTS_FOR_OUTLIERS)
+        .map(|(level, mut values)| (level, median(&mut values)))
+        .collect();
+
+    for loss in &mut losses {
+        loss.expected_loss_percent = medians.get(&level_key(&loss.roast_level)).copied();
+        let off_level = loss
+            .expected_loss_percent
+            .is_some_and(|expected| (loss.loss_percent - expected).abs() > tolerance);
+        loss.outlier = off_level || !PLAUSIBLE_LOSS_PERCENT.contains(&loss.loss_percent);
+    }
+
+    Ok(Json(RoastLossReport {
+        by_roast_level: summarise(&losses, |loss| Some(level_key(&loss.roast_level))),
+        by_green_coffee: summarise(&losses, |loss| {
+            loss.green_coffee.as_ref().map(|green| green.to_string())
+        }),
+        by_month: summarise(&losses, |loss| {
+            loss.date_roasted
+                .map(|date| date.format("%Y-%m").to_string())
+        }),
+        outliers: losses.iter().filter(|loss| loss.outlier).cloned().collect(),
+        roasts: losses,
+    }))
+}
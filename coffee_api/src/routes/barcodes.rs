@@ -0,0 +1,139 @@
+use crate::barcode::{normalize_gtin, render_png, render_svg};
+use crate::certifications::certify_products;
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{BarcodeMatch, Product, SkuSettings, UpdateSkuSettingsRequest};
+use crate::sku::{SKU_SETTINGS_KEY, load_sku_settings, validate_template};
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+/// Largest PNG scale accepted, in pixels per module.
+const MAX_PNG_SCALE: u32 = 10;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarcodeFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BarcodeQuery {
+    #[serde(default)]
+    pub format: BarcodeFormat,
+    /// Pixels per module for PNGs.
+    pub scale: Option<u32>,
+}
+
+// GET /sku-settings - Template used to generate SKUs
+pub async fn get_sku_settings(State(db): State<Db>) -> ApiResult<Json<SkuSettings>> {
+    Ok(Json(load_sku_settings(&db).await?))
+}
+
+// PUT /sku-settings - Update the SKU template
+pub async fn update_sku_settings(
+    State(db): State<Db>,
+    Json(payload): Json<UpdateSkuSettingsRequest>,
+) -> ApiResult<Json<SkuSettings>> {
+    let mut settings = load_sku_settings(&db).await?;
+
+    // Update fields if provided
+    if let Some(template) = payload.template {
+        settings.template = template;
+    }
+    if let Some(name_length) = payload.name_length {
+        settings.name_length = name_length;
+    }
+    validate_template(&settings.template).map_err(|message| ApiError::BadRequest { message })?;
+    if settings.name_length == 0 {
+        return Err(ApiError::BadRequest {
+            message: "Name length must be at least 1".to_string(),
+        });
+    }
+    settings.id = None;
+    settings.updated_at = Some(Utc::now());
+
+    let updated: Option<SkuSettings> = db.upsert(SKU_SETTINGS_KEY).content(settings).await?;
+
+    match updated {
+        Some(settings) => Ok(Json(settings)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update SKU settings".to_string(),
+        }),
+    }
+}
+
+// GET /products/by-barcode/:code - Find the product and variant for a scanned GTIN or SKU
+pub async fn get_product_by_barcode(
+    State(db): State<Db>,
+    Path(code): Path<String>,
+) -> ApiResult<Json<BarcodeMatch>> {
+    // Numeric codes are GTINs, anything else is taken as a SKU
+    let gtin = if code.chars().all(|c| c.is_ascii_digit()) {
+        Some(normalize_gtin(&code).map_err(|message| ApiError::BadRequest { message })?)
+    } else {
+        None
+    };
+
+    let products: Vec<Product> = db.select("product").await?;
+    for mut product in products {
+        let key = product
+            .variants
+            .iter()
+            .find(|variant| match &gtin {
+                Some(gtin) => variant.gtin.as_ref() == Some(gtin),
+                None => variant.sku.as_ref() == Some(&code),
+            })
+            .map(|variant| variant.key.clone());
+        if let Some(variant) = key {
+            certify_products(&db, std::slice::from_mut(&mut product)).await?;
+            return Ok(Json(BarcodeMatch { product, variant }));
+        }
+    }
+
+    Err(ApiError::NotFound {
+        message: format!("No product has barcode '{}'", code),
+    })
+}
+
+// GET /products/:id/variants/:variant/barcode?format=svg|png&scale=N - EAN-13 image for a label
+pub async fn get_variant_barcode(
+    State(db): State<Db>,
+    Path((id, key)): Path<(String, String)>,
+    Query(query): Query<BarcodeQuery>,
+) -> ApiResult<Response> {
+    let product: Option<Product> = db.select(("product", id.as_str())).await?;
+    let product = product.ok_or_else(|| ApiError::NotFound {
+        message: format!("Product with id '{}' not found", id),
+    })?;
+    let variant = product
+        .variant(Some(&key))
+        .ok_or_else(|| ApiError::NotFound {
+            message: format!("Product '{}' has no '{}' variant", id, key),
+        })?;
+    let gtin = variant.gtin.as_deref().ok_or_else(|| ApiError::NotFound {
+        message: format!("Variant '{}' has no GTIN to render", key),
+    })?;
+
+    match query.format {
+        BarcodeFormat::Svg => {
+            Ok(([(header::CONTENT_TYPE, "image/svg+xml")], render_svg(gtin)).into_response())
+        }
+        BarcodeFormat::Png => {
+            let scale = query.scale.unwrap_or(2);
+            if !(1..=MAX_PNG_SCALE).contains(&scale) {
+                return Err(ApiError::BadRequest {
+                    message: format!("Scale must be between 1 and {}", MAX_PNG_SCALE),
+                });
+            }
+            let png = render_png(gtin, scale).map_err(|message| ApiError::Internal { message })?;
+            Ok(([(header::CONTENT_TYPE, "image/png")], png).into_response())
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::costing::{COST_SETTINGS_KEY, load_settings, product_cost, roast_cost};
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::fx::FxTable;
+use crate::models::{
+    CostSettings, Product, ProductCost, Roast, RoastCost, UpdateCostSettingsRequest,
+};
+use crate::routes::products::find_variant;
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+// GET /cost-settings - Overheads and margin threshold used for costing
+pub async fn get_cost_settings(State(db): State<Db>) -> ApiResult<Json<CostSettings>> {
+    Ok(Json(load_settings(&db).await?))
+}
+
+// PUT /cost-settings - Update overheads and margin threshold
+pub async fn update_cost_settings(
+    State(db): State<Db>,
+    Json(payload): Json<UpdateCostSettingsRequest>,
+) -> ApiResult<Json<CostSettings>> {
+    let mut settings = load_settings(&db).await?;
+
+    // Update fields if provided
+    if let Some(currency) = payload.currency {
+        settings.currency = currency;
+    }
+    if let Some(packaging_per_unit) = payload.packaging_per_unit {
+        settings.packaging_per_unit = packaging_per_unit;
+    }
+    if let Some(labour_per_batch) = payload.labour_per_batch {
+        settings.labour_per_batch = labour_per_batch;
+    }
+    if let Some(energy_per_batch) = payload.energy_per_batch {
+        settings.energy_per_batch = energy_per_batch;
+    }
+    if let Some(min_margin_percent) = payload.min_margin_percent {
+        settings.min_margin_percent = min_margin_percent;
+    }
+    if [
+        settings.packaging_per_unit,
+        settings.labour_per_batch,
+        settings.energy_per_batch,
+    ]
+    .iter()
+    .any(|overhead| overhead.is_sign_negative())
+    {
+        return Err(ApiError::BadRequest {
+            message: "Overheads cannot be negative".to_string(),
+        });
+    }
+    settings.id = None;
+    settings.updated_at = Some(Utc::now());
+
+    let updated: Option<CostSettings> = db.upsert(COST_SETTINGS_KEY).content(settings).await?;
+
+    match updated {
+        Some(settings) => Ok(Json(settings)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update cost settings".to_string(),
+        }),
+    }
+}
+
+// GET /roasts/:id/cost - Cost of a roast batch and per roasted kilo
+pub async fn get_roast_cost(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<RoastCost>> {
+    let roast: Option<Roast> = db.select(("roast", id.as_str())).await?;
+    let roast = roast.ok_or_else(|| ApiError::NotFound {
+        message: format!("Roast with id '{}' not found", id),
+    })?;
+
+    let settings = load_settings(&db).await?;
+    let fx = FxTable::load(&db).await?;
+
+    Ok(Json(roast_cost(&db, &settings, &fx, &roast).await?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VariantQuery {
+    /// Required when the product has more than one variant.
+    pub variant: Option<String>,
+}
+
+// GET /products/:id/cost?variant=KEY - Cost and margin of one unit of a product variant
+pub async fn get_product_cost(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Query(query): Query<VariantQuery>,
+) -> ApiResult<Json<ProductCost>> {
+    let product: Option<Product> = db.select(("product", id.as_str())).await?;
+    let product = product.ok_or_else(|| ApiError::NotFound {
+        message: format!("Product with id '{}' not found", id),
+    })?;
+    let variant = find_variant(&product, query.variant.as_deref())?;
+
+    let settings = load_settings(&db).await?;
+    let fx = FxTable::load(&db).await?;
+
+    Ok(Json(
+        product_cost(&db, &settings, &fx, &product, variant).await?,
+    ))
+}
+
+// GET /products/margins - Margins of every product variant that can be costed, lowest first
+pub async fn list_product_margins(State(db): State<Db>) -> ApiResult<Json<Vec<ProductCost>>> {
+    let products: Vec<Product> = db.select("product").await?;
+    let settings = load_settings(&db).await?;
+    let fx = FxTable::load(&db).await?;
+
+    let mut margins = Vec::new();
+    for product in &products {
+        for variant in &product.variants {
+            match product_cost(&db, &settings, &fx, product, variant).await {
+                Ok(cost) => margins.push(cost),
+                // Products missing a roast, green price or exchange rate can't be costed
+                Err(ApiError::BadRequest { .. } | ApiError::NotFound { .. }) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    margins.sort_by_key(|cost| cost.margin_percent);
+
+    Ok(Json(margins))
+}
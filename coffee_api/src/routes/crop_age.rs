@@ -0,0 +1,147 @@
+use crate::crop_age::{
+    CROP_AGE_SETTINGS_KEY, crop_age_of, load_crop_age_settings, origin_key, validate_rule,
+    validate_warning_days,
+};
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    CropAge, CropAgeSettings, CropAgeStatus, GreenCoffee, UpdateCropAgeSettingsRequest,
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+/// Crop age of every green still in stock that has a harvest year or an
+/// arrival date.
+async fn stocked_lots(db: &Db, settings: &CropAgeSettings) -> ApiResult<Vec<CropAge>> {
+    let greens: Vec<GreenCoffee> = db.select("green_coffee").await?;
+    let now = Utc::now();
+
+    Ok(greens
+        .iter()
+        .filter(|green| green.stock_grams > 0.0)
+        .filter_map(|green| crop_age_of(settings, green, now))
+        .collect())
+}
+
+// GET /crop-age-settings - Harvest timing and crop life by origin
+pub async fn get_crop_age_settings(State(db): State<Db>) -> ApiResult<Json<CropAgeSettings>> {
+    Ok(Json(load_crop_age_settings(&db).await?))
+}
+
+// PUT /crop-age-settings - Update harvest timing, crop life and the past-crop warning window
+pub async fn update_crop_age_settings(
+    State(db): State<Db>,
+    Json(payload): Json<UpdateCropAgeSettingsRequest>,
+) -> ApiResult<Json<CropAgeSettings>> {
+    let mut settings = load_crop_age_settings(&db).await?;
+
+    // Update fields if provided
+    if let Some(default_rule) = payload.default_rule {
+        settings.default_rule = default_rule;
+    }
+    if let Some(origins) = payload.origins {
+        settings.origins = origins;
+    }
+    if let Some(warning_days) = payload.warning_days {
+        settings.warning_days = warning_days;
+    }
+    validate_warning_days(settings.warning_days)
+        .map_err(|message| ApiError::BadRequest { message })?;
+    validate_rule(&settings.default_rule).map_err(|message| ApiError::BadRequest { message })?;
+    for (i, entry) in settings.origins.iter().enumerate() {
+        let origin = origin_key(&entry.origin_country);
+        if origin.is_empty() {
+            return Err(ApiError::BadRequest {
+                message: "Origin country must not be empty".to_string(),
+            });
+        }
+        if settings.origins[..i]
+            .iter()
+            .any(|other| origin_key(&other.origin_country) == origin)
+        {
+            return Err(ApiError::BadRequest {
+                message: format!("Origin '{}' is listed more than once", entry.origin_country),
+            });
+        }
+        validate_rule(&entry.rule).map_err(|message| ApiError::BadRequest {
+            message: format!("{}: {}", entry.origin_country, message),
+        })?;
+    }
+    settings.id = None;
+    settings.updated_at = Some(Utc::now());
+
+    let updated: Option<CropAgeSettings> =
+        db.upsert(CROP_AGE_SETTINGS_KEY).content(settings).await?;
+
+    match updated {
+        Some(settings) => Ok(Json(settings)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update crop age settings".to_string(),
+        }),
+    }
+}
+
+// GET /greens/:id/crop-age - How old a lot's crop is and when it goes past-crop
+pub async fn get_green_crop_age(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<CropAge>> {
+    let green: Option<GreenCoffee> = db.select(("green_coffee", id.as_str())).await?;
+    let green = green.ok_or_else(|| ApiError::NotFound {
+        message: format!("Green coffee with id '{}' not found", id),
+    })?;
+    let settings = load_crop_age_settings(&db).await?;
+
+    let crop_age =
+        crop_age_of(&settings, &green, Utc::now()).ok_or_else(|| ApiError::BadRequest {
+            message: format!("Green coffee '{}' has no harvest year or arrival date", id),
+        })?;
+
+    Ok(Json(crop_age))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PastCropQuery {
+    /// Flag lots going past-crop within this many days instead of the
+    /// configured warning window.
+    pub days: Option<i64>,
+}
+
+// GET /greens/past-crop?days=N - Lots in stock that are past-crop or about to be, soonest first
+pub async fn list_past_crop_greens(
+    State(db): State<Db>,
+    Query(query): Query<PastCropQuery>,
+) -> ApiResult<Json<Vec<CropAge>>> {
+    let mut settings = load_crop_age_settings(&db).await?;
+    if let Some(days) = query.days {
+        validate_warning_days(days).map_err(|message| ApiError::BadRequest { message })?;
+        settings.warning_days = days;
+    }
+
+    let mut flagged: Vec<CropAge> = stocked_lots(&db, &settings)
+        .await?
+        .into_iter()
+        .filter(|lot| lot.status != CropAgeStatus::Current)
+        .collect();
+    flagged.sort_by_key(|lot| lot.past_crop_date);
+
+    Ok(Json(flagged))
+}
+
+// GET /greens/use-first - Lots in stock to roast first: nearest past-crop date, then least stock left
+pub async fn list_use_first_greens(State(db): State<Db>) -> ApiResult<Json<Vec<CropAge>>> {
+    let settings = load_crop_age_settings(&db).await?;
+    let mut lots = stocked_lots(&db, &settings).await?;
+
+    lots.sort_by(|a, b| {
+        a.past_crop_date
+            .cmp(&b.past_crop_date)
+            .then(a.stock_grams.total_cmp(&b.stock_grams))
+    });
+
+    Ok(Json(lots))
+}
@@ -0,0 +1,292 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    CUPS_PER_SAMPLE, CreateCuppingSessionRequest, CuppingAggregate, CuppingForm, CuppingSample,
+    CuppingScores, CuppingSession, GreenCoffee, Roast, SampleKind, SubmitCuppingFormRequest,
+};
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+use surrealdb::sql::Thing;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "cupping_session".to_string()
+}
+
+// Helper function to create SurrealDB record ID
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+async fn find_session(db: &Db, id: &str) -> ApiResult<CuppingSession> {
+    let existing: Option<CuppingSession> = db.select(make_record_id(id)).await?;
+
+    existing.ok_or_else(|| ApiError::NotFound {
+        message: format!("Cupping session with id '{}' not found", id),
+    })
+}
+
+/// Checks what a sample refers to exists, and fills in the green of a
+/// production sample from its roast.
+async fn resolve_sample(db: &Db, sample: &mut CuppingSample) -> ApiResult<()> {
+    if sample.label.trim().is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Every sample needs a label".to_string(),
+        });
+    }
+
+    match sample.kind {
+        SampleKind::Production => {
+            let roast_id = sample.roast.as_ref().ok_or_else(|| ApiError::BadRequest {
+                message: format!("Production sample '{}' needs a roast", sample.label),
+            })?;
+            let roast: Option<Roast> = db.select(record_key(roast_id)).await?;
+            let roast = roast.ok_or_else(|| ApiError::NotFound {
+                message: format!("Roast '{}' not found", roast_id),
+            })?;
+            sample.green_coffee = roast.green_coffee;
+        }
+        SampleKind::Arrival | SampleKind::PreShipment => {
+            if sample.roast.is_some() {
+                return Err(ApiError::BadRequest {
+                    message: format!("Green sample '{}' cannot have a roast", sample.label),
+                });
+            }
+            match &sample.green_coffee {
+                Some(green_id) => {
+                    let green: Option<GreenCoffee> = db.select(record_key(green_id)).await?;
+                    if green.is_none() {
+                        return Err(ApiError::NotFound {
+                            message: format!("Green coffee '{}' not found", green_id),
+                        });
+                    }
+                }
+                // Offers can be cupped before the lot is bought
+                None if sample.kind == SampleKind::PreShipment => {
+                    if sample.description.is_none() {
+                        return Err(ApiError::BadRequest {
+                            message: format!(
+                                "Pre-shipment sample '{}' needs a green coffee or description",
+                                sample.label
+                            ),
+                        });
+                    }
+                }
+                None => {
+                    return Err(ApiError::BadRequest {
+                        message: format!("Arrival sample '{}' needs a green coffee", sample.label),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_scores(scores: &CuppingScores) -> ApiResult<()> {
+    for (attribute, score) in scores.quality_attributes() {
+        if !(6.0..=10.0).contains(&score) || (score * 4.0).fract() != 0.0 {
+            return Err(ApiError::BadRequest {
+                message: format!("{} must be between 6 and 10 in quarter points", attribute),
+            });
+        }
+    }
+    for (attribute, score) in scores.cup_attributes() {
+        if !(0.0..=10.0).contains(&score) || score % 2.0 != 0.0 {
+            return Err(ApiError::BadRequest {
+                message: format!("{} must be 2 points per cup, up to 10", attribute),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Aggregates the forms for samples that `matches` selects across `sessions`.
+fn aggregate(
+    subject: Thing,
+    sessions: &[CuppingSession],
+    matches: impl Fn(&CuppingSample) -> bool,
+) -> CuppingAggregate {
+    let mut session_count = 0;
+    let mut forms: Vec<&CuppingForm> = Vec::new();
+    for session in sessions {
+        let before = forms.len();
+        forms.extend(
+            session
+                .forms
+                .iter()
+                .filter(|form| session.samples.get(form.sample).is_some_and(&matches)),
+        );
+        if forms.len() > before {
+            session_count += 1;
+        }
+    }
+
+    let finals: Vec<f64> = forms.iter().map(|form| form.final_score).collect();
+    let count = finals.len() as f64;
+    CuppingAggregate {
+        subject,
+        sessions: session_count,
+        forms: forms.len(),
+        mean_final_score: (!finals.is_empty()).then(|| finals.iter().sum::<f64>() / count),
+        min_final_score: finals.iter().copied().reduce(f64::min),
+        max_final_score: finals.iter().copied().reduce(f64::max),
+        mean_scores: CuppingScores::mean(forms.iter().map(|form| &form.scores)),
+        mean_defect_points: (!forms.is_empty())
+            .then(|| forms.iter().map(|form| form.defects.points()).sum::<f64>() / count),
+    }
+}
+
+// GET /cupping-sessions - List all cupping sessions
+pub async fn list_cupping_sessions(State(db): State<Db>) -> ApiResult<Json<Vec<CuppingSession>>> {
+    let sessions: Vec<CuppingSession> = db.select(table_name()).await?;
+
+    Ok(Json(sessions))
+}
+
+// GET /cupping-sessions/:id - Get specific cupping session with its forms
+pub async fn get_cupping_session(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<CuppingSession>> {
+    Ok(Json(find_session(&db, &id).await?))
+}
+
+// POST /cupping-sessions - Set up a cupping table
+pub async fn create_cupping_session(
+    State(db): State<Db>,
+    Json(payload): Json<CreateCuppingSessionRequest>,
+) -> ApiResult<Json<CuppingSession>> {
+    let mut session: CuppingSession = payload.into();
+    if session.cuppers.is_empty() || session.samples.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Cupping session needs at least one cupper and one sample".to_string(),
+        });
+    }
+    for (index, cupper) in session.cuppers.iter().enumerate() {
+        if cupper.trim().is_empty() || session.cuppers[..index].contains(cupper) {
+            return Err(ApiError::BadRequest {
+                message: "Cupper names must be unique and not empty".to_string(),
+            });
+        }
+    }
+    for sample in &mut session.samples {
+        resolve_sample(&db, sample).await?;
+    }
+
+    let created: Option<CuppingSession> = db.create(table_name()).content(session).await?;
+
+    match created {
+        Some(session) => Ok(Json(session)),
+        None => Err(ApiError::Internal {
+            message: "Failed to create cupping session record".to_string(),
+        }),
+    }
+}
+
+// POST /cupping-sessions/:id/forms - Score a sample, replacing the cupper's earlier form for it
+pub async fn submit_cupping_form(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<SubmitCuppingFormRequest>,
+) -> ApiResult<Json<CuppingSession>> {
+    let mut session = find_session(&db, &id).await?;
+    if !session.cuppers.contains(&payload.cupper) {
+        return Err(ApiError::BadRequest {
+            message: format!("'{}' is not cupping in this session", payload.cupper),
+        });
+    }
+    if payload.sample >= session.samples.len() {
+        return Err(ApiError::BadRequest {
+            message: format!("Cupping session has no sample {}", payload.sample),
+        });
+    }
+    validate_scores(&payload.scores)?;
+    if payload.defects.cups() > CUPS_PER_SAMPLE {
+        return Err(ApiError::BadRequest {
+            message: format!("Defects can affect at most {} cups", CUPS_PER_SAMPLE),
+        });
+    }
+
+    let form = CuppingForm::from(payload);
+    session
+        .forms
+        .retain(|existing| existing.cupper != form.cupper || existing.sample != form.sample);
+    session.forms.push(form);
+    session.updated_at = Some(Utc::now());
+
+    let updated: Option<CuppingSession> = db.update(make_record_id(&id)).content(session).await?;
+
+    match updated {
+        Some(session) => Ok(Json(session)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update cupping session record".to_string(),
+        }),
+    }
+}
+
+// DELETE /cupping-sessions/:id - Delete cupping session
+pub async fn delete_cupping_session(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let deleted: Option<CuppingSession> = db.delete(make_record_id(&id)).await?;
+
+    match deleted {
+        Some(_) => Ok(Json(
+            serde_json::json!({"message": "Cupping session deleted successfully"}),
+        )),
+        None => Err(ApiError::NotFound {
+            message: format!("Cupping session with id '{}' not found", id),
+        }),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CuppingScoresQuery {
+    /// Only count samples of this kind.
+    pub kind: Option<SampleKind>,
+}
+
+// GET /greens/:id/cupping-scores?kind=arrival - Scores for a green, including roasts made from it
+pub async fn get_green_cupping_scores(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Query(query): Query<CuppingScoresQuery>,
+) -> ApiResult<Json<CuppingAggregate>> {
+    let green: Option<GreenCoffee> = db.select(("green_coffee", id.as_str())).await?;
+    let green_id = green
+        .and_then(|green| green.id)
+        .ok_or_else(|| ApiError::NotFound {
+            message: format!("Green coffee with id '{}' not found", id),
+        })?;
+    let sessions: Vec<CuppingSession> = db.select(table_name()).await?;
+
+    Ok(Json(aggregate(green_id.clone(), &sessions, |sample| {
+        sample.green_coffee.as_ref() == Some(&green_id)
+            && query.kind.is_none_or(|kind| kind == sample.kind)
+    })))
+}
+
+// GET /roasts/:id/cupping-scores - Scores for a production roast
+pub async fn get_roast_cupping_scores(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<CuppingAggregate>> {
+    let roast: Option<Roast> = db.select(("roast", id.as_str())).await?;
+    let roast_id = roast
+        .and_then(|roast| roast.id)
+        .ok_or_else(|| ApiError::NotFound {
+            message: format!("Roast with id '{}' not found", id),
+        })?;
+    let sessions: Vec<CuppingSession> = db.select(table_name()).await?;
+
+    Ok(Json(aggregate(roast_id.clone(), &sessions, |sample| {
+        sample.roast.as_ref() == Some(&roast_id)
+    })))
+}
@@ -0,0 +1,266 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    CreateCustomerRequest, Customer, Order, Payment, PriceList, PriceListKind, SubscriptionStatus,
+    UpdateCustomerRequest, normalize_email,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::{Value, json};
+use surrealdb::sql::Thing;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "customer".to_string()
+}
+
+// Helper function to create SurrealDB record ID
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+async fn find_customer(db: &Db, id: &str) -> ApiResult<Customer> {
+    let existing: Option<Customer> = db.select(make_record_id(id)).await?;
+
+    existing.ok_or_else(|| ApiError::NotFound {
+        message: format!("Customer with id '{}' not found", id),
+    })
+}
+
+/// A `local@domain.tld` shape, checked up front so that the schema's
+/// `string::is::email` assertion never has to reject a write.
+fn is_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && !email.chars().any(char::is_whitespace)
+        && !domain.contains('@')
+        && domain.contains('.')
+        && domain.split('.').all(|label| !label.is_empty())
+}
+
+/// Rejects `email` if it is malformed or another customer already uses it.
+async fn ensure_email_available(db: &Db, email: &str, current: Option<&Thing>) -> ApiResult<()> {
+    if email.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Customer email must not be empty".to_string(),
+        });
+    }
+    if !is_email(email) {
+        return Err(ApiError::BadRequest {
+            message: format!("'{}' is not a valid email address", email),
+        });
+    }
+
+    let mut response = db
+        .query("SELECT * FROM customer WHERE email = $email")
+        .bind(("email", email.to_string()))
+        .await?;
+    let existing: Vec<Customer> = response.take(0)?;
+
+    if existing.iter().any(|c| c.id.as_ref() != current) {
+        return Err(ApiError::Conflict {
+            message: format!("A customer with email '{}' already exists", email),
+        });
+    }
+    Ok(())
+}
+
+/// Customers can only be assigned to an existing wholesale price list.
+async fn ensure_wholesale_list(db: &Db, price_list: &Thing) -> ApiResult<()> {
+    let existing: Option<PriceList> = db.select(record_key(price_list)).await?;
+
+    match existing {
+        Some(list) if list.kind == PriceListKind::Wholesale => Ok(()),
+        Some(_) => Err(ApiError::BadRequest {
+            message: format!("Price list '{}' is not a wholesale list", price_list),
+        }),
+        None => Err(ApiError::BadRequest {
+            message: format!("Price list '{}' not found", price_list),
+        }),
+    }
+}
+
+async fn customer_orders(db: &Db, customer: &Customer) -> ApiResult<Vec<Order>> {
+    let mut response = db
+        .query("SELECT * FROM order WHERE customer = $customer ORDER BY created_at")
+        .bind(("customer", customer.id.clone()))
+        .await?;
+    let orders: Vec<Order> = response.take(0)?;
+
+    Ok(orders)
+}
+
+// GET /customers - List all customers
+pub async fn list_customers(State(db): State<Db>) -> ApiResult<Json<Vec<Customer>>> {
+    let customers: Vec<Customer> = db.select("customer").await?;
+
+    Ok(Json(customers))
+}
+
+// GET /customers/:id - Get specific customer
+pub async fn get_customer(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Customer>> {
+    Ok(Json(find_customer(&db, &id).await?))
+}
+
+// POST /customers - Create new customer, unless the email is already taken
+pub async fn create_customer(
+    State(db): State<Db>,
+    Json(payload): Json<CreateCustomerRequest>,
+) -> ApiResult<Json<Customer>> {
+    let customer: Customer = payload.into();
+    ensure_email_available(&db, &customer.email, None).await?;
+    if let Some(price_list) = &customer.price_list {
+        ensure_wholesale_list(&db, price_list).await?;
+    }
+
+    let created: Option<Customer> = db.create(table_name()).content(customer).await?;
+
+    match created {
+        Some(customer) => Ok(Json(customer)),
+        None => Err(ApiError::Internal {
+            message: "Failed to create customer record".to_string(),
+        }),
+    }
+}
+
+// PUT /customers/:id - Update customer
+pub async fn update_customer(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateCustomerRequest>,
+) -> ApiResult<Json<Customer>> {
+    let mut customer = find_customer(&db, &id).await?;
+    if customer.erased_at.is_some() {
+        return Err(ApiError::Conflict {
+            message: format!("Customer '{}' has been erased", id),
+        });
+    }
+
+    // Update fields if provided
+    if let Some(name) = payload.name {
+        customer.name = name;
+    }
+    if let Some(email) = payload.email {
+        let email = normalize_email(&email);
+        ensure_email_available(&db, &email, customer.id.as_ref()).await?;
+        customer.email = email;
+    }
+    if let Some(addresses) = payload.addresses {
+        customer.addresses = addresses;
+    }
+    if let Some(marketing_consent) = payload.marketing_consent {
+        customer.marketing_consent = marketing_consent;
+    }
+    if let Some(notes) = payload.notes {
+        customer.notes = Some(notes);
+    }
+    if let Some(price_list) = payload.price_list {
+        ensure_wholesale_list(&db, &price_list).await?;
+        customer.price_list = Some(price_list);
+    }
+    customer.updated_at = Some(Utc::now());
+
+    let updated: Option<Customer> = db.update(make_record_id(&id)).content(customer).await?;
+
+    match updated {
+        Some(customer) => Ok(Json(customer)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update customer record".to_string(),
+        }),
+    }
+}
+
+// DELETE /customers/:id - Delete a customer who has never ordered
+pub async fn delete_customer(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let customer = find_customer(&db, &id).await?;
+    if !customer_orders(&db, &customer).await?.is_empty() {
+        return Err(ApiError::Conflict {
+            message: format!(
+                "Customer '{}' has orders; erase their personal data instead",
+                id
+            ),
+        });
+    }
+
+    let _: Option<Customer> = db.delete(make_record_id(&id)).await?;
+
+    Ok(Json(json!({"message": "Customer deleted successfully"})))
+}
+
+// GET /customers/:id/orders - Order history for a customer
+pub async fn list_customer_orders(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Vec<Order>>> {
+    let customer = find_customer(&db, &id).await?;
+
+    Ok(Json(customer_orders(&db, &customer).await?))
+}
+
+// GET /customers/:id/export - Everything held about a customer
+pub async fn export_customer(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let customer = find_customer(&db, &id).await?;
+    let orders = customer_orders(&db, &customer).await?;
+
+    let order_ids: Vec<Thing> = orders.iter().filter_map(|o| o.id.clone()).collect();
+    let mut response = db
+        .query("SELECT * FROM payment WHERE order IN $orders")
+        .bind(("orders", order_ids))
+        .await?;
+    let payments: Vec<Payment> = response.take(0)?;
+
+    Ok(Json(json!({
+        "customer": customer,
+        "orders": orders,
+        "payments": payments,
+        "exported_at": Utc::now(),
+    })))
+}
+
+// POST /customers/:id/erase - Anonymise a customer while keeping their orders
+pub async fn erase_customer(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Customer>> {
+    let mut customer = find_customer(&db, &id).await?;
+
+    let now = Utc::now();
+    customer.name = "Erased customer".to_string();
+    // Keeps the unique email index satisfied without retaining the address
+    customer.email = format!("erased-{}@anonymised.invalid", id);
+    customer.addresses = Vec::new();
+    customer.marketing_consent = false;
+    customer.notes = None;
+    customer.erased_at.get_or_insert(now);
+    customer.updated_at = Some(now);
+
+    // Erased customers cannot order, so their subscriptions would only fail
+    db.query("UPDATE subscription SET status = $cancelled, updated_at = time::now() WHERE customer = $customer AND status != $cancelled")
+        .bind(("cancelled", SubscriptionStatus::Cancelled))
+        .bind(("customer", customer.id.clone()))
+        .await?
+        .check()?;
+
+    let updated: Option<Customer> = db.update(make_record_id(&id)).content(customer).await?;
+
+    match updated {
+        Some(customer) => Ok(Json(customer)),
+        None => Err(ApiError::Internal {
+            message: "Failed to erase customer record".to_string(),
+        }),
+    }
+}
@@ -0,0 +1,127 @@
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::flavours::{FlavourWheel, flavour_tree};
+use crate::models::{
+    CreateFlavourAliasRequest, FlavourAlias, FlavourCategory, GreenCoffee, NoteMapping, Roast,
+    flavour_key,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "flavour_alias".to_string()
+}
+
+// Helper function to create SurrealDB record ID
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+// GET /flavours - The flavour wheel as a tree of categories
+pub async fn list_flavours() -> Json<Vec<FlavourCategory>> {
+    Json(flavour_tree())
+}
+
+// GET /flavours/notes - Tasting notes in use and the categories they map to
+pub async fn list_flavour_notes(State(db): State<Db>) -> ApiResult<Json<Vec<NoteMapping>>> {
+    let wheel = FlavourWheel::load(&db).await?;
+    let greens: Vec<GreenCoffee> = db.select("green_coffee").await?;
+    let roasts: Vec<Roast> = db.select("roast").await?;
+
+    // Spellings that differ only in case or punctuation are counted together
+    let mut notes: BTreeMap<String, NoteMapping> = BTreeMap::new();
+    let mut count = |note: &String, green: bool| {
+        let mapping = notes
+            .entry(flavour_key(note))
+            .or_insert_with(|| NoteMapping {
+                note: note.trim().to_string(),
+                category: wheel.categorise(note),
+                greens: 0,
+                roasts: 0,
+            });
+        if green {
+            mapping.greens += 1;
+        } else {
+            mapping.roasts += 1;
+        }
+    };
+    for note in greens
+        .iter()
+        .flat_map(|green| green.cupping_notes.iter().flatten())
+    {
+        count(note, true);
+    }
+    for note in roasts.iter().flat_map(|roast| roast.notes.iter().flatten()) {
+        count(note, false);
+    }
+
+    Ok(Json(notes.into_values().collect()))
+}
+
+// GET /flavour-aliases - List all flavour aliases
+pub async fn list_flavour_aliases(State(db): State<Db>) -> ApiResult<Json<Vec<FlavourAlias>>> {
+    let aliases: Vec<FlavourAlias> = db.select(table_name()).await?;
+
+    Ok(Json(aliases))
+}
+
+// POST /flavour-aliases - Map free text onto a flavour category
+pub async fn create_flavour_alias(
+    State(db): State<Db>,
+    Json(payload): Json<CreateFlavourAliasRequest>,
+) -> ApiResult<Json<FlavourAlias>> {
+    let mut alias: FlavourAlias = payload.into();
+    let key = flavour_key(&alias.alias);
+    if key.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Alias must not be empty".to_string(),
+        });
+    }
+
+    let existing: Vec<FlavourAlias> = db.select(table_name()).await?;
+    if existing
+        .iter()
+        .any(|other| flavour_key(&other.alias) == key)
+    {
+        return Err(ApiError::Conflict {
+            message: format!("'{}' is already an alias", alias.alias),
+        });
+    }
+    let wheel = FlavourWheel::new(existing);
+    alias.category = wheel
+        .resolve(&alias.category)
+        .ok_or_else(|| ApiError::BadRequest {
+            message: format!("Unknown flavour category '{}'", alias.category),
+        })?;
+
+    let created: Option<FlavourAlias> = db.create(table_name()).content(alias).await?;
+
+    match created {
+        Some(alias) => Ok(Json(alias)),
+        None => Err(ApiError::Internal {
+            message: "Failed to create flavour alias record".to_string(),
+        }),
+    }
+}
+
+// DELETE /flavour-aliases/:id - Delete flavour alias
+pub async fn delete_flavour_alias(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let deleted: Option<FlavourAlias> = db.delete(make_record_id(&id)).await?;
+
+    match deleted {
+        Some(_) => Ok(Json(
+            serde_json::json!({"message": "Flavour alias deleted successfully"}),
+        )),
+        None => Err(ApiError::NotFound {
+            message: format!("Flavour alias with id '{}' not found", id),
+        }),
+    }
+}
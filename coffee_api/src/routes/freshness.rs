@@ -0,0 +1,235 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::freshness::{
+    FRESHNESS_SETTINGS_KEY, MAX_FRESHNESS_DAYS, freshness_of, load_freshness_settings,
+    validate_rule,
+};
+use crate::models::{
+    FifoRecommendations, Freshness, FreshnessSettings, FreshnessStatus, Product, Roast,
+    RoastFreshness, UpdateFreshnessSettingsRequest, VariantFreshness,
+};
+use crate::routes::analytics::level_key;
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+fn roast_freshness(roast: &Roast, freshness: Freshness) -> RoastFreshness {
+    RoastFreshness {
+        name: roast.name.clone(),
+        roast_level: roast.roast_level.clone(),
+        yield_grams: roast.yield_grams,
+        freshness,
+    }
+}
+
+/// One entry per variant of `product`, all sharing the freshness of its roast.
+fn variant_freshness(product: &Product, freshness: &Freshness) -> Vec<VariantFreshness> {
+    let Some(product_id) = &product.id else {
+        return Vec::new();
+    };
+    product
+        .variants
+        .iter()
+        .map(|variant| VariantFreshness {
+            product: product_id.clone(),
+            variant: variant.key.clone(),
+            name: product.variant_name(variant),
+            stock_units: variant.stock_units,
+            available_units: variant.available_units(),
+            freshness: freshness.clone(),
+        })
+        .collect()
+}
+
+/// Every product variant whose roast has a date, with its freshness now.
+async fn stocked_variants(
+    db: &Db,
+    settings: &FreshnessSettings,
+) -> ApiResult<Vec<VariantFreshness>> {
+    let products: Vec<Product> = db.select("product").await?;
+    let roasts: Vec<Roast> = db.select("roast").await?;
+    let now = Utc::now();
+
+    let mut variants = Vec::new();
+    for product in &products {
+        let Some(roast) = roasts
+            .iter()
+            .find(|roast| roast.id.is_some() && roast.id == product.roast)
+        else {
+            continue;
+        };
+        if let Some(freshness) = freshness_of(settings, Some(product), roast, now) {
+            variants.extend(variant_freshness(product, &freshness));
+        }
+    }
+    Ok(variants)
+}
+
+// GET /freshness-settings - Rest periods and shelf life by roast level
+pub async fn get_freshness_settings(State(db): State<Db>) -> ApiResult<Json<FreshnessSettings>> {
+    Ok(Json(load_freshness_settings(&db).await?))
+}
+
+// PUT /freshness-settings - Update rest periods and shelf life
+pub async fn update_freshness_settings(
+    State(db): State<Db>,
+    Json(payload): Json<UpdateFreshnessSettingsRequest>,
+) -> ApiResult<Json<FreshnessSettings>> {
+    let mut settings = load_freshness_settings(&db).await?;
+
+    // Update fields if provided
+    if let Some(default_rule) = payload.default_rule {
+        settings.default_rule = default_rule;
+    }
+    if let Some(roast_levels) = payload.roast_levels {
+        settings.roast_levels = roast_levels;
+    }
+    validate_rule(&settings.default_rule).map_err(|message| ApiError::BadRequest { message })?;
+    for (i, entry) in settings.roast_levels.iter().enumerate() {
+        let level = level_key(&entry.roast_level);
+        if level.is_empty() {
+            return Err(ApiError::BadRequest {
+                message: "Roast level must not be empty".to_string(),
+            });
+        }
+        if settings.roast_levels[..i]
+            .iter()
+            .any(|other| level_key(&other.roast_level) == level)
+        {
+            return Err(ApiError::BadRequest {
+                message: format!(
+                    "Roast level '{}' is listed more than once",
+                    entry.roast_level
+                ),
+            });
+        }
+        validate_rule(&entry.rule).map_err(|message| ApiError::BadRequest {
+            message: format!("{}: {}", entry.roast_level, message),
+        })?;
+    }
+    settings.id = None;
+    settings.updated_at = Some(Utc::now());
+
+    let updated: Option<FreshnessSettings> =
+        db.upsert(FRESHNESS_SETTINGS_KEY).content(settings).await?;
+
+    match updated {
+        Some(settings) => Ok(Json(settings)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update freshness settings".to_string(),
+        }),
+    }
+}
+
+// GET /roasts/:id/freshness - Whether a batch is resting, at its peak, ageing or expired
+pub async fn get_roast_freshness(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<RoastFreshness>> {
+    let roast: Option<Roast> = db.select(("roast", id.as_str())).await?;
+    let roast = roast.ok_or_else(|| ApiError::NotFound {
+        message: format!("Roast with id '{}' not found", id),
+    })?;
+    let settings = load_freshness_settings(&db).await?;
+
+    let freshness =
+        freshness_of(&settings, None, &roast, Utc::now()).ok_or_else(|| ApiError::BadRequest {
+            message: format!("Roast '{}' has no roast date", id),
+        })?;
+
+    Ok(Json(roast_freshness(&roast, freshness)))
+}
+
+// GET /products/:id/freshness - Freshness of the roast each variant was packed from
+pub async fn get_product_freshness(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Vec<VariantFreshness>>> {
+    let product: Option<Product> = db.select(("product", id.as_str())).await?;
+    let product = product.ok_or_else(|| ApiError::NotFound {
+        message: format!("Product with id '{}' not found", id),
+    })?;
+    let roast_id = product.roast.as_ref().ok_or_else(|| ApiError::BadRequest {
+        message: format!("Product '{}' has no roast", id),
+    })?;
+    let roast: Option<Roast> = db.select(record_key(roast_id)).await?;
+    let roast = roast.ok_or_else(|| ApiError::NotFound {
+        message: format!("Roast '{}' not found", roast_id),
+    })?;
+    let settings = load_freshness_settings(&db).await?;
+
+    let freshness =
+        freshness_of(&settings, Some(&product), &roast, Utc::now()).ok_or_else(|| {
+            ApiError::BadRequest {
+                message: format!("Roast '{}' has no roast date", roast_id),
+            }
+        })?;
+
+    Ok(Json(variant_freshness(&product, &freshness)))
+}
+
+// GET /freshness/fifo - Batches to pack and stock to ship first, oldest roast first
+pub async fn get_fifo_recommendations(
+    State(db): State<Db>,
+) -> ApiResult<Json<FifoRecommendations>> {
+    let settings = load_freshness_settings(&db).await?;
+    let roasts: Vec<Roast> = db.select("roast").await?;
+    let now = Utc::now();
+
+    let mut packing: Vec<RoastFreshness> = roasts
+        .iter()
+        .filter_map(|roast| {
+            let freshness = freshness_of(&settings, None, roast, now)?;
+            matches!(
+                freshness.status,
+                FreshnessStatus::Peak | FreshnessStatus::Ageing
+            )
+            .then(|| roast_freshness(roast, freshness))
+        })
+        .collect();
+    packing.sort_by_key(|roast| roast.freshness.roast_date);
+
+    let mut shipping: Vec<VariantFreshness> = stocked_variants(&db, &settings)
+        .await?
+        .into_iter()
+        .filter(|variant| {
+            variant.available_units > 0 && variant.freshness.status != FreshnessStatus::Expired
+        })
+        .collect();
+    shipping.sort_by_key(|variant| variant.freshness.roast_date);
+
+    Ok(Json(FifoRecommendations { packing, shipping }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExpiringQuery {
+    /// Report stock whose best-before date is within this many days.
+    pub days: Option<i64>,
+}
+
+// GET /freshness/expiring?days=N - Stock past or nearing its best-before date, soonest first
+pub async fn get_expiring_stock(
+    State(db): State<Db>,
+    Query(query): Query<ExpiringQuery>,
+) -> ApiResult<Json<Vec<VariantFreshness>>> {
+    let days = query.days.unwrap_or(14);
+    if !(0..=MAX_FRESHNESS_DAYS).contains(&days) {
+        return Err(ApiError::BadRequest {
+            message: format!("Days must be between 0 and {}", MAX_FRESHNESS_DAYS),
+        });
+    }
+    let settings = load_freshness_settings(&db).await?;
+    let horizon = Utc::now() + Duration::days(days);
+
+    let mut expiring: Vec<VariantFreshness> = stocked_variants(&db, &settings)
+        .await?
+        .into_iter()
+        .filter(|variant| variant.stock_units > 0 && variant.freshness.best_before <= horizon)
+        .collect();
+    expiring.sort_by_key(|variant| variant.freshness.best_before);
+
+    Ok(Json(expiring))
+}
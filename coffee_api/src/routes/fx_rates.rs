@@ -0,0 +1,117 @@
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{CreateFxRateRequest, FxRate, FxRateSource};
+use axum::{extract::State, response::Json};
+use chrono::{DateTime, Utc};
+use coffee_shared::MoneyError;
+use rust_decimal::Decimal;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "fx_rate".to_string()
+}
+
+fn validate_fx_rate(rate: &FxRate) -> Result<(), String> {
+    if rate.base == rate.quote {
+        return Err(format!(
+            "Exchange rate from {} to itself is always 1",
+            rate.base
+        ));
+    }
+    if rate.rate <= Decimal::ZERO {
+        return Err(format!(
+            "Exchange rate from {} to {} must be positive",
+            rate.base, rate.quote
+        ));
+    }
+    Ok(())
+}
+
+async fn insert_fx_rate(db: &Db, rate: FxRate) -> ApiResult<FxRate> {
+    let created: Option<FxRate> = db.create(table_name()).content(rate).await?;
+
+    created.ok_or_else(|| ApiError::Internal {
+        message: "Failed to create exchange rate record".to_string(),
+    })
+}
+
+/// Parses one `base,quote,rate[,effective_at]` line of a rate import.
+fn parse_csv_line(line: &str, now: DateTime<Utc>) -> Result<FxRate, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let (base, quote, rate, effective_at) = match fields.as_slice() {
+        [base, quote, rate] => (base, quote, rate, None),
+        [base, quote, rate, effective_at] => (base, quote, rate, Some(effective_at)),
+        _ => return Err("expected base,quote,rate[,effective_at]".to_string()),
+    };
+
+    Ok(FxRate {
+        id: None,
+        base: base.parse().map_err(|e: MoneyError| e.to_string())?,
+        quote: quote.parse().map_err(|e: MoneyError| e.to_string())?,
+        rate: rate
+            .parse()
+            .map_err(|_| format!("'{}' is not a decimal rate", rate))?,
+        source: FxRateSource::Csv,
+        effective_at: match effective_at {
+            Some(at) => at
+                .parse()
+                .map_err(|_| format!("'{}' is not an RFC 3339 timestamp", at))?,
+            None => now,
+        },
+        created_at: None,
+    })
+}
+
+// GET /fx-rates - List all exchange rates, newest first
+pub async fn list_fx_rates(State(db): State<Db>) -> ApiResult<Json<Vec<FxRate>>> {
+    let mut response = db
+        .query("SELECT * FROM fx_rate ORDER BY effective_at DESC")
+        .await?;
+    let rates: Vec<FxRate> = response.take(0)?;
+
+    Ok(Json(rates))
+}
+
+// POST /fx-rates - Enter an exchange rate by hand
+pub async fn create_fx_rate(
+    State(db): State<Db>,
+    Json(payload): Json<CreateFxRateRequest>,
+) -> ApiResult<Json<FxRate>> {
+    let rate: FxRate = payload.into();
+    validate_fx_rate(&rate).map_err(|message| ApiError::BadRequest { message })?;
+
+    Ok(Json(insert_fx_rate(&db, rate).await?))
+}
+
+// POST /fx-rates/import - Import exchange rates from a CSV body
+pub async fn import_fx_rates(State(db): State<Db>, body: String) -> ApiResult<Json<Vec<FxRate>>> {
+    let now = Utc::now();
+    let mut rates = Vec::new();
+    for (index, line) in body.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || (index == 0 && line.to_ascii_lowercase().starts_with("base")) {
+            continue;
+        }
+
+        let rate = parse_csv_line(line, now)
+            .and_then(|rate| validate_fx_rate(&rate).map(|_| rate))
+            .map_err(|message| ApiError::BadRequest {
+                message: format!("Line {}: {}", index + 1, message),
+            })?;
+        rates.push(rate);
+    }
+
+    if rates.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "No exchange rates found in import".to_string(),
+        });
+    }
+
+    // Every line is checked before anything is saved
+    let mut created = Vec::with_capacity(rates.len());
+    for rate in rates {
+        created.push(insert_fx_rate(&db, rate).await?);
+    }
+
+    Ok(Json(created))
+}
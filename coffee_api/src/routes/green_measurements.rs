@@ -0,0 +1,208 @@
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    AcceptableRange, CreateGreenMeasurementRequest, GreenCoffee, GreenMeasurement, QualityRanges,
+    QualityWarning, RecordedMeasurement, UpdateQualityRangesRequest,
+};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::Utc;
+use surrealdb::sql::Thing;
+
+/// Key of the single quality ranges record.
+const QUALITY_RANGES_KEY: (&str, &str) = ("quality_ranges", "current");
+
+// Helper function to get table name
+fn table_name() -> String {
+    "green_measurement".to_string()
+}
+
+async fn find_green(db: &Db, id: &str) -> ApiResult<GreenCoffee> {
+    let green: Option<GreenCoffee> = db.select(("green_coffee", id)).await?;
+
+    green.ok_or_else(|| ApiError::NotFound {
+        message: format!("Green coffee with id '{}' not found", id),
+    })
+}
+
+/// The stored quality ranges, or the defaults if none have been saved.
+async fn load_ranges(db: &Db) -> ApiResult<QualityRanges> {
+    let ranges: Option<QualityRanges> = db.select(QUALITY_RANGES_KEY).await?;
+
+    Ok(ranges.unwrap_or_default())
+}
+
+/// Every reading of `green`, oldest first.
+async fn measurements_of(db: &Db, green: &Thing) -> ApiResult<Vec<GreenMeasurement>> {
+    let mut response = db
+        .query("SELECT * FROM green_measurement WHERE green_coffee = $green ORDER BY measured_at")
+        .bind(("green", green.clone()))
+        .await?;
+
+    Ok(response.take(0)?)
+}
+
+fn validate_measurement(measurement: &GreenMeasurement) -> ApiResult<()> {
+    let readings = [
+        measurement.moisture_percent,
+        measurement.density_g_per_l,
+        measurement.water_activity,
+    ];
+    if readings.iter().all(Option::is_none) {
+        return Err(ApiError::BadRequest {
+            message: "Measurement must include at least one reading".to_string(),
+        });
+    }
+    if measurement
+        .moisture_percent
+        .is_some_and(|moisture| !(0.0..=100.0).contains(&moisture))
+    {
+        return Err(ApiError::BadRequest {
+            message: "Moisture must be between 0 and 100 percent".to_string(),
+        });
+    }
+    if measurement
+        .density_g_per_l
+        .is_some_and(|density| !density.is_finite() || density <= 0.0)
+    {
+        return Err(ApiError::BadRequest {
+            message: "Density must be positive".to_string(),
+        });
+    }
+    if measurement
+        .water_activity
+        .is_some_and(|activity| !(0.0..=1.0).contains(&activity))
+    {
+        return Err(ApiError::BadRequest {
+            message: "Water activity must be between 0 and 1".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn validate_range(name: &str, range: &AcceptableRange) -> ApiResult<()> {
+    if let (Some(min), Some(max)) = (range.min, range.max)
+        && min > max
+    {
+        return Err(ApiError::BadRequest {
+            message: format!("Minimum {} is above the maximum", name),
+        });
+    }
+    Ok(())
+}
+
+// GET /greens/:id/measurements - Moisture, density and water activity readings, oldest first
+pub async fn list_green_measurements(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Vec<GreenMeasurement>>> {
+    let green = find_green(&db, &id).await?;
+    let green_id = green.id.ok_or_else(|| ApiError::Internal {
+        message: "Green coffee record has no id".to_string(),
+    })?;
+
+    Ok(Json(measurements_of(&db, &green_id).await?))
+}
+
+// POST /greens/:id/measurements - Record a reading and report any out-of-range metrics
+pub async fn create_green_measurement(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<CreateGreenMeasurementRequest>,
+) -> ApiResult<Json<RecordedMeasurement>> {
+    let green = find_green(&db, &id).await?;
+    let green_id = green.id.ok_or_else(|| ApiError::Internal {
+        message: "Green coffee record has no id".to_string(),
+    })?;
+
+    let measurement = GreenMeasurement {
+        id: None,
+        green_coffee: green_id.clone(),
+        measured_at: payload.measured_at.unwrap_or_else(Utc::now),
+        moisture_percent: payload.moisture_percent,
+        density_g_per_l: payload.density_g_per_l,
+        water_activity: payload.water_activity,
+        notes: payload.notes,
+        created_at: None,
+    };
+    validate_measurement(&measurement)?;
+
+    let created: Option<GreenMeasurement> = db.create(table_name()).content(measurement).await?;
+    let measurement = created.ok_or_else(|| ApiError::Internal {
+        message: "Failed to create green measurement record".to_string(),
+    })?;
+
+    let ranges = load_ranges(&db).await?;
+    let measurements = measurements_of(&db, &green_id).await?;
+    let warnings = ranges.warnings(&green_id, &green.name, &measurements);
+
+    Ok(Json(RecordedMeasurement {
+        measurement,
+        warnings,
+    }))
+}
+
+// GET /greens/quality-warnings - Greens in stock whose latest readings are out of range
+pub async fn list_quality_warnings(State(db): State<Db>) -> ApiResult<Json<Vec<QualityWarning>>> {
+    let ranges = load_ranges(&db).await?;
+    let greens: Vec<GreenCoffee> = db.select("green_coffee").await?;
+    let measurements: Vec<GreenMeasurement> = db.select(table_name()).await?;
+
+    let mut warnings = Vec::new();
+    for green in greens {
+        let Some(green_id) = green.id else {
+            continue;
+        };
+        if green.stock_grams <= 0.0 {
+            continue;
+        }
+        let readings: Vec<GreenMeasurement> = measurements
+            .iter()
+            .filter(|measurement| measurement.green_coffee == green_id)
+            .cloned()
+            .collect();
+        warnings.extend(ranges.warnings(&green_id, &green.name, &readings));
+    }
+
+    Ok(Json(warnings))
+}
+
+// GET /quality-ranges - Acceptable moisture, density and water activity of green coffee
+pub async fn get_quality_ranges(State(db): State<Db>) -> ApiResult<Json<QualityRanges>> {
+    Ok(Json(load_ranges(&db).await?))
+}
+
+// PUT /quality-ranges - Update acceptable ranges
+pub async fn update_quality_ranges(
+    State(db): State<Db>,
+    Json(payload): Json<UpdateQualityRangesRequest>,
+) -> ApiResult<Json<QualityRanges>> {
+    let mut ranges = load_ranges(&db).await?;
+
+    // Update fields if provided
+    if let Some(moisture_percent) = payload.moisture_percent {
+        ranges.moisture_percent = moisture_percent;
+    }
+    if let Some(density_g_per_l) = payload.density_g_per_l {
+        ranges.density_g_per_l = density_g_per_l;
+    }
+    if let Some(water_activity) = payload.water_activity {
+        ranges.water_activity = water_activity;
+    }
+    validate_range("moisture", &ranges.moisture_percent)?;
+    validate_range("density", &ranges.density_g_per_l)?;
+    validate_range("water activity", &ranges.water_activity)?;
+    ranges.id = None;
+    ranges.updated_at = Some(Utc::now());
+
+    let updated: Option<QualityRanges> = db.upsert(QUALITY_RANGES_KEY).content(ranges).await?;
+
+    match updated {
+        Some(ranges) => Ok(Json(ranges)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update quality ranges".to_string(),
+        }),
+    }
+}
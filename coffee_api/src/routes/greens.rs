@@ -1,11 +1,17 @@
-use crate::db::Db;
+use crate::certifications::{CertificationQuery, validate_certifications};
+use crate::db::{Db, record_key};
 use crate::error::{ApiError, ApiResult};
-use crate::models::{CreateGreenCoffeeRequest, GreenCoffee, UpdateGreenCoffeeRequest};
+use crate::flavours::FlavourQuery;
+use crate::fx::{CurrencyQuery, FxTable};
+use crate::models::{CreateGreenCoffeeRequest, GreenCoffee, Roast, UpdateGreenCoffeeRequest};
+use crate::routes::suppliers::ensure_supplier_exists;
+use crate::trace::relate_green;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::Json,
 };
 use chrono::Utc;
+use coffee_shared::Currency;
 use serde_json::Value;
 
 // Helper function to get table name
@@ -18,24 +24,91 @@ fn make_record_id(id: &str) -> (String, String) {
     (table_name(), id.to_string())
 }
 
-// GET /greens - List all green coffees
-pub async fn list_greens(State(db): State<Db>) -> ApiResult<Json<Vec<GreenCoffee>>> {
-    let greens: Vec<GreenCoffee> = db.select("green_coffee").await?;
+fn validate_green(green: &GreenCoffee) -> ApiResult<()> {
+    if green.price_per_kg.is_some_and(|price| price.is_negative()) {
+        return Err(ApiError::BadRequest {
+            message: "Green coffee price cannot be negative".to_string(),
+        });
+    }
+    if green
+        .farm_gate_price_per_kg
+        .is_some_and(|price| price.is_negative())
+    {
+        return Err(ApiError::BadRequest {
+            message: "Farm-gate price cannot be negative".to_string(),
+        });
+    }
+    if green.reorder_point_grams.is_some_and(|grams| grams < 0.0) {
+        return Err(ApiError::BadRequest {
+            message: "Reorder point cannot be negative".to_string(),
+        });
+    }
+    validate_certifications(&green.certifications)
+        .map_err(|message| ApiError::BadRequest { message })?;
+    Ok(())
+}
+
+/// Converts the prices of `green`, where it has them, into `currency`.
+fn convert_green(fx: &FxTable, green: &mut GreenCoffee, currency: Currency) -> ApiResult<()> {
+    if let Some(price) = &green.price_per_kg {
+        green.price_per_kg = Some(fx.convert(price, currency)?);
+    }
+    if let Some(price) = &green.farm_gate_price_per_kg {
+        green.farm_gate_price_per_kg = Some(fx.convert(price, currency)?);
+    }
+    Ok(())
+}
+
+// GET /greens?currency=XXX&flavour=fruity > citrus&certification=organic - List all green coffees, optionally with prices converted or narrowed to a flavour category or unexpired certification
+pub async fn list_greens(
+    State(db): State<Db>,
+    Query(query): Query<CurrencyQuery>,
+    Query(filter): Query<FlavourQuery>,
+    Query(certified): Query<CertificationQuery>,
+) -> ApiResult<Json<Vec<GreenCoffee>>> {
+    let mut greens: Vec<GreenCoffee> = db.select("green_coffee").await?;
+
+    if let Some(certification) = certified.certification {
+        let now = Utc::now();
+        greens.retain(|green| green.is_certified(certification, now));
+    }
+
+    if let Some((wheel, category)) = filter.load(&db).await? {
+        greens.retain(|green| {
+            green
+                .cupping_notes
+                .as_ref()
+                .is_some_and(|notes| wheel.matches(notes, &category))
+        });
+    }
+
+    if let Some(currency) = query.currency {
+        let fx = FxTable::load(&db).await?;
+        for green in &mut greens {
+            convert_green(&fx, green, currency)?;
+        }
+    }
 
     Ok(Json(greens))
 }
 
-// GET /greens/:id - Get specific green coffee
+// GET /greens/:id?currency=XXX - Get specific green coffee, optionally with its price converted
 pub async fn get_green(
     State(db): State<Db>,
     Path(id): Path<String>,
+    Query(query): Query<CurrencyQuery>,
 ) -> ApiResult<Json<GreenCoffee>> {
-    match db.select(make_record_id(&id)).await? {
-        Some(green) => Ok(Json(green)),
-        None => Err(ApiError::NotFound {
-            message: "Failed to get green coffee record".to_string(),
-        }),
+    let green: Option<GreenCoffee> = db.select(make_record_id(&id)).await?;
+    let mut green = green.ok_or_else(|| ApiError::NotFound {
+        message: "Failed to get green coffee record".to_string(),
+    })?;
+
+    if let Some(currency) = query.currency {
+        let fx = FxTable::load(&db).await?;
+        convert_green(&fx, &mut green, currency)?;
     }
+
+    Ok(Json(green))
 }
 
 // POST /greens - Create new green coffee
@@ -44,11 +117,18 @@ pub async fn create_green(
     Json(payload): Json<CreateGreenCoffeeRequest>,
 ) -> ApiResult<Json<GreenCoffee>> {
     let green_coffee: GreenCoffee = payload.into();
+    validate_green(&green_coffee)?;
+    if let Some(supplier) = &green_coffee.supplier {
+        ensure_supplier_exists(&db, supplier).await?;
+    }
 
     let created: Option<GreenCoffee> = db.create(table_name()).content(green_coffee).await?;
 
     match created {
-        Some(green) => Ok(Json(green)),
+        Some(green) => {
+            relate_green(&db, &green).await?;
+            Ok(Json(green))
+        }
         None => Err(ApiError::Internal {
             message: "Failed to create green coffee record".to_string(),
         }),
@@ -90,27 +170,59 @@ pub async fn update_green(
     if let Some(harvest_year) = payload.harvest_year {
         green.harvest_year = Some(harvest_year);
     }
+    if let Some(arrival_date) = payload.arrival_date {
+        green.arrival_date = Some(arrival_date);
+    }
     if let Some(stock_grams) = payload.stock_grams {
         green.stock_grams = stock_grams;
     }
     if let Some(price_per_kg) = payload.price_per_kg {
         green.price_per_kg = Some(price_per_kg);
     }
-    if let Some(price_currency) = payload.price_currency {
-        green.price_currency = Some(price_currency);
+    if let Some(producer) = payload.producer {
+        green.producer = Some(producer);
+    }
+    if let Some(farm_gate_price_per_kg) = payload.farm_gate_price_per_kg {
+        green.farm_gate_price_per_kg = Some(farm_gate_price_per_kg);
+    }
+    if let Some(certifications) = payload.certifications {
+        green.certifications = certifications;
     }
     if let Some(supplier) = payload.supplier {
+        ensure_supplier_exists(&db, &supplier).await?;
         green.supplier = Some(supplier);
     }
     if let Some(cupping_notes) = payload.cupping_notes {
         green.cupping_notes = Some(cupping_notes);
     }
+    if let Some(reorder_point_grams) = payload.reorder_point_grams {
+        green.reorder_point_grams = Some(reorder_point_grams);
+    }
+    if let Some(reference_roast) = payload.reference_roast {
+        let roast: Option<Roast> = db.select(record_key(&reference_roast)).await?;
+        let roast = roast.ok_or_else(|| ApiError::NotFound {
+            message: format!("Roast '{}' not found", reference_roast),
+        })?;
+        if roast.green_coffee != green.id {
+            return Err(ApiError::BadRequest {
+                message: format!(
+                    "Roast '{}' was not roasted from this green coffee",
+                    reference_roast
+                ),
+            });
+        }
+        green.reference_roast = Some(reference_roast);
+    }
+    validate_green(&green)?;
     green.updated_at = Some(Utc::now());
 
     let updated: Option<GreenCoffee> = db.update(make_record_id(&id)).content(green).await?;
 
     match updated {
-        Some(green) => Ok(Json(green)),
+        Some(green) => {
+            relate_green(&db, &green).await?;
+            Ok(Json(green))
+        }
         None => Err(ApiError::Internal {
             message: "Failed to update green coffee record".to_string(),
         }),
@@ -122,9 +234,14 @@ pub async fn delete_green(State(db): State<Db>, Path(id): Path<String>) -> ApiRe
     let deleted: Option<GreenCoffee> = db.delete(make_record_id(&id)).await?;
 
     match deleted {
-        Some(_) => Ok(Json(
-            serde_json::json!({"message": "Green coffee deleted successfully"}),
-        )),
+        Some(green) => {
+            db.query("DELETE green_measurement WHERE green_coffee = $green")
+                .bind(("green", green.id))
+                .await?;
+            Ok(Json(
+                serde_json::json!({"message": "Green coffee deleted successfully"}),
+            ))
+        }
         None => Err(ApiError::NotFound {
             message: format!("Green coffee with id '{}' not found", id),
         }),
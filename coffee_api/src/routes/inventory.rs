@@ -0,0 +1,16 @@
+use crate::db::Db;
+use crate::error::ApiResult;
+use crate::models::InventoryMovement;
+use axum::{extract::State, response::Json};
+
+// GET /inventory-movements - List all stock movements, newest first
+pub async fn list_inventory_movements(
+    State(db): State<Db>,
+) -> ApiResult<Json<Vec<InventoryMovement>>> {
+    let mut response = db
+        .query("SELECT * FROM inventory_movement ORDER BY created_at DESC")
+        .await?;
+    let movements: Vec<InventoryMovement> = response.take(0)?;
+
+    Ok(Json(movements))
+}
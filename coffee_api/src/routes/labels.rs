@@ -0,0 +1,216 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::freshness::load_freshness_settings;
+use crate::labels::{MAX_LABELS_PER_RUN, build_label, render_pdf, render_zpl, validate_template};
+use crate::models::{
+    CreateLabelTemplateRequest, LabelFormat, LabelItem, LabelTemplate, PrintLabelsRequest,
+    UpdateLabelTemplateRequest,
+};
+use crate::routes::orders::build_pick_list;
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+use surrealdb::sql::Thing;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "label_template".to_string()
+}
+
+// Helper function to create SurrealDB record ID
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+async fn find_label_template(db: &Db, id: &str) -> ApiResult<LabelTemplate> {
+    let existing: Option<LabelTemplate> = db.select(make_record_id(id)).await?;
+
+    existing.ok_or_else(|| ApiError::NotFound {
+        message: format!("Label template with id '{}' not found", id),
+    })
+}
+
+/// The template at `id`, or the standard layout when none is given.
+async fn load_template(db: &Db, id: Option<&Thing>) -> ApiResult<LabelTemplate> {
+    match id {
+        Some(id) => find_label_template(db, &record_key(id).1).await,
+        None => Ok(LabelTemplate::default()),
+    }
+}
+
+/// Labels for `items` in the requested format.
+async fn print(
+    db: &Db,
+    template: &LabelTemplate,
+    format: LabelFormat,
+    items: &[LabelItem],
+) -> ApiResult<Response> {
+    if items.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Nothing to print labels for".to_string(),
+        });
+    }
+    let total: i64 = items.iter().map(|item| i64::from(item.quantity)).sum();
+    if total > MAX_LABELS_PER_RUN {
+        return Err(ApiError::BadRequest {
+            message: format!(
+                "{} labels asked for, at most {} can be printed at once",
+                total, MAX_LABELS_PER_RUN
+            ),
+        });
+    }
+    let freshness = load_freshness_settings(db).await?;
+    let mut labels = Vec::with_capacity(items.len());
+    for item in items {
+        labels.push(build_label(db, template, &freshness, item).await?);
+    }
+
+    Ok(match format {
+        LabelFormat::Pdf => (
+            [(header::CONTENT_TYPE, "application/pdf")],
+            render_pdf(template, &labels),
+        )
+            .into_response(),
+        LabelFormat::Zpl => (
+            [(header::CONTENT_TYPE, "application/x-zpl")],
+            render_zpl(template, &labels),
+        )
+            .into_response(),
+        LabelFormat::Json => Json(labels).into_response(),
+    })
+}
+
+// GET /label-templates - List label layouts
+pub async fn list_label_templates(State(db): State<Db>) -> ApiResult<Json<Vec<LabelTemplate>>> {
+    let templates: Vec<LabelTemplate> = db.select(table_name()).await?;
+
+    Ok(Json(templates))
+}
+
+// GET /label-templates/:id - Get specific label layout
+pub async fn get_label_template(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<LabelTemplate>> {
+    Ok(Json(find_label_template(&db, &id).await?))
+}
+
+// POST /label-templates - Create new label layout
+pub async fn create_label_template(
+    State(db): State<Db>,
+    Json(payload): Json<CreateLabelTemplateRequest>,
+) -> ApiResult<Json<LabelTemplate>> {
+    let template: LabelTemplate = payload.into();
+    validate_template(&template).map_err(|message| ApiError::BadRequest { message })?;
+
+    let created: Option<LabelTemplate> = db.create(table_name()).content(template).await?;
+
+    match created {
+        Some(template) => Ok(Json(template)),
+        None => Err(ApiError::Internal {
+            message: "Failed to create label template record".to_string(),
+        }),
+    }
+}
+
+// PUT /label-templates/:id - Update label layout
+pub async fn update_label_template(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateLabelTemplateRequest>,
+) -> ApiResult<Json<LabelTemplate>> {
+    let mut template = find_label_template(&db, &id).await?;
+
+    // Update fields if provided
+    if let Some(name) = payload.name {
+        template.name = name;
+    }
+    if let Some(width_mm) = payload.width_mm {
+        template.width_mm = width_mm;
+    }
+    if let Some(height_mm) = payload.height_mm {
+        template.height_mm = height_mm;
+    }
+    if let Some(dpi) = payload.dpi {
+        template.dpi = dpi;
+    }
+    if let Some(lines) = payload.lines {
+        template.lines = lines;
+    }
+    if let Some(show_barcode) = payload.show_barcode {
+        template.show_barcode = show_barcode;
+    }
+    validate_template(&template).map_err(|message| ApiError::BadRequest { message })?;
+    template.updated_at = Some(Utc::now());
+
+    let updated: Option<LabelTemplate> = db.update(make_record_id(&id)).content(template).await?;
+
+    match updated {
+        Some(template) => Ok(Json(template)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update label template record".to_string(),
+        }),
+    }
+}
+
+// DELETE /label-templates/:id - Delete label layout
+pub async fn delete_label_template(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let deleted: Option<LabelTemplate> = db.delete(make_record_id(&id)).await?;
+
+    match deleted {
+        Some(_) => Ok(Json(
+            serde_json::json!({"message": "Label template deleted successfully"}),
+        )),
+        None => Err(ApiError::NotFound {
+            message: format!("Label template with id '{}' not found", id),
+        }),
+    }
+}
+
+// POST /labels - Print labels for the given bags as PDF, ZPL or JSON
+pub async fn print_labels(
+    State(db): State<Db>,
+    Json(payload): Json<PrintLabelsRequest>,
+) -> ApiResult<Response> {
+    let template = load_template(&db, payload.template.as_ref()).await?;
+
+    print(&db, &template, payload.format, &payload.items).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PickListLabelsQuery {
+    pub template: Option<String>,
+    #[serde(default)]
+    pub format: LabelFormat,
+}
+
+// GET /pick-list/labels?template=ID&format=pdf|zpl|json - Labels for every bag on the current pick list
+pub async fn print_pick_list_labels(
+    State(db): State<Db>,
+    Query(query): Query<PickListLabelsQuery>,
+) -> ApiResult<Response> {
+    let template = match &query.template {
+        Some(id) => find_label_template(&db, id).await?,
+        None => LabelTemplate::default(),
+    };
+    let items: Vec<LabelItem> = build_pick_list(&db)
+        .await?
+        .into_iter()
+        .map(|line| LabelItem {
+            product: line.product,
+            variant: Some(line.variant),
+            roast: None,
+            quantity: line.quantity,
+        })
+        .collect();
+
+    print(&db, &template, query.format, &items).await
+}
@@ -1,9 +1,53 @@
+pub mod alerts;
+pub mod analytics;
+pub mod barcodes;
+pub mod costing;
+pub mod crop_age;
+pub mod cupping;
+pub mod customers;
+pub mod flavours;
+pub mod freshness;
+pub mod fx_rates;
+pub mod green_measurements;
 pub mod greens;
 pub mod health;
+pub mod inventory;
+pub mod labels;
+pub mod orders;
+pub mod payments;
+pub mod price_lists;
+pub mod production;
 pub mod products;
+pub mod purchase_orders;
+pub mod roast_profiles;
 pub mod roasts;
+pub mod subscriptions;
+pub mod suppliers;
+pub mod trace;
 
+pub use alerts::*;
+pub use analytics::*;
+pub use barcodes::*;
+pub use costing::*;
+pub use crop_age::*;
+pub use cupping::*;
+pub use customers::*;
+pub use flavours::*;
+pub use freshness::*;
+pub use fx_rates::*;
+pub use green_measurements::*;
 pub use greens::*;
 pub use health::*;
+pub use inventory::*;
+pub use labels::*;
+pub use orders::*;
+pub use payments::*;
+pub use price_lists::*;
+pub use production::*;
 pub use products::*;
+pub use purchase_orders::*;
+pub use roast_profiles::*;
 pub use roasts::*;
+pub use subscriptions::*;
+pub use suppliers::*;
+pub use trace::*;
@@ -0,0 +1,475 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::fx::FxTable;
+use crate::models::{
+    CreateOrderRequest, Customer, InventoryMovement, MovementReason, Order, OrderLine, OrderStatus,
+    Payment, PaymentStatus, PickListLine, Product, UpdateOrderStatusRequest,
+};
+use crate::payments::PaymentProvider;
+use crate::pricing::resolve_price;
+use crate::routes::payments::{apply_payment_event, payment_for_order};
+use crate::routes::products::find_variant;
+use crate::trace::relate_order;
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::Utc;
+use coffee_shared::Money;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use surrealdb::sql::Thing;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "order".to_string()
+}
+
+// Helper function to create SurrealDB record ID
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+async fn find_order(db: &Db, id: &str) -> ApiResult<Order> {
+    let existing: Option<Order> = db.select(make_record_id(id)).await?;
+
+    existing.ok_or_else(|| ApiError::NotFound {
+        message: format!("Order with id '{}' not found", id),
+    })
+}
+
+/// Product, variant and quantity, keyed by the product record id and variant
+/// so the lines come out in a stable order.
+type VariantQuantities = BTreeMap<(String, Option<String>), (Thing, Option<String>, i32)>;
+
+/// Sums order line quantities per product variant.
+fn quantities_by_variant<'a>(lines: impl IntoIterator<Item = &'a OrderLine>) -> VariantQuantities {
+    let mut quantities = VariantQuantities::new();
+    for line in lines {
+        quantities
+            .entry((line.product.to_string(), line.variant.clone()))
+            .or_insert_with(|| (line.product.clone(), line.variant.clone(), 0))
+            .2 += line.quantity;
+    }
+    quantities
+}
+
+/// Loads every product ordered on `lines` once, however many of its
+/// variants were ordered.
+async fn ordered_products(db: &Db, lines: &[OrderLine]) -> ApiResult<Vec<(Thing, Product)>> {
+    let mut products: Vec<(Thing, Product)> = Vec::new();
+    for line in lines {
+        if products.iter().any(|(id, _)| *id == line.product) {
+            continue;
+        }
+        let product: Option<Product> = db.select(record_key(&line.product)).await?;
+        let product = product.ok_or_else(|| ApiError::NotFound {
+            message: format!("Product '{}' not found", line.product),
+        })?;
+        products.push((line.product.clone(), product));
+    }
+    Ok(products)
+}
+
+async fn save_products(db: &Db, products: Vec<(Thing, Product)>) -> ApiResult<()> {
+    for (product_id, mut product) in products {
+        product.updated_at = Some(Utc::now());
+        let _: Option<Product> = db.update(record_key(&product_id)).content(product).await?;
+    }
+    Ok(())
+}
+
+/// Takes the shipped units out of variant stock and records a movement for
+/// each variant. Every variant is checked before any stock is touched, and
+/// only stock not reserved for other orders can ship.
+async fn ship_order_stock(db: &Db, order: &Order) -> ApiResult<()> {
+    let mut products = ordered_products(db, &order.lines).await?;
+    let quantities = quantities_by_variant(&order.lines);
+    for (product_id, key, quantity) in quantities.values() {
+        let Some((_, product)) = products.iter().find(|(id, _)| id == product_id) else {
+            continue;
+        };
+        let variant = find_variant(product, key.as_deref())?;
+        // Stock held for other orders is not ours to ship
+        let own_reservation = if order.stock_reserved { *quantity } else { 0 };
+        let available = variant.available_units() + own_reservation;
+        if available < *quantity {
+            return Err(ApiError::Conflict {
+                message: format!(
+                    "Product '{}' has {} units of '{}' available, {} needed",
+                    product_id, available, variant.key, quantity
+                ),
+            });
+        }
+    }
+
+    for (product_id, key, quantity) in quantities.into_values() {
+        let Some(variant) = products
+            .iter_mut()
+            .find(|(id, _)| *id == product_id)
+            .and_then(|(_, product)| product.variant_mut(key.as_deref()))
+        else {
+            continue;
+        };
+        variant.stock_units -= quantity;
+        if order.stock_reserved {
+            variant.reserved_units = (variant.reserved_units - quantity).max(0);
+        }
+
+        let movement = InventoryMovement {
+            id: None,
+            item: product_id,
+            variant: Some(variant.key.clone()),
+            quantity: -(quantity as f64),
+            reason: MovementReason::Shipment,
+            reference: order.id.clone(),
+            created_at: None,
+        };
+        let _: Option<InventoryMovement> =
+            db.create("inventory_movement").content(movement).await?;
+    }
+
+    save_products(db, products).await
+}
+
+// GET /orders - List all orders
+pub async fn list_orders(State(db): State<Db>) -> ApiResult<Json<Vec<Order>>> {
+    let orders: Vec<Order> = db.select("order").await?;
+
+    Ok(Json(orders))
+}
+
+// GET /orders/:id - Get specific order
+pub async fn get_order(State(db): State<Db>, Path(id): Path<String>) -> ApiResult<Json<Order>> {
+    Ok(Json(find_order(&db, &id).await?))
+}
+
+/// Validates a new order and prices each line for its customer and
+/// quantity, without saving it. Lines priced in another currency are
+/// converted at the latest exchange rate.
+pub async fn build_order(db: &Db, payload: CreateOrderRequest) -> ApiResult<Order> {
+    if payload.lines.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Order must contain at least one line".to_string(),
+        });
+    }
+
+    if let Some(customer) = &payload.customer {
+        let existing: Option<Customer> = db.select(record_key(customer)).await?;
+        match existing {
+            None => {
+                return Err(ApiError::NotFound {
+                    message: format!("Customer '{}' not found", customer),
+                });
+            }
+            Some(existing) if existing.erased_at.is_some() => {
+                return Err(ApiError::Conflict {
+                    message: format!("Customer '{}' has been erased", customer),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    let fx = FxTable::load(db).await?;
+    let mut currency = payload.currency;
+    let mut lines = Vec::with_capacity(payload.lines.len());
+    for line in payload.lines {
+        if line.quantity <= 0 {
+            return Err(ApiError::BadRequest {
+                message: format!("Quantity for product '{}' must be positive", line.product),
+            });
+        }
+
+        let product: Option<Product> = db.select(record_key(&line.product)).await?;
+        let product = product.ok_or_else(|| ApiError::NotFound {
+            message: format!("Product '{}' not found", line.product),
+        })?;
+
+        let variant = find_variant(&product, line.variant.as_deref())?;
+
+        let currency = *currency.get_or_insert(variant.price.currency);
+        let (unit_price, _) = resolve_price(
+            db,
+            payload.customer.as_ref(),
+            &product,
+            variant,
+            line.quantity,
+        )
+        .await?;
+        let unit_price = fx.convert(&unit_price, currency)?;
+        lines.push(OrderLine {
+            product: line.product,
+            variant: Some(variant.key.clone()),
+            quantity: line.quantity,
+            unit_price,
+        });
+    }
+
+    let currency = currency.ok_or_else(|| ApiError::BadRequest {
+        message: "Order currency could not be determined".to_string(),
+    })?;
+    let mut total = Money::zero(currency);
+    for line in &lines {
+        total = total.checked_add(&line.unit_price.times(line.quantity)?)?;
+    }
+
+    Ok(Order {
+        id: None,
+        customer: payload.customer,
+        subscription: None,
+        total: total.rounded(),
+        lines,
+        status: OrderStatus::Pending,
+        stock_reserved: false,
+        carrier: None,
+        tracking_number: None,
+        shipped_at: None,
+        created_at: None,
+        updated_at: None,
+    })
+}
+
+pub async fn insert_order(db: &Db, order: Order) -> ApiResult<Order> {
+    let created: Option<Order> = db.create(table_name()).content(order).await?;
+    let order = created.ok_or_else(|| ApiError::Internal {
+        message: "Failed to create order record".to_string(),
+    })?;
+    relate_order(db, &order).await?;
+
+    Ok(order)
+}
+
+/// Holds back stock for every line of `order` if all of it is available.
+/// Returns whether the reservation was made; nothing is reserved otherwise.
+pub async fn reserve_order_stock(db: &Db, order: &Order) -> ApiResult<bool> {
+    let mut products = match ordered_products(db, &order.lines).await {
+        Ok(products) => products,
+        Err(ApiError::NotFound { .. }) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let quantities = quantities_by_variant(&order.lines);
+    for (product_id, key, quantity) in quantities.values() {
+        let available = products
+            .iter()
+            .find(|(id, _)| id == product_id)
+            .and_then(|(_, product)| product.variant(key.as_deref()))
+            .map(|variant| variant.available_units());
+        if available.is_none_or(|available| available < *quantity) {
+            return Ok(false);
+        }
+    }
+
+    for (product_id, key, quantity) in quantities.into_values() {
+        if let Some(variant) = products
+            .iter_mut()
+            .find(|(id, _)| *id == product_id)
+            .and_then(|(_, product)| product.variant_mut(key.as_deref()))
+        {
+            variant.reserved_units += quantity;
+        }
+    }
+    save_products(db, products).await?;
+    Ok(true)
+}
+
+/// Returns reserved stock for an order that will no longer ship.
+pub(crate) async fn release_order_stock(db: &Db, order: &Order) -> ApiResult<()> {
+    let mut products = Vec::new();
+    for (product_id, key, quantity) in quantities_by_variant(&order.lines).into_values() {
+        if !products.iter().any(|(id, _)| *id == product_id) {
+            let product: Option<Product> = db.select(record_key(&product_id)).await?;
+            let Some(product) = product else {
+                continue;
+            };
+            products.push((product_id.clone(), product));
+        }
+        if let Some(variant) = products
+            .iter_mut()
+            .find(|(id, _)| *id == product_id)
+            .and_then(|(_, product)| product.variant_mut(key.as_deref()))
+        {
+            variant.reserved_units = (variant.reserved_units - quantity).max(0);
+        }
+    }
+    save_products(db, products).await
+}
+
+// POST /orders - Create new pending order at the customer's effective prices
+pub async fn create_order(
+    State(db): State<Db>,
+    Json(payload): Json<CreateOrderRequest>,
+) -> ApiResult<Json<Order>> {
+    let order = build_order(&db, payload).await?;
+
+    Ok(Json(insert_order(&db, order).await?))
+}
+
+// POST /orders/:id/pay - Authorize and capture payment for a pending order
+pub async fn pay_order(
+    State(db): State<Db>,
+    State(payments): State<Arc<dyn PaymentProvider>>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Order>> {
+    let order = find_order(&db, &id).await?;
+    if !order.status.can_transition_to(OrderStatus::Paid) {
+        return Err(ApiError::Conflict {
+            message: format!("Order '{}' cannot be paid while {:?}", id, order.status),
+        });
+    }
+
+    // A payment authorized by an earlier attempt whose capture failed is
+    // captured now rather than left holding the customer's funds
+    let reference = match payment_for_order(&db, &order, PaymentStatus::Authorized).await {
+        Ok(payment) => payment.provider_reference,
+        Err(ApiError::NotFound { .. }) => {
+            let authorization = payments.authorize(&order.total, &id).await?;
+            let payment = Payment {
+                id: None,
+                order: order.id.clone().ok_or_else(|| ApiError::Internal {
+                    message: "Order record has no id".to_string(),
+                })?,
+                provider: payments.name().to_string(),
+                provider_reference: authorization.reference.clone(),
+                amount: order.total,
+                status: PaymentStatus::Authorized,
+                created_at: None,
+                updated_at: None,
+            };
+            let created: Option<Payment> = db.create("payment").content(payment).await?;
+            if created.is_none() {
+                return Err(ApiError::Internal {
+                    message: "Failed to create payment record".to_string(),
+                });
+            }
+            authorization.reference
+        }
+        Err(e) => return Err(e),
+    };
+
+    let captured = payments.capture(&reference).await?;
+    let order = apply_payment_event(&db, payments.as_ref(), &captured).await?;
+
+    Ok(Json(order))
+}
+
+// POST /orders/:id/refund - Refund the captured payment of a paid order
+pub async fn refund_order(
+    State(db): State<Db>,
+    State(payments): State<Arc<dyn PaymentProvider>>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Order>> {
+    let order = find_order(&db, &id).await?;
+    if !order.status.can_transition_to(OrderStatus::Refunded) {
+        return Err(ApiError::Conflict {
+            message: format!("Order '{}' cannot be refunded while {:?}", id, order.status),
+        });
+    }
+
+    let payment = payment_for_order(&db, &order, PaymentStatus::Captured).await?;
+    let refunded = payments
+        .refund(&payment.provider_reference, &payment.amount)
+        .await?;
+    let order = apply_payment_event(&db, payments.as_ref(), &refunded).await?;
+
+    Ok(Json(order))
+}
+
+// PUT /orders/:id/status - Move an order through fulfilment
+pub async fn update_order_status(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateOrderStatusRequest>,
+) -> ApiResult<Json<Order>> {
+    let mut order = find_order(&db, &id).await?;
+
+    if matches!(payload.status, OrderStatus::Paid | OrderStatus::Refunded) {
+        return Err(ApiError::BadRequest {
+            message: "Paid and refunded are set by payment events".to_string(),
+        });
+    }
+    if !order.status.can_transition_to(payload.status) {
+        return Err(ApiError::Conflict {
+            message: format!(
+                "Order '{}' cannot move from {:?} to {:?}",
+                id, order.status, payload.status
+            ),
+        });
+    }
+
+    if payload.status == OrderStatus::Shipped {
+        let tracking_number = payload
+            .tracking_number
+            .filter(|t| !t.trim().is_empty())
+            .ok_or_else(|| ApiError::BadRequest {
+                message: "A tracking number is required to ship an order".to_string(),
+            })?;
+
+        ship_order_stock(&db, &order).await?;
+        order.tracking_number = Some(tracking_number);
+        order.carrier = payload.carrier;
+        order.shipped_at = Some(Utc::now());
+        order.stock_reserved = false;
+    }
+    if payload.status == OrderStatus::Cancelled && order.stock_reserved {
+        release_order_stock(&db, &order).await?;
+        order.stock_reserved = false;
+    }
+    order.status = payload.status;
+    order.updated_at = Some(Utc::now());
+
+    let updated: Option<Order> = db.update(make_record_id(&id)).content(order).await?;
+
+    match updated {
+        Some(order) => Ok(Json(order)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update order record".to_string(),
+        }),
+    }
+}
+
+// GET /pick-list - Product variants to pick for every order currently being picked
+pub async fn get_pick_list(State(db): State<Db>) -> ApiResult<Json<Vec<PickListLine>>> {
+    Ok(Json(build_pick_list(&db).await?))
+}
+
+/// Bags to pick for every order being picked, one line per product variant.
+pub(crate) async fn build_pick_list(db: &Db) -> ApiResult<Vec<PickListLine>> {
+    let mut response = db
+        .query("SELECT * FROM order WHERE status = $status ORDER BY created_at")
+        .bind(("status", OrderStatus::Picking))
+        .await?;
+    let orders: Vec<Order> = response.take(0)?;
+
+    let mut pick_list = Vec::new();
+    for (product_id, key, quantity) in
+        quantities_by_variant(orders.iter().flat_map(|order| &order.lines)).into_values()
+    {
+        let product: Option<Product> = db.select(record_key(&product_id)).await?;
+        let product = product.ok_or_else(|| ApiError::NotFound {
+            message: format!("Product '{}' not found", product_id),
+        })?;
+        let variant = find_variant(&product, key.as_deref())?;
+
+        pick_list.push(PickListLine {
+            orders: orders
+                .iter()
+                .filter(|order| {
+                    order
+                        .lines
+                        .iter()
+                        .any(|line| line.product == product_id && line.variant == key)
+                })
+                .filter_map(|order| order.id.clone())
+                .collect(),
+            product: product_id,
+            variant: variant.key.clone(),
+            name: product.variant_name(variant),
+            package_size_grams: variant.package_size_grams,
+            grind: variant.grind,
+            quantity,
+        });
+    }
+
+    Ok(pick_list)
+}
@@ -0,0 +1,125 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{Order, OrderStatus, Payment, PaymentStatus};
+use crate::payments::{PaymentEvent, PaymentProvider};
+use crate::routes::orders::release_order_stock;
+use axum::{body::Bytes, extract::State, http::HeaderMap, response::Json};
+use chrono::Utc;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Finds the payment of `order` that is currently in `status`.
+pub async fn payment_for_order(
+    db: &Db,
+    order: &Order,
+    status: PaymentStatus,
+) -> ApiResult<Payment> {
+    let mut response = db
+        .query("SELECT * FROM payment WHERE order = $order AND status = $status LIMIT 1")
+        .bind(("order", order.id.clone()))
+        .bind(("status", status))
+        .await?;
+    let payment: Option<Payment> = response.take(0)?;
+
+    payment.ok_or_else(|| ApiError::NotFound {
+        message: format!("No {:?} payment found for order", status),
+    })
+}
+
+/// Records a provider event against its payment and moves the owning order
+/// along: captured payments mark the order paid, refunds mark it refunded
+/// and give back any stock it held. A capture that lands after the order was
+/// cancelled is refunded through `payments` straight away. Events that are
+/// repeated or arrive out of order leave both untouched.
+pub async fn apply_payment_event(
+    db: &Db,
+    payments: &dyn PaymentProvider,
+    event: &PaymentEvent,
+) -> ApiResult<Order> {
+    let mut response = db
+        .query("SELECT * FROM payment WHERE provider_reference = $reference LIMIT 1")
+        .bind(("reference", event.reference.clone()))
+        .await?;
+    let payment: Option<Payment> = response.take(0)?;
+    let mut payment = payment.ok_or_else(|| ApiError::NotFound {
+        message: format!("Payment '{}' not found", event.reference),
+    })?;
+
+    let order: Option<Order> = db.select(record_key(&payment.order)).await?;
+    let mut order = order.ok_or_else(|| ApiError::NotFound {
+        message: format!("Order '{}' not found", payment.order),
+    })?;
+
+    if !payment.status.can_transition_to(event.status) {
+        return Ok(order);
+    }
+
+    let mut status = event.status;
+    if status == PaymentStatus::Captured && order.status == OrderStatus::Cancelled {
+        status = payments
+            .refund(&payment.provider_reference, &payment.amount)
+            .await?
+            .status;
+    }
+    let next = match status {
+        PaymentStatus::Captured => Some(OrderStatus::Paid),
+        PaymentStatus::Refunded => Some(OrderStatus::Refunded),
+        PaymentStatus::Authorized | PaymentStatus::Failed => None,
+    };
+    // Nothing is written unless the order can follow the payment
+    if let Some(next) = next
+        && !order.status.can_transition_to(next)
+    {
+        return Err(ApiError::Conflict {
+            message: format!("Order cannot move from {:?} to {:?}", order.status, next),
+        });
+    }
+
+    let payment_id = payment.id.clone().ok_or_else(|| ApiError::Internal {
+        message: "Payment record has no id".to_string(),
+    })?;
+    payment.status = status;
+    payment.updated_at = Some(Utc::now());
+    let _: Option<Payment> = db.update(record_key(&payment_id)).content(payment).await?;
+
+    let Some(next) = next else {
+        return Ok(order);
+    };
+    let order_id = order.id.clone().ok_or_else(|| ApiError::Internal {
+        message: "Order record has no id".to_string(),
+    })?;
+    if next == OrderStatus::Refunded && order.stock_reserved {
+        release_order_stock(db, &order).await?;
+        order.stock_reserved = false;
+    }
+    order.status = next;
+    order.updated_at = Some(Utc::now());
+    let updated: Option<Order> = db.update(record_key(&order_id)).content(order).await?;
+
+    updated.ok_or_else(|| ApiError::Internal {
+        message: "Failed to update order record".to_string(),
+    })
+}
+
+// GET /payments - List all payments
+pub async fn list_payments(State(db): State<Db>) -> ApiResult<Json<Vec<Payment>>> {
+    let payments: Vec<Payment> = db.select("payment").await?;
+
+    Ok(Json(payments))
+}
+
+// POST /payments/webhook - Receive a signed event from the payment provider
+pub async fn payment_webhook(
+    State(db): State<Db>,
+    State(payments): State<Arc<dyn PaymentProvider>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<Json<Value>> {
+    // Verified events that need no action are still acknowledged, so the
+    // provider does not keep retrying them
+    if let Some(event) = payments.verify_webhook(&headers, &body)? {
+        apply_payment_event(&db, payments.as_ref(), &event).await?;
+    }
+
+    Ok(Json(serde_json::json!({"received": true})))
+}
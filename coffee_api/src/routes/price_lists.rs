@@ -0,0 +1,186 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    CreatePriceListRequest, Customer, PriceList, PriceListKind, PriceQuote, PriceQuoteRequest,
+    Product, UpdatePriceListRequest,
+};
+use crate::pricing::resolve_price;
+use crate::routes::products::find_variant;
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde_json::Value;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "price_list".to_string()
+}
+
+// Helper function to create SurrealDB record ID
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+fn validate_price_list(price_list: &PriceList) -> ApiResult<()> {
+    match (price_list.kind, &price_list.customer) {
+        (PriceListKind::Customer, None) => {
+            return Err(ApiError::BadRequest {
+                message: "Customer price lists must name a customer".to_string(),
+            });
+        }
+        (PriceListKind::Retail | PriceListKind::Wholesale, Some(_)) => {
+            return Err(ApiError::BadRequest {
+                message: "Only customer price lists can name a customer".to_string(),
+            });
+        }
+        _ => {}
+    }
+
+    for entry in &price_list.entries {
+        if entry.min_quantity <= 0 || entry.price.is_sign_negative() {
+            return Err(ApiError::BadRequest {
+                message: format!(
+                    "Invalid price break for product '{}': quantities start at 1 and prices cannot be negative",
+                    entry.product
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+// GET /price-lists - List all price lists
+pub async fn list_price_lists(State(db): State<Db>) -> ApiResult<Json<Vec<PriceList>>> {
+    let price_lists: Vec<PriceList> = db.select("price_list").await?;
+
+    Ok(Json(price_lists))
+}
+
+// GET /price-lists/:id - Get specific price list
+pub async fn get_price_list(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<PriceList>> {
+    match db.select(make_record_id(&id)).await? {
+        Some(price_list) => Ok(Json(price_list)),
+        None => Err(ApiError::NotFound {
+            message: "Failed to get price list record".to_string(),
+        }),
+    }
+}
+
+// POST /price-lists - Create new price list
+pub async fn create_price_list(
+    State(db): State<Db>,
+    Json(payload): Json<CreatePriceListRequest>,
+) -> ApiResult<Json<PriceList>> {
+    let price_list: PriceList = payload.into();
+    validate_price_list(&price_list)?;
+
+    let created: Option<PriceList> = db.create(table_name()).content(price_list).await?;
+
+    match created {
+        Some(price_list) => Ok(Json(price_list)),
+        None => Err(ApiError::Internal {
+            message: "Failed to create price list record".to_string(),
+        }),
+    }
+}
+
+// PUT /price-lists/:id - Update price list
+pub async fn update_price_list(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdatePriceListRequest>,
+) -> ApiResult<Json<PriceList>> {
+    // First check if the record exists
+    let existing: Option<PriceList> = db.select(make_record_id(&id)).await?;
+
+    let mut price_list = existing.ok_or_else(|| ApiError::NotFound {
+        message: format!("Price list with id '{}' not found", id),
+    })?;
+
+    // Update fields if provided
+    if let Some(name) = payload.name {
+        price_list.name = name;
+    }
+    if let Some(entries) = payload.entries {
+        price_list.entries = entries;
+    }
+    validate_price_list(&price_list)?;
+    price_list.updated_at = Some(Utc::now());
+
+    let updated: Option<PriceList> = db.update(make_record_id(&id)).content(price_list).await?;
+
+    match updated {
+        Some(price_list) => Ok(Json(price_list)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update price list record".to_string(),
+        }),
+    }
+}
+
+// DELETE /price-lists/:id - Delete price list
+pub async fn delete_price_list(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let deleted: Option<PriceList> = db.delete(make_record_id(&id)).await?;
+
+    match deleted {
+        Some(_) => Ok(Json(
+            serde_json::json!({"message": "Price list deleted successfully"}),
+        )),
+        None => Err(ApiError::NotFound {
+            message: format!("Price list with id '{}' not found", id),
+        }),
+    }
+}
+
+// POST /price-quote - Effective price of a product for a customer and quantity
+pub async fn quote_price(
+    State(db): State<Db>,
+    Json(payload): Json<PriceQuoteRequest>,
+) -> ApiResult<Json<PriceQuote>> {
+    if payload.quantity <= 0 {
+        return Err(ApiError::BadRequest {
+            message: "Quantity must be positive".to_string(),
+        });
+    }
+
+    let product: Option<Product> = db.select(record_key(&payload.product)).await?;
+    let product = product.ok_or_else(|| ApiError::NotFound {
+        message: format!("Product '{}' not found", payload.product),
+    })?;
+    if let Some(customer) = &payload.customer {
+        let existing: Option<Customer> = db.select(record_key(customer)).await?;
+        if existing.is_none() {
+            return Err(ApiError::NotFound {
+                message: format!("Customer '{}' not found", customer),
+            });
+        }
+    }
+
+    let variant = find_variant(&product, payload.variant.as_deref())?;
+
+    let (unit_price, price_list) = resolve_price(
+        &db,
+        payload.customer.as_ref(),
+        &product,
+        variant,
+        payload.quantity,
+    )
+    .await?;
+
+    Ok(Json(PriceQuote {
+        product: payload.product,
+        variant: variant.key.clone(),
+        customer: payload.customer,
+        quantity: payload.quantity,
+        unit_price,
+        total: unit_price.times(payload.quantity)?.rounded(),
+        price_list,
+    }))
+}
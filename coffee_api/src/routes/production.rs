@@ -0,0 +1,274 @@
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    BlendMethod, GreenAvailability, GreenCoffee, Order, OrderStatus, Product, ProductDemand,
+    ProductionPlan, Roast, RoastRequirement,
+};
+use crate::routes::analytics::{PLAUSIBLE_LOSS_PERCENT, level_key};
+use crate::routes::subscriptions::{forecast_bags, forecast_until};
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use surrealdb::sql::Thing;
+
+/// Green batch the roaster takes when no capacity is given.
+const DEFAULT_BATCH_CAPACITY_GRAMS: f64 = 15_000.0;
+
+/// Loss assumed for a roast level nothing has been roasted at yet.
+const DEFAULT_LOSS_PERCENT: f64 = 15.0;
+
+/// Orders whose units are still in product stock.
+const OPEN_ORDER_STATUSES: [OrderStatus; 4] = [
+    OrderStatus::Pending,
+    OrderStatus::Paid,
+    OrderStatus::Picking,
+    OrderStatus::Packed,
+];
+
+#[derive(Debug, Deserialize)]
+pub struct ProductionPlanQuery {
+    /// Weeks of subscription orders to roast for. Defaults to 1.
+    pub weeks: Option<i64>,
+    /// Largest green batch the roaster takes, in grams.
+    pub batch_capacity_grams: Option<f64>,
+}
+
+fn average_loss_percent<'a>(roasts: impl Iterator<Item = &'a Roast>) -> Option<f64> {
+    let losses: Vec<f64> = roasts
+        .filter_map(Roast::loss_percent)
+        .filter(|loss| PLAUSIBLE_LOSS_PERCENT.contains(loss))
+        .collect();
+    (!losses.is_empty()).then(|| losses.iter().sum::<f64>() / losses.len() as f64)
+}
+
+/// Average loss of past roasts of `green` at `level`, falling back to all
+/// roasts at that level. Implausible losses are left out.
+fn typical_loss_percent(roasts: &[Roast], green: &Thing, level: &str) -> f64 {
+    let at_level = || {
+        roasts
+            .iter()
+            .filter(move |roast| level_key(&roast.roast_level) == level)
+    };
+
+    average_loss_percent(at_level().filter(|roast| roast.green_coffee.as_ref() == Some(green)))
+        .or_else(|| average_loss_percent(at_level()))
+        .unwrap_or(DEFAULT_LOSS_PERCENT)
+}
+
+/// The greens that go into `roast`, each with the level it is roasted to and
+/// its share of the roasted weight. Post-roast blends are followed into their
+/// component roasts.
+fn roast_inputs(roast: &Roast, roasts: &[Roast]) -> Vec<(Thing, String, f64)> {
+    match roast.blend {
+        None => roast
+            .green_coffee
+            .iter()
+            .map(|green| (green.clone(), roast.roast_level.clone(), 1.0))
+            .collect(),
+        Some(BlendMethod::PreRoast) => roast
+            .components
+            .iter()
+            .filter_map(|component| {
+                Some((
+                    component.green_coffee.clone()?,
+                    roast.roast_level.clone(),
+                    component.percent / 100.0,
+                ))
+            })
+            .collect(),
+        Some(BlendMethod::PostRoast) => roast
+            .components
+            .iter()
+            .filter_map(|component| {
+                let part = roasts
+                    .iter()
+                    .find(|part| part.id.is_some() && part.id == component.roast)?;
+                (part.blend != Some(BlendMethod::PostRoast)).then_some((part, component.percent))
+            })
+            .flat_map(|(part, percent)| {
+                roast_inputs(part, roasts)
+                    .into_iter()
+                    .map(move |(green, level, share)| (green, level, share * percent / 100.0))
+            })
+            .collect(),
+    }
+}
+
+// GET /production-plan?weeks=N&batch_capacity_grams=G - Roasts needed for open orders, subscriptions and stock targets
+pub async fn get_production_plan(
+    State(db): State<Db>,
+    Query(query): Query<ProductionPlanQuery>,
+) -> ApiResult<Json<ProductionPlan>> {
+    let weeks = query.weeks.unwrap_or(1);
+    if weeks <= 0 {
+        return Err(ApiError::BadRequest {
+            message: "Plan must cover at least one week".to_string(),
+        });
+    }
+    let batch_capacity_grams = query
+        .batch_capacity_grams
+        .unwrap_or(DEFAULT_BATCH_CAPACITY_GRAMS);
+    if !batch_capacity_grams.is_finite() || batch_capacity_grams <= 0.0 {
+        return Err(ApiError::BadRequest {
+            message: "Batch capacity must be positive".to_string(),
+        });
+    }
+
+    let products: Vec<Product> = db.select("product").await?;
+    let roasts: Vec<Roast> = db.select("roast").await?;
+    let greens: Vec<GreenCoffee> = db.select("green_coffee").await?;
+
+    let mut response = db
+        .query("SELECT * FROM order WHERE status IN $statuses")
+        .bind(("statuses", OPEN_ORDER_STATUSES))
+        .await?;
+    let orders: Vec<Order> = response.take(0)?;
+
+    let mut subscribed = Vec::new();
+    let mut roasters_choice_bags = 0;
+    for (item, bags) in forecast_bags(&db, forecast_until(weeks)?).await? {
+        match item {
+            Some(item) => subscribed.push((item, bags)),
+            None => roasters_choice_bags += bags,
+        }
+    }
+
+    let mut demands = Vec::new();
+    let mut requirements: BTreeMap<(String, String), RoastRequirement> = BTreeMap::new();
+    let mut unplanned_products = Vec::new();
+    for product in &products {
+        let Some(product_id) = product.id.clone() else {
+            continue;
+        };
+        let roast = product
+            .roast
+            .as_ref()
+            .and_then(|id| roasts.iter().find(|roast| roast.id.as_ref() == Some(id)));
+        let inputs = roast
+            .map(|roast| roast_inputs(roast, &roasts))
+            .unwrap_or_default();
+
+        for variant in &product.variants {
+            // Lines and subscriptions from before variants name none
+            let picks = |id: &Thing, key: Option<&str>| {
+                *id == product_id && product.variant(key).is_some_and(|v| v.key == variant.key)
+            };
+            let open: i32 = orders
+                .iter()
+                .flat_map(|order| &order.lines)
+                .filter(|line| picks(&line.product, line.variant.as_deref()))
+                .map(|line| line.quantity)
+                .sum();
+            let forecast: i32 = subscribed
+                .iter()
+                .filter(|((id, key), _)| picks(id, key.as_deref()))
+                .map(|(_, bags)| bags)
+                .sum();
+            let target = variant.target_stock_units.unwrap_or(0);
+            if open + forecast + target == 0 {
+                continue;
+            }
+
+            let units_to_make = (open + forecast + target - variant.stock_units).max(0);
+            let roasted_grams = units_to_make as f64 * variant.package_size_grams;
+            demands.push(ProductDemand {
+                product: product_id.clone(),
+                variant: variant.key.clone(),
+                name: product.variant_name(variant),
+                open_order_units: open,
+                forecast_units: forecast,
+                target_stock_units: target,
+                stock_units: variant.stock_units,
+                units_to_make,
+                roasted_grams,
+            });
+            if units_to_make == 0 {
+                continue;
+            }
+            if inputs.is_empty() {
+                if !unplanned_products.contains(&product_id) {
+                    unplanned_products.push(product_id.clone());
+                }
+                continue;
+            }
+
+            // Blends are planned as roasts of each of their greens
+            for (green_id, roast_level, share) in &inputs {
+                let level = level_key(roast_level);
+                let requirement = requirements
+                    .entry((green_id.to_string(), level.clone()))
+                    .or_insert_with(|| RoastRequirement {
+                        name: greens
+                            .iter()
+                            .find(|green| green.id.as_ref() == Some(green_id))
+                            .map(|green| green.name.clone())
+                            .unwrap_or_else(|| green_id.to_string()),
+                        expected_loss_percent: typical_loss_percent(&roasts, green_id, &level),
+                        green_coffee: green_id.clone(),
+                        roast_level: roast_level.clone(),
+                        products: Vec::new(),
+                        roasted_grams: 0.0,
+                        green_grams: 0.0,
+                        batches: 0,
+                        batch_size_grams: 0.0,
+                        short_of_green: false,
+                    });
+                if !requirement.products.contains(&product_id) {
+                    requirement.products.push(product_id.clone());
+                }
+                requirement.roasted_grams += roasted_grams * share;
+            }
+        }
+    }
+
+    // Work back from roasted to green weight and split into batches the
+    // roaster can take, all of a similar size
+    let mut availability: BTreeMap<String, GreenAvailability> = BTreeMap::new();
+    for requirement in requirements.values_mut() {
+        requirement.green_grams =
+            (requirement.roasted_grams / (1.0 - requirement.expected_loss_percent / 100.0)).ceil();
+        requirement.batches = (requirement.green_grams / batch_capacity_grams).ceil() as u32;
+        if requirement.batches > 0 {
+            requirement.batch_size_grams =
+                (requirement.green_grams / requirement.batches as f64).ceil();
+        }
+
+        let green_id = &requirement.green_coffee;
+        availability
+            .entry(green_id.to_string())
+            .or_insert_with(|| GreenAvailability {
+                green_coffee: green_id.clone(),
+                name: requirement.name.clone(),
+                required_grams: 0.0,
+                stock_grams: greens
+                    .iter()
+                    .find(|green| green.id.as_ref() == Some(green_id))
+                    .map(|green| green.stock_grams)
+                    .unwrap_or(0.0),
+                shortfall_grams: 0.0,
+            })
+            .required_grams += requirement.green_grams;
+    }
+    for green in availability.values_mut() {
+        green.shortfall_grams = (green.required_grams - green.stock_grams).max(0.0);
+    }
+    let mut roasts: Vec<RoastRequirement> = requirements.into_values().collect();
+    for requirement in &mut roasts {
+        requirement.short_of_green = availability
+            .get(&requirement.green_coffee.to_string())
+            .is_some_and(|green| green.shortfall_grams > 0.0);
+    }
+
+    Ok(Json(ProductionPlan {
+        weeks,
+        batch_capacity_grams,
+        products: demands,
+        roasts,
+        greens: availability.into_values().collect(),
+        roasters_choice_bags,
+        unplanned_products,
+    }))
+}
@@ -1,8 +1,19 @@
+use crate::barcode::normalize_gtin;
+use crate::certifications::{CertificationQuery, certify_products};
 use crate::db::Db;
 use crate::error::{ApiError, ApiResult};
-use crate::models::{CreateProductRequest, Product, UpdateProductRequest};
+use crate::flavours::{FlavourQuery, FlavourWheel};
+use crate::freshness::validate_rule;
+use crate::fx::{CurrencyQuery, FxTable};
+use crate::models::{
+    CreateProductRequest, CreateProductVariantRequest, GreenCoffee, Order, OrderStatus, Product,
+    ProductVariant, Roast, Subscription, SubscriptionStatus, UpdateProductRequest,
+    UpdateProductVariantRequest,
+};
+use crate::sku::{assign_skus, load_sku_settings};
+use crate::trace::relate_product;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::Json,
 };
 use chrono::Utc;
@@ -18,34 +29,278 @@ fn make_record_id(id: &str) -> (String, String) {
     (table_name(), id.to_string())
 }
 
-// GET /products - List all products
-pub async fn list_products(State(db): State<Db>) -> ApiResult<Json<Vec<Product>>> {
-    let products: Vec<Product> = db.select("product").await?;
+fn validate_product(product: &Product) -> ApiResult<()> {
+    if product.variants.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Product needs at least one variant".to_string(),
+        });
+    }
+    if let Some(rule) = &product.freshness {
+        validate_rule(rule).map_err(|message| ApiError::BadRequest { message })?;
+    }
+    for (i, variant) in product.variants.iter().enumerate() {
+        if product.variants[..i]
+            .iter()
+            .any(|other| other.key == variant.key)
+        {
+            return Err(ApiError::BadRequest {
+                message: format!("Product has more than one '{}' variant", variant.key),
+            });
+        }
+        if !variant.package_size_grams.is_finite() || variant.package_size_grams <= 0.0 {
+            return Err(ApiError::BadRequest {
+                message: "Package size must be positive".to_string(),
+            });
+        }
+        if variant.price.is_negative() {
+            return Err(ApiError::BadRequest {
+                message: "Product price cannot be negative".to_string(),
+            });
+        }
+        if variant.stock_units < 0 {
+            return Err(ApiError::BadRequest {
+                message: "Stock cannot be negative".to_string(),
+            });
+        }
+        if variant.stock_units < variant.reserved_units {
+            return Err(ApiError::Conflict {
+                message: format!(
+                    "Variant '{}' has {} units reserved for open orders",
+                    variant.key, variant.reserved_units
+                ),
+            });
+        }
+        if variant.target_stock_units.is_some_and(|target| target < 0) {
+            return Err(ApiError::BadRequest {
+                message: "Target stock cannot be negative".to_string(),
+            });
+        }
+        if variant.reorder_point_units.is_some_and(|units| units < 0) {
+            return Err(ApiError::BadRequest {
+                message: "Reorder point cannot be negative".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
 
-    Ok(Json(products))
+/// Normalizes the GTINs of `product`, checks that no other product uses its
+/// SKUs or GTINs, and generates the SKUs it is missing.
+async fn assign_codes(db: &Db, product: &mut Product) -> ApiResult<()> {
+    for variant in &mut product.variants {
+        if let Some(gtin) = &variant.gtin {
+            let gtin = normalize_gtin(gtin).map_err(|message| ApiError::BadRequest { message })?;
+            variant.gtin = Some(gtin);
+        }
+    }
+
+    let others: Vec<Product> = db.select(table_name()).await?;
+    let mut skus = Vec::new();
+    let mut gtins = Vec::new();
+    for other in others.iter().filter(|other| other.id != product.id) {
+        for variant in &other.variants {
+            skus.extend(variant.sku.clone());
+            gtins.extend(variant.gtin.clone());
+        }
+    }
+    for variant in &product.variants {
+        if let Some(sku) = &variant.sku {
+            if skus.contains(sku) {
+                return Err(ApiError::Conflict {
+                    message: format!("SKU '{}' is already in use", sku),
+                });
+            }
+            skus.push(sku.clone());
+        }
+        if let Some(gtin) = &variant.gtin {
+            if gtins.contains(gtin) {
+                return Err(ApiError::Conflict {
+                    message: format!("GTIN '{}' is already in use", gtin),
+                });
+            }
+            gtins.push(gtin.clone());
+        }
+    }
+
+    let settings = load_sku_settings(db).await?;
+    assign_skus(&settings, product, &mut skus);
+    Ok(())
 }
 
-// GET /products/:id - Get specific product
-pub async fn get_product(State(db): State<Db>, Path(id): Path<String>) -> ApiResult<Json<Product>> {
-    match db.select(make_record_id(&id)).await? {
-        Some(product) => Ok(Json(product)),
-        None => Err(ApiError::NotFound {
-            message: "Failed to get product record".to_string(),
+/// The variant of `product` picked by `key`. Products with a single variant
+/// need no key.
+pub(crate) fn find_variant<'a>(
+    product: &'a Product,
+    key: Option<&str>,
+) -> ApiResult<&'a ProductVariant> {
+    product.variant(key).ok_or_else(|| {
+        let product_id = product
+            .id
+            .as_ref()
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| product.name.clone());
+        match key {
+            Some(key) => ApiError::NotFound {
+                message: format!("Product '{}' has no '{}' variant", product_id, key),
+            },
+            None => ApiError::BadRequest {
+                message: format!("Choose a variant of product '{}'", product_id),
+            },
+        }
+    })
+}
+
+/// Applies the fields given in `update` to `variant`.
+fn update_variant(variant: &mut ProductVariant, update: UpdateProductVariantRequest) {
+    if let Some(sku) = update.sku {
+        variant.sku = Some(sku);
+    }
+    if let Some(gtin) = update.gtin {
+        variant.gtin = Some(gtin);
+    }
+    if let Some(price) = update.price {
+        variant.price = price;
+    }
+    if let Some(stock_units) = update.stock_units {
+        variant.stock_units = stock_units;
+    }
+    if let Some(target_stock_units) = update.target_stock_units {
+        variant.target_stock_units = Some(target_stock_units);
+    }
+    if let Some(reorder_point_units) = update.reorder_point_units {
+        variant.reorder_point_units = Some(reorder_point_units);
+    }
+}
+
+async fn find_product(db: &Db, id: &str) -> ApiResult<Product> {
+    let product: Option<Product> = db.select(make_record_id(id)).await?;
+
+    product.ok_or_else(|| ApiError::NotFound {
+        message: format!("Product with id '{}' not found", id),
+    })
+}
+
+async fn save_product(db: &Db, id: &str, mut product: Product) -> ApiResult<Product> {
+    validate_product(&product)?;
+    assign_codes(db, &mut product).await?;
+    product.updated_at = Some(Utc::now());
+
+    let updated: Option<Product> = db.update(make_record_id(id)).content(product).await?;
+
+    match updated {
+        Some(mut product) => {
+            relate_product(db, &product).await?;
+            certify_products(db, std::slice::from_mut(&mut product)).await?;
+            Ok(product)
+        }
+        None => Err(ApiError::Internal {
+            message: "Failed to update product record".to_string(),
         }),
     }
 }
 
+/// Whether the roast of `product`, or any green it was roasted from, has a
+/// note under `category`.
+fn product_has_flavour(
+    wheel: &FlavourWheel,
+    category: &str,
+    product: &Product,
+    roasts: &[Roast],
+    greens: &[GreenCoffee],
+) -> bool {
+    let Some(roast) = roasts
+        .iter()
+        .find(|roast| roast.id.is_some() && roast.id == product.roast)
+    else {
+        return false;
+    };
+    let green_notes = roast.greens().filter_map(|id| {
+        greens
+            .iter()
+            .find(|green| green.id.as_ref() == Some(id))
+            .and_then(|green| green.cupping_notes.as_ref())
+    });
+
+    roast
+        .notes
+        .iter()
+        .chain(green_notes)
+        .any(|notes| wheel.matches(notes, category))
+}
+
+// GET /products?currency=XXX&flavour=fruity > citrus&certification=organic - List all products, optionally with prices converted or narrowed to a flavour category or certification
+pub async fn list_products(
+    State(db): State<Db>,
+    Query(query): Query<CurrencyQuery>,
+    Query(filter): Query<FlavourQuery>,
+    Query(certified): Query<CertificationQuery>,
+) -> ApiResult<Json<Vec<Product>>> {
+    let mut products: Vec<Product> = db.select("product").await?;
+    certify_products(&db, &mut products).await?;
+
+    if let Some(certification) = certified.certification {
+        products.retain(|product| product.certifications.contains(&certification));
+    }
+
+    if let Some((wheel, category)) = filter.load(&db).await? {
+        let roasts: Vec<Roast> = db.select("roast").await?;
+        let greens: Vec<GreenCoffee> = db.select("green_coffee").await?;
+        products
+            .retain(|product| product_has_flavour(&wheel, &category, product, &roasts, &greens));
+    }
+
+    if let Some(currency) = query.currency {
+        let fx = FxTable::load(&db).await?;
+        for variant in products
+            .iter_mut()
+            .flat_map(|product| &mut product.variants)
+        {
+            variant.price = fx.convert(&variant.price, currency)?;
+        }
+    }
+
+    Ok(Json(products))
+}
+
+// GET /products/:id?currency=XXX - Get specific product, optionally with its price converted
+pub async fn get_product(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Query(query): Query<CurrencyQuery>,
+) -> ApiResult<Json<Product>> {
+    let product: Option<Product> = db.select(make_record_id(&id)).await?;
+    let mut product = product.ok_or_else(|| ApiError::NotFound {
+        message: "Failed to get product record".to_string(),
+    })?;
+    certify_products(&db, std::slice::from_mut(&mut product)).await?;
+
+    if let Some(currency) = query.currency {
+        let fx = FxTable::load(&db).await?;
+        for variant in &mut product.variants {
+            variant.price = fx.convert(&variant.price, currency)?;
+        }
+    }
+
+    Ok(Json(product))
+}
+
 // POST /products - Create new product
 pub async fn create_product(
     State(db): State<Db>,
     Json(payload): Json<CreateProductRequest>,
 ) -> ApiResult<Json<Product>> {
-    let product: Product = payload.into();
+    let mut product: Product = payload.into();
+    validate_product(&product)?;
+    assign_codes(&db, &mut product).await?;
 
     let created: Option<Product> = db.create(table_name()).content(product).await?;
 
     match created {
-        Some(product) => Ok(Json(product)),
+        Some(mut product) => {
+            relate_product(&db, &product).await?;
+            certify_products(&db, std::slice::from_mut(&mut product)).await?;
+            Ok(Json(product))
+        }
         None => Err(ApiError::Internal {
             message: "Failed to create product record".to_string(),
         }),
@@ -58,12 +313,7 @@ pub async fn update_product(
     Path(id): Path<String>,
     Json(payload): Json<UpdateProductRequest>,
 ) -> ApiResult<Json<Product>> {
-    // First check if the record exists
-    let existing: Option<Product> = db.select(make_record_id(&id)).await?;
-
-    let mut product = existing.ok_or_else(|| ApiError::NotFound {
-        message: format!("Product with id '{}' not found", id),
-    })?;
+    let mut product = find_product(&db, &id).await?;
 
     // Update fields if provided
     if let Some(roast) = payload.roast {
@@ -75,28 +325,119 @@ pub async fn update_product(
     if let Some(description) = payload.description {
         product.description = Some(description);
     }
-    if let Some(package_size_grams) = payload.package_size_grams {
-        product.package_size_grams = package_size_grams;
-    }
-    if let Some(price) = payload.price {
-        product.price = price;
+    if let Some(freshness) = payload.freshness {
+        product.freshness = Some(freshness);
     }
-    if let Some(price_currency) = payload.price_currency {
-        product.price_currency = Some(price_currency);
+    let update = payload.variant;
+    if update.sku.is_some()
+        || update.gtin.is_some()
+        || update.price.is_some()
+        || update.stock_units.is_some()
+        || update.target_stock_units.is_some()
+        || update.reorder_point_units.is_some()
+    {
+        let variant = product
+            .variant_mut(None)
+            .ok_or_else(|| ApiError::BadRequest {
+                message: format!(
+                    "Product '{}' has several variants, update them through /products/{}/variants",
+                    id, id
+                ),
+            })?;
+        update_variant(variant, update);
     }
-    if let Some(stock_units) = payload.stock_units {
-        product.stock_units = stock_units;
+
+    Ok(Json(save_product(&db, &id, product).await?))
+}
+
+// POST /products/:id/variants - Add a size or grind to a product
+pub async fn create_product_variant(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<CreateProductVariantRequest>,
+) -> ApiResult<Json<Product>> {
+    let mut product = find_product(&db, &id).await?;
+    let variant: ProductVariant = payload.into();
+    if product.variant(Some(&variant.key)).is_some() {
+        return Err(ApiError::Conflict {
+            message: format!("Product '{}' already has a '{}' variant", id, variant.key),
+        });
     }
-    product.updated_at = Some(Utc::now());
+    product.variants.push(variant);
 
-    let updated: Option<Product> = db.update(make_record_id(&id)).content(product).await?;
+    Ok(Json(save_product(&db, &id, product).await?))
+}
 
-    match updated {
-        Some(product) => Ok(Json(product)),
-        None => Err(ApiError::Internal {
-            message: "Failed to update product record".to_string(),
-        }),
+// PUT /products/:id/variants/:variant - Update the price or stock of one variant
+pub async fn update_product_variant(
+    State(db): State<Db>,
+    Path((id, key)): Path<(String, String)>,
+    Json(payload): Json<UpdateProductVariantRequest>,
+) -> ApiResult<Json<Product>> {
+    let mut product = find_product(&db, &id).await?;
+    let variant = product
+        .variant_mut(Some(&key))
+        .ok_or_else(|| ApiError::NotFound {
+            message: format!("Product '{}' has no '{}' variant", id, key),
+        })?;
+    update_variant(variant, payload);
+
+    Ok(Json(save_product(&db, &id, product).await?))
+}
+
+// DELETE /products/:id/variants/:variant - Stop selling one variant
+pub async fn delete_product_variant(
+    State(db): State<Db>,
+    Path((id, key)): Path<(String, String)>,
+) -> ApiResult<Json<Product>> {
+    let mut product = find_product(&db, &id).await?;
+    let variant = product
+        .variant(Some(&key))
+        .ok_or_else(|| ApiError::NotFound {
+            message: format!("Product '{}' has no '{}' variant", id, key),
+        })?;
+    if variant.reserved_units > 0 {
+        return Err(ApiError::Conflict {
+            message: format!("Variant '{}' has units reserved for open orders", key),
+        });
+    }
+
+    // Orders that have not shipped and subscriptions still running would be
+    // left naming a variant that no longer exists
+    let orders: Vec<Order> = db.select("order").await?;
+    let ordered = orders.iter().any(|order| {
+        !matches!(
+            order.status,
+            OrderStatus::Shipped
+                | OrderStatus::Delivered
+                | OrderStatus::Cancelled
+                | OrderStatus::Refunded
+        ) && order.lines.iter().any(|line| {
+            product.id.as_ref() == Some(&line.product) && line.variant.as_deref() == Some(&key)
+        })
+    });
+    if ordered {
+        return Err(ApiError::Conflict {
+            message: format!("Variant '{}' is on orders that have not shipped", key),
+        });
     }
+    let subscriptions: Vec<Subscription> = db.select("subscription").await?;
+    let subscribed = subscriptions.iter().any(|subscription| {
+        subscription.status != SubscriptionStatus::Cancelled
+            && subscription.product == product.id
+            && subscription.variant.as_deref() == Some(&key)
+    });
+    if subscribed {
+        return Err(ApiError::Conflict {
+            message: format!(
+                "Variant '{}' is sent by subscriptions that are not cancelled",
+                key
+            ),
+        });
+    }
+    product.variants.retain(|variant| variant.key != key);
+
+    Ok(Json(save_product(&db, &id, product).await?))
 }
 
 // DELETE /products/:id - Delete product
@@ -0,0 +1,321 @@
+use crate::costing::grams_to_kg;
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    CreateGreenCoffeeRequest, CreatePurchaseOrderRequest, GreenCoffee, GreenReceipt,
+    InventoryMovement, MovementReason, OutstandingLine, PurchaseOrder, PurchaseOrderStatus,
+    ReceivePurchaseOrderRequest,
+};
+use crate::routes::suppliers::ensure_supplier_exists;
+use crate::trace::{relate_green, relate_purchase_order};
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::{DateTime, Utc};
+use surrealdb::sql::Thing;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "purchase_order".to_string()
+}
+
+// Helper function to create SurrealDB record ID
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+async fn find_purchase_order(db: &Db, id: &str) -> ApiResult<PurchaseOrder> {
+    let existing: Option<PurchaseOrder> = db.select(make_record_id(id)).await?;
+
+    existing.ok_or_else(|| ApiError::NotFound {
+        message: format!("Purchase order with id '{}' not found", id),
+    })
+}
+
+async fn save_purchase_order(
+    db: &Db,
+    id: &str,
+    mut order: PurchaseOrder,
+) -> ApiResult<PurchaseOrder> {
+    order.updated_at = Some(Utc::now());
+    let updated: Option<PurchaseOrder> = db.update(make_record_id(id)).content(order).await?;
+
+    let order = updated.ok_or_else(|| ApiError::Internal {
+        message: "Failed to update purchase order record".to_string(),
+    })?;
+    relate_purchase_order(db, &order).await?;
+
+    Ok(order)
+}
+
+fn validate_weight(grams: f64) -> ApiResult<()> {
+    if !grams.is_finite() || grams <= 0.0 {
+        return Err(ApiError::BadRequest {
+            message: "Weights must be positive".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Adds `grams` to the green the line is received into, creating the green
+/// from the line on its first receipt. A green without an arrival date is
+/// dated `received_at`.
+async fn receive_into_green(
+    db: &Db,
+    order: &PurchaseOrder,
+    line: usize,
+    grams: f64,
+    received_at: DateTime<Utc>,
+) -> ApiResult<Thing> {
+    let line = &order.lines[line];
+    let saved: Option<GreenCoffee> = match &line.green_coffee {
+        Some(green_id) => {
+            let green: Option<GreenCoffee> = db.select(record_key(green_id)).await?;
+            let mut green = green.ok_or_else(|| ApiError::NotFound {
+                message: format!("Green coffee '{}' not found", green_id),
+            })?;
+            green.stock_grams += grams;
+            green.arrival_date.get_or_insert(received_at);
+            green.updated_at = Some(Utc::now());
+            db.update(record_key(green_id)).content(green).await?
+        }
+        None => {
+            let green = GreenCoffee::from(CreateGreenCoffeeRequest {
+                name: line.lot.clone(),
+                origin_country: line.origin_country.clone(),
+                region: None,
+                variety: None,
+                processing_method: None,
+                altitude_masl: None,
+                harvest_year: None,
+                arrival_date: Some(received_at),
+                stock_grams: grams,
+                price_per_kg: Some(line.price_per_kg),
+                producer: None,
+                farm_gate_price_per_kg: None,
+                certifications: Vec::new(),
+                supplier: Some(order.supplier.clone()),
+                cupping_notes: None,
+                reorder_point_grams: None,
+            });
+            let created: Option<GreenCoffee> = db.create("green_coffee").content(green).await?;
+            if let Some(green) = &created {
+                relate_green(db, green).await?;
+            }
+            created
+        }
+    };
+
+    saved
+        .and_then(|green| green.id)
+        .ok_or_else(|| ApiError::Internal {
+            message: "Failed to save green coffee record".to_string(),
+        })
+}
+
+// GET /purchase-orders - List all purchase orders
+pub async fn list_purchase_orders(State(db): State<Db>) -> ApiResult<Json<Vec<PurchaseOrder>>> {
+    let orders: Vec<PurchaseOrder> = db.select(table_name()).await?;
+
+    Ok(Json(orders))
+}
+
+// GET /purchase-orders/:id - Get specific purchase order
+pub async fn get_purchase_order(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<PurchaseOrder>> {
+    Ok(Json(find_purchase_order(&db, &id).await?))
+}
+
+// POST /purchase-orders - Record a contract with a supplier
+pub async fn create_purchase_order(
+    State(db): State<Db>,
+    Json(payload): Json<CreatePurchaseOrderRequest>,
+) -> ApiResult<Json<PurchaseOrder>> {
+    let order: PurchaseOrder = payload.into();
+    ensure_supplier_exists(&db, &order.supplier).await?;
+    if order.lines.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Purchase order must have at least one line".to_string(),
+        });
+    }
+    for line in &order.lines {
+        if line.lot.trim().is_empty() {
+            return Err(ApiError::BadRequest {
+                message: "Every line needs a lot name".to_string(),
+            });
+        }
+        validate_weight(line.contracted_grams)?;
+        if line.price_per_kg.is_negative() {
+            return Err(ApiError::BadRequest {
+                message: "Green coffee price cannot be negative".to_string(),
+            });
+        }
+        if let Some(green_id) = &line.green_coffee {
+            let green: Option<GreenCoffee> = db.select(record_key(green_id)).await?;
+            if green.is_none() {
+                return Err(ApiError::NotFound {
+                    message: format!("Green coffee '{}' not found", green_id),
+                });
+            }
+        }
+    }
+
+    let created: Option<PurchaseOrder> = db.create(table_name()).content(order).await?;
+
+    match created {
+        Some(order) => {
+            relate_purchase_order(&db, &order).await?;
+            Ok(Json(order))
+        }
+        None => Err(ApiError::Internal {
+            message: "Failed to create purchase order record".to_string(),
+        }),
+    }
+}
+
+// POST /purchase-orders/:id/receipts - Receive coffee against one or more lines
+pub async fn receive_purchase_order(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<ReceivePurchaseOrderRequest>,
+) -> ApiResult<Json<PurchaseOrder>> {
+    let mut order = find_purchase_order(&db, &id).await?;
+    if matches!(
+        order.status,
+        PurchaseOrderStatus::Received | PurchaseOrderStatus::Cancelled
+    ) {
+        return Err(ApiError::Conflict {
+            message: format!("Purchase order '{}' is closed", id),
+        });
+    }
+    if payload.lines.is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Receipt must include at least one line".to_string(),
+        });
+    }
+    // Check everything before any stock moves
+    for receipt in &payload.lines {
+        if receipt.line >= order.lines.len() {
+            return Err(ApiError::BadRequest {
+                message: format!("Purchase order has no line {}", receipt.line),
+            });
+        }
+        validate_weight(receipt.grams)?;
+        if let Some(green_id) = &order.lines[receipt.line].green_coffee {
+            let green: Option<GreenCoffee> = db.select(record_key(green_id)).await?;
+            if green.is_none() {
+                return Err(ApiError::NotFound {
+                    message: format!("Green coffee '{}' not found", green_id),
+                });
+            }
+        }
+    }
+
+    let received_at = payload.received_at.unwrap_or_else(Utc::now);
+    for receipt in payload.lines {
+        let green_id =
+            receive_into_green(&db, &order, receipt.line, receipt.grams, received_at).await?;
+        let line = &mut order.lines[receipt.line];
+        line.green_coffee = Some(green_id.clone());
+        line.received_grams += receipt.grams;
+
+        let movement = InventoryMovement {
+            id: None,
+            item: green_id.clone(),
+            variant: None,
+            quantity: receipt.grams,
+            reason: MovementReason::Receipt,
+            reference: order.id.clone(),
+            created_at: None,
+        };
+        let _: Option<InventoryMovement> =
+            db.create("inventory_movement").content(movement).await?;
+
+        order.receipts.push(GreenReceipt {
+            line: receipt.line,
+            green_coffee: green_id,
+            grams: receipt.grams,
+            received_at,
+        });
+    }
+
+    order.status = if order
+        .lines
+        .iter()
+        .all(|line| line.outstanding_grams() == 0.0)
+    {
+        PurchaseOrderStatus::Received
+    } else {
+        PurchaseOrderStatus::PartiallyReceived
+    };
+
+    Ok(Json(save_purchase_order(&db, &id, order).await?))
+}
+
+// POST /purchase-orders/:id/cancel - Stop expecting the rest of a purchase order
+pub async fn cancel_purchase_order(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<PurchaseOrder>> {
+    let mut order = find_purchase_order(&db, &id).await?;
+    if matches!(
+        order.status,
+        PurchaseOrderStatus::Received | PurchaseOrderStatus::Cancelled
+    ) {
+        return Err(ApiError::Conflict {
+            message: format!("Purchase order '{}' is closed", id),
+        });
+    }
+    order.status = PurchaseOrderStatus::Cancelled;
+
+    Ok(Json(save_purchase_order(&db, &id, order).await?))
+}
+
+// GET /purchase-orders/outstanding - Contracted coffee not yet received, soonest arrival first
+pub async fn list_outstanding_green(State(db): State<Db>) -> ApiResult<Json<Vec<OutstandingLine>>> {
+    let mut response = db
+        .query("SELECT * FROM purchase_order WHERE status IN $statuses")
+        .bind((
+            "statuses",
+            [
+                PurchaseOrderStatus::Open,
+                PurchaseOrderStatus::PartiallyReceived,
+            ],
+        ))
+        .await?;
+    let orders: Vec<PurchaseOrder> = response.take(0)?;
+
+    let mut outstanding = Vec::new();
+    for order in orders {
+        let Some(order_id) = order.id else {
+            continue;
+        };
+        for (index, line) in order.lines.into_iter().enumerate() {
+            let outstanding_grams = line.outstanding_grams();
+            if outstanding_grams == 0.0 {
+                continue;
+            }
+            outstanding.push(OutstandingLine {
+                purchase_order: order_id.clone(),
+                supplier: order.supplier.clone(),
+                reference: order.reference.clone(),
+                line: index,
+                outstanding_value: line
+                    .price_per_kg
+                    .times(grams_to_kg(outstanding_grams)?)?
+                    .rounded(),
+                lot: line.lot,
+                contracted_grams: line.contracted_grams,
+                received_grams: line.received_grams,
+                outstanding_grams,
+                expected_arrival: order.expected_arrival,
+            });
+        }
+    }
+    outstanding.sort_by_key(|line| (line.expected_arrival.is_none(), line.expected_arrival));
+
+    Ok(Json(outstanding))
+}
@@ -0,0 +1,135 @@
+use crate::artisan::parse_alog;
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{GreenCoffee, Roast, RoastProfile};
+use crate::profile_comparison::compare_profiles;
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use chrono::Utc;
+use surrealdb::sql::Thing;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "roast_profile".to_string()
+}
+
+// Profiles share their roast's id, so each roast has at most one
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+async fn find_roast(db: &Db, id: &str) -> ApiResult<Roast> {
+    let roast: Option<Roast> = db.select(("roast", id)).await?;
+
+    roast.ok_or_else(|| ApiError::NotFound {
+        message: format!("Roast with id '{}' not found", id),
+    })
+}
+
+async fn find_profile(db: &Db, roast: &Thing) -> ApiResult<RoastProfile> {
+    let profile: Option<RoastProfile> = db.select(make_record_id(&roast.id.to_raw())).await?;
+
+    profile.ok_or_else(|| ApiError::NotFound {
+        message: format!("Roast '{}' has no profile", roast),
+    })
+}
+
+/// Compares the profile of `roast` with the reference roast of its green
+/// coffee and stores the outcome on the roast.
+async fn compare_with_reference(db: &Db, mut roast: Roast) -> ApiResult<Roast> {
+    let roast_id = roast.id.clone().ok_or_else(|| ApiError::Internal {
+        message: "Roast record has no id".to_string(),
+    })?;
+    let green_id = roast
+        .green_coffee
+        .as_ref()
+        .ok_or_else(|| ApiError::BadRequest {
+            message: format!("Roast '{}' has no green coffee", roast_id),
+        })?;
+    let green: Option<GreenCoffee> = db.select(record_key(green_id)).await?;
+    let reference_id = green
+        .and_then(|green| green.reference_roast)
+        .ok_or_else(|| ApiError::BadRequest {
+            message: format!("Green coffee '{}' has no reference roast", green_id),
+        })?;
+    if reference_id == roast_id {
+        return Err(ApiError::BadRequest {
+            message: format!("Roast '{}' is the reference roast", roast_id),
+        });
+    }
+
+    let profile = find_profile(db, &roast_id).await?;
+    let reference = find_profile(db, &reference_id).await?;
+    let comparison = compare_profiles(&profile, &reference, reference_id).ok_or_else(|| {
+        ApiError::BadRequest {
+            message: "The profiles have no readings in common".to_string(),
+        }
+    })?;
+
+    roast.profile_comparison = Some(comparison);
+    roast.updated_at = Some(Utc::now());
+    let updated: Option<Roast> = db.update(record_key(&roast_id)).content(roast).await?;
+
+    updated.ok_or_else(|| ApiError::Internal {
+        message: "Failed to update roast record".to_string(),
+    })
+}
+
+// GET /roasts/:id/profile - Temperature curve and events of a roast
+pub async fn get_roast_profile(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<RoastProfile>> {
+    let profile: Option<RoastProfile> = db.select(make_record_id(&id)).await?;
+
+    profile.map(Json).ok_or_else(|| ApiError::NotFound {
+        message: format!("Roast '{}' has no profile", id),
+    })
+}
+
+// POST /roasts/:id/profile - Import an Artisan .alog file, replacing any existing profile
+pub async fn import_roast_profile(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    body: String,
+) -> ApiResult<Json<RoastProfile>> {
+    let roast = find_roast(&db, &id).await?;
+    let alog = parse_alog(&body).map_err(|e| ApiError::BadRequest {
+        message: format!("Invalid Artisan profile: {}", e),
+    })?;
+
+    let profile = RoastProfile {
+        id: None,
+        roast: roast.id.ok_or_else(|| ApiError::Internal {
+            message: "Roast record has no id".to_string(),
+        })?,
+        temperature_unit: alog.temperature_unit,
+        samples: alog.samples,
+        events: alog.events,
+        created_at: None,
+    };
+    let saved: Option<RoastProfile> = db.upsert(make_record_id(&id)).content(profile).await?;
+    let saved = saved.ok_or_else(|| ApiError::Internal {
+        message: "Failed to save roast profile".to_string(),
+    })?;
+
+    // Compare straight away when there is a reference to compare with
+    match compare_with_reference(&db, find_roast(&db, &id).await?).await {
+        Ok(_) | Err(ApiError::BadRequest { .. } | ApiError::NotFound { .. }) => {}
+        Err(e) => return Err(e),
+    }
+
+    Ok(Json(saved))
+}
+
+// POST /roasts/:id/profile/compare - Compare a roast's profile with its green's reference roast
+pub async fn compare_roast_profile(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Roast>> {
+    let roast = find_roast(&db, &id).await?;
+
+    Ok(Json(compare_with_reference(&db, roast).await?))
+}
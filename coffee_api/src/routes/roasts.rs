@@ -1,12 +1,20 @@
-use crate::db::Db;
+use crate::db::{Db, record_key};
 use crate::error::{ApiError, ApiResult};
-use crate::models::{CreateRoastRequest, Roast, UpdateRoastRequest};
+use crate::models::{
+    BlendMethod, CreateRoastRequest, GreenCoffee, InventoryMovement, MovementReason, Roast,
+    RoastProfile, UpdateRoastRequest,
+};
+use crate::trace::relate_roast;
 use axum::{
     extract::{Path, State},
     response::Json,
 };
 use chrono::Utc;
 use serde_json::Value;
+use surrealdb::sql::Thing;
+
+/// Component percentages may be off by this much from 100 to allow for rounding.
+const PERCENT_TOLERANCE: f64 = 0.01;
 
 // Helper function to get table name
 fn table_name() -> String {
@@ -18,6 +26,202 @@ fn make_record_id(id: &str) -> (String, String) {
     (table_name(), id.to_string())
 }
 
+/// Checks the components of a blend add up, and fills in the green of each
+/// component roast of a post-roast blend.
+async fn resolve_components(db: &Db, roast: &mut Roast) -> ApiResult<()> {
+    let Some(blend) = roast.blend else {
+        if !roast.components.is_empty() {
+            return Err(ApiError::BadRequest {
+                message: "Only blends have components".to_string(),
+            });
+        }
+        return Ok(());
+    };
+
+    if roast.green_coffee.is_some() {
+        return Err(ApiError::BadRequest {
+            message: "A blend takes its green coffee from its components".to_string(),
+        });
+    }
+    if roast.components.len() < 2 {
+        return Err(ApiError::BadRequest {
+            message: "A blend needs at least two components".to_string(),
+        });
+    }
+    if roast
+        .components
+        .iter()
+        .any(|component| !component.percent.is_finite() || component.percent <= 0.0)
+    {
+        return Err(ApiError::BadRequest {
+            message: "Component percentages must be positive".to_string(),
+        });
+    }
+    let total: f64 = roast
+        .components
+        .iter()
+        .map(|component| component.percent)
+        .sum();
+    if (total - 100.0).abs() > PERCENT_TOLERANCE {
+        return Err(ApiError::BadRequest {
+            message: format!("Component percentages add up to {}, not 100", total),
+        });
+    }
+
+    let mut seen: Vec<Thing> = Vec::new();
+    for component in &mut roast.components {
+        let part = match blend {
+            BlendMethod::PreRoast => {
+                if component.roast.is_some() {
+                    return Err(ApiError::BadRequest {
+                        message: "Pre-roast blend components are green coffees, not roasts"
+                            .to_string(),
+                    });
+                }
+                let green_id =
+                    component
+                        .green_coffee
+                        .clone()
+                        .ok_or_else(|| ApiError::BadRequest {
+                            message: "Every pre-roast blend component needs a green coffee"
+                                .to_string(),
+                        })?;
+                let green: Option<GreenCoffee> = db.select(record_key(&green_id)).await?;
+                if green.is_none() {
+                    return Err(ApiError::NotFound {
+                        message: format!("Green coffee '{}' not found", green_id),
+                    });
+                }
+                green_id
+            }
+            BlendMethod::PostRoast => {
+                let roast_id = component
+                    .roast
+                    .clone()
+                    .ok_or_else(|| ApiError::BadRequest {
+                        message: "Every post-roast blend component needs a roast".to_string(),
+                    })?;
+                if roast.id.as_ref() == Some(&roast_id) {
+                    return Err(ApiError::BadRequest {
+                        message: "A blend cannot contain itself".to_string(),
+                    });
+                }
+                let part: Option<Roast> = db.select(record_key(&roast_id)).await?;
+                let part = part.ok_or_else(|| ApiError::NotFound {
+                    message: format!("Roast '{}' not found", roast_id),
+                })?;
+                if part.blend == Some(BlendMethod::PostRoast) {
+                    return Err(ApiError::BadRequest {
+                        message: format!("Roast '{}' is itself a post-roast blend", roast_id),
+                    });
+                }
+                component.green_coffee = part.green_coffee;
+                roast_id
+            }
+        };
+        if seen.contains(&part) {
+            return Err(ApiError::BadRequest {
+                message: format!("'{}' appears in the blend more than once", part),
+            });
+        }
+        seen.push(part);
+    }
+    Ok(())
+}
+
+/// Works out how green stock changes when a roast's usage goes from `before`
+/// to `after`, checking every lot has enough before any stock is touched.
+async fn plan_green_stock(
+    db: &Db,
+    before: Vec<(Thing, f64)>,
+    after: Vec<(Thing, f64)>,
+) -> ApiResult<Vec<(Thing, GreenCoffee, f64)>> {
+    let mut changes: Vec<(Thing, f64)> = Vec::new();
+    let usage = after.into_iter().map(|(green, grams)| (green, -grams));
+    for (green_id, grams) in before.into_iter().chain(usage) {
+        match changes.iter_mut().find(|(id, _)| *id == green_id) {
+            Some((_, change)) => *change += grams,
+            None => changes.push((green_id, grams)),
+        }
+    }
+
+    let mut plan = Vec::new();
+    for (green_id, change) in changes {
+        if change == 0.0 {
+            continue;
+        }
+        let green: Option<GreenCoffee> = db.select(record_key(&green_id)).await?;
+        let Some(green) = green else {
+            // Stock of a lot that has since been deleted cannot be returned
+            if change > 0.0 {
+                continue;
+            }
+            return Err(ApiError::NotFound {
+                message: format!("Green coffee '{}' not found", green_id),
+            });
+        };
+        if green.stock_grams + change < 0.0 {
+            return Err(ApiError::Conflict {
+                message: format!(
+                    "Green coffee '{}' has {} g in stock, {} g needed",
+                    green_id, green.stock_grams, -change
+                ),
+            });
+        }
+        plan.push((green_id, green, change));
+    }
+    Ok(plan)
+}
+
+/// Green taken out of stock for `roast`, by lot, as its roast movements
+/// record it. Roasts from before roasting took green out of stock have none.
+async fn green_taken(db: &Db, roast: &Option<Thing>) -> ApiResult<Vec<(Thing, f64)>> {
+    let Some(roast) = roast else {
+        return Ok(Vec::new());
+    };
+    let movements: Vec<InventoryMovement> = db
+        .query("SELECT * FROM inventory_movement WHERE reason = 'roast' AND reference = $roast")
+        .bind(("roast", roast.clone()))
+        .await?
+        .take(0)?;
+
+    let mut taken: Vec<(Thing, f64)> = Vec::new();
+    for movement in movements {
+        match taken.iter_mut().find(|(id, _)| *id == movement.item) {
+            Some((_, grams)) => *grams -= movement.quantity,
+            None => taken.push((movement.item, -movement.quantity)),
+        }
+    }
+    Ok(taken)
+}
+
+/// Applies a planned change to green stock and records a movement against
+/// `roast` for each lot.
+async fn apply_green_stock(
+    db: &Db,
+    roast: &Option<Thing>,
+    plan: Vec<(Thing, GreenCoffee, f64)>,
+) -> ApiResult<()> {
+    for (green_id, mut green, change) in plan {
+        green.stock_grams += change;
+        green.updated_at = Some(Utc::now());
+        let _: Option<GreenCoffee> = db.update(record_key(&green_id)).content(green).await?;
+
+        let movement = InventoryMovement {
+            id: None,
+            item: green_id,
+            variant: None,
+            quantity: change,
+            reason: MovementReason::Roast,
+            reference: roast.clone(),
+            created_at: None,
+        };
+        let _: Option<InventoryMovement> =
+            db.create("inventory_movement").content(movement).await?;
+    }
+    Ok(())
+}
+
 // GET /roasts - List all roasts
 pub async fn list_roasts(State(db): State<Db>) -> ApiResult<Json<Vec<Roast>>> {
     let roasts: Vec<Roast> = db.select("roast").await?;
@@ -40,12 +244,18 @@ pub async fn create_roast(
     State(db): State<Db>,
     Json(payload): Json<CreateRoastRequest>,
 ) -> ApiResult<Json<Roast>> {
-    let roast: Roast = payload.into();
+    let mut roast: Roast = payload.into();
+    resolve_components(&db, &mut roast).await?;
+    let stock = plan_green_stock(&db, Vec::new(), roast.green_usage()).await?;
 
     let created: Option<Roast> = db.create(table_name()).content(roast).await?;
 
     match created {
-        Some(roast) => Ok(Json(roast)),
+        Some(roast) => {
+            apply_green_stock(&db, &roast.id, stock).await?;
+            relate_roast(&db, &roast).await?;
+            Ok(Json(roast))
+        }
         None => Err(ApiError::Internal {
             message: "Failed to create roast record".to_string(),
         }),
@@ -64,13 +274,24 @@ pub async fn update_roast(
     let mut roast = existing.ok_or_else(|| ApiError::NotFound {
         message: format!("Roast with id '{}' not found", id),
     })?;
+    let taken = green_taken(&db, &roast.id).await?;
 
-    // Update fields if provided
+    // Update fields if provided. A single green and blend components replace
+    // one another.
     if let Some(name) = payload.name {
         roast.name = name;
     }
     if let Some(green_coffee) = payload.green_coffee {
         roast.green_coffee = Some(green_coffee);
+        roast.blend = None;
+        roast.components = Vec::new();
+    }
+    if let Some(blend) = payload.blend {
+        roast.blend = Some(blend);
+        roast.green_coffee = None;
+    }
+    if let Some(components) = payload.components {
+        roast.components = components;
     }
     if let Some(date_roasted) = payload.date_roasted {
         roast.date_roasted = Some(date_roasted);
@@ -87,12 +308,23 @@ pub async fn update_roast(
     if let Some(notes) = payload.notes {
         roast.notes = Some(notes);
     }
+    resolve_components(&db, &mut roast).await?;
+    // A roast that never took green out of stock leaves it alone when edited
+    let stock = if taken.is_empty() {
+        Vec::new()
+    } else {
+        plan_green_stock(&db, taken, roast.green_usage()).await?
+    };
     roast.updated_at = Some(Utc::now());
 
     let updated: Option<Roast> = db.update(make_record_id(&id)).content(roast).await?;
 
     match updated {
-        Some(roast) => Ok(Json(roast)),
+        Some(roast) => {
+            apply_green_stock(&db, &roast.id, stock).await?;
+            relate_roast(&db, &roast).await?;
+            Ok(Json(roast))
+        }
         None => Err(ApiError::Internal {
             message: "Failed to update roast record".to_string(),
         }),
@@ -101,12 +333,38 @@ pub async fn update_roast(
 
 // DELETE /roasts/:id - Delete roast
 pub async fn delete_roast(State(db): State<Db>, Path(id): Path<String>) -> ApiResult<Json<Value>> {
+    let roast_id = Thing::from(make_record_id(&id));
+    let mut response = db
+        .query("SELECT VALUE id FROM roast WHERE $roast IN components.roast")
+        .query("SELECT VALUE id FROM product WHERE roast = $roast")
+        .bind(("roast", roast_id))
+        .await?;
+    let blends: Vec<Thing> = response.take(0)?;
+    let products: Vec<Thing> = response.take(1)?;
+    if !blends.is_empty() {
+        return Err(ApiError::Conflict {
+            message: format!("Roast '{}' is a component of {} blends", id, blends.len()),
+        });
+    }
+    if !products.is_empty() {
+        return Err(ApiError::Conflict {
+            message: format!("Roast '{}' is sold as {} products", id, products.len()),
+        });
+    }
+
     let deleted: Option<Roast> = db.delete(make_record_id(&id)).await?;
 
     match deleted {
-        Some(_) => Ok(Json(
-            serde_json::json!({"message": "Roast deleted successfully"}),
-        )),
+        Some(roast) => {
+            // Whatever green the roast took goes back into stock
+            let taken = green_taken(&db, &roast.id).await?;
+            let stock = plan_green_stock(&db, taken, Vec::new()).await?;
+            apply_green_stock(&db, &roast.id, stock).await?;
+            let _: Option<RoastProfile> = db.delete(("roast_profile", id.as_str())).await?;
+            Ok(Json(
+                serde_json::json!({"message": "Roast deleted successfully"}),
+            ))
+        }
         None => Err(ApiError::NotFound {
             message: format!("Roast with id '{}' not found", id),
         }),
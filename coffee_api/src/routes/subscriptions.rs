@@ -0,0 +1,340 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    CreateSubscriptionRequest, Customer, Order, Product, Subscription, SubscriptionForecastLine,
+    SubscriptionStatus, UpdateSubscriptionRequest,
+};
+use crate::routes::products::find_variant;
+use crate::scheduler::run_due_subscriptions;
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use surrealdb::sql::Thing;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "subscription".to_string()
+}
+
+// Helper function to create SurrealDB record ID
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+async fn find_subscription(db: &Db, id: &str) -> ApiResult<Subscription> {
+    let existing: Option<Subscription> = db.select(make_record_id(id)).await?;
+
+    existing.ok_or_else(|| ApiError::NotFound {
+        message: format!("Subscription with id '{}' not found", id),
+    })
+}
+
+async fn save_subscription(
+    db: &Db,
+    id: &str,
+    mut subscription: Subscription,
+) -> ApiResult<Subscription> {
+    subscription.updated_at = Some(Utc::now());
+    let updated: Option<Subscription> = db.update(make_record_id(id)).content(subscription).await?;
+
+    updated.ok_or_else(|| ApiError::Internal {
+        message: "Failed to update subscription record".to_string(),
+    })
+}
+
+/// Checks a subscription can be fulfilled, and pins it to the variant of its
+/// product it sends.
+async fn validate_subscription(db: &Db, subscription: &mut Subscription) -> ApiResult<()> {
+    if subscription.quantity <= 0 {
+        return Err(ApiError::BadRequest {
+            message: "Subscription quantity must be positive".to_string(),
+        });
+    }
+    if subscription.interval_weeks <= 0 {
+        return Err(ApiError::BadRequest {
+            message: "Subscription interval must be at least one week".to_string(),
+        });
+    }
+
+    let customer: Option<Customer> = db.select(record_key(&subscription.customer)).await?;
+    if customer.is_none() {
+        return Err(ApiError::NotFound {
+            message: format!("Customer '{}' not found", subscription.customer),
+        });
+    }
+    match &subscription.product {
+        Some(product_id) => {
+            let product: Option<Product> = db.select(record_key(product_id)).await?;
+            let product = product.ok_or_else(|| ApiError::NotFound {
+                message: format!("Product '{}' not found", product_id),
+            })?;
+            let variant = find_variant(&product, subscription.variant.as_deref())?;
+            subscription.variant = Some(variant.key.clone());
+        }
+        None => subscription.variant = None,
+    }
+    Ok(())
+}
+
+// GET /subscriptions - List all subscriptions
+pub async fn list_subscriptions(State(db): State<Db>) -> ApiResult<Json<Vec<Subscription>>> {
+    let subscriptions: Vec<Subscription> = db.select("subscription").await?;
+
+    Ok(Json(subscriptions))
+}
+
+// GET /subscriptions/:id - Get specific subscription
+pub async fn get_subscription(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Subscription>> {
+    Ok(Json(find_subscription(&db, &id).await?))
+}
+
+// POST /subscriptions - Create new subscription
+pub async fn create_subscription(
+    State(db): State<Db>,
+    Json(payload): Json<CreateSubscriptionRequest>,
+) -> ApiResult<Json<Subscription>> {
+    let mut subscription: Subscription = payload.into();
+    validate_subscription(&db, &mut subscription).await?;
+
+    let created: Option<Subscription> = db.create(table_name()).content(subscription).await?;
+
+    match created {
+        Some(subscription) => Ok(Json(subscription)),
+        None => Err(ApiError::Internal {
+            message: "Failed to create subscription record".to_string(),
+        }),
+    }
+}
+
+// PUT /subscriptions/:id - Update subscription
+pub async fn update_subscription(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateSubscriptionRequest>,
+) -> ApiResult<Json<Subscription>> {
+    let mut subscription = find_subscription(&db, &id).await?;
+
+    // Update fields if provided
+    if payload.roasters_choice {
+        if payload.product.is_some() || payload.variant.is_some() {
+            return Err(ApiError::BadRequest {
+                message: "A roaster's choice subscription cannot name a product".to_string(),
+            });
+        }
+        subscription.product = None;
+        subscription.variant = None;
+    }
+    if let Some(product) = payload.product {
+        subscription.product = Some(product);
+        subscription.variant = None;
+    }
+    if let Some(variant) = payload.variant {
+        subscription.variant = Some(variant);
+    }
+    if let Some(quantity) = payload.quantity {
+        subscription.quantity = quantity;
+    }
+    if let Some(interval_weeks) = payload.interval_weeks {
+        subscription.interval_weeks = interval_weeks;
+    }
+    if let Some(next_run_at) = payload.next_run_at {
+        subscription.next_run_at = next_run_at;
+    }
+    validate_subscription(&db, &mut subscription).await?;
+
+    Ok(Json(save_subscription(&db, &id, subscription).await?))
+}
+
+/// Moves a subscription to `status`; cancelled subscriptions stay cancelled.
+async fn set_status(db: &Db, id: &str, status: SubscriptionStatus) -> ApiResult<Subscription> {
+    let mut subscription = find_subscription(db, id).await?;
+    if subscription.status == SubscriptionStatus::Cancelled {
+        return Err(ApiError::Conflict {
+            message: format!("Subscription '{}' has been cancelled", id),
+        });
+    }
+
+    if status == SubscriptionStatus::Active {
+        // Don't fire a burst of orders for the time spent paused
+        subscription.next_run_at = subscription.next_run_at.max(Utc::now());
+    }
+    subscription.status = status;
+
+    save_subscription(db, id, subscription).await
+}
+
+// POST /subscriptions/:id/pause - Stop generating orders until resumed
+pub async fn pause_subscription(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Subscription>> {
+    Ok(Json(
+        set_status(&db, &id, SubscriptionStatus::Paused).await?,
+    ))
+}
+
+// POST /subscriptions/:id/resume - Resume a paused subscription
+pub async fn resume_subscription(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Subscription>> {
+    Ok(Json(
+        set_status(&db, &id, SubscriptionStatus::Active).await?,
+    ))
+}
+
+// POST /subscriptions/:id/cancel - Cancel a subscription for good
+pub async fn cancel_subscription(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Subscription>> {
+    let mut subscription = find_subscription(&db, &id).await?;
+    subscription.status = SubscriptionStatus::Cancelled;
+
+    Ok(Json(save_subscription(&db, &id, subscription).await?))
+}
+
+// POST /subscriptions/:id/skip - Skip the next delivery
+pub async fn skip_subscription(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Subscription>> {
+    let mut subscription = find_subscription(&db, &id).await?;
+    if subscription.status != SubscriptionStatus::Active {
+        return Err(ApiError::Conflict {
+            message: "Only active subscriptions can skip a delivery".to_string(),
+        });
+    }
+    let now = Utc::now();
+    if subscription.next_run_at <= now {
+        subscription.advance_past(now);
+    } else {
+        subscription.next_run_at += subscription.interval();
+    }
+
+    Ok(Json(save_subscription(&db, &id, subscription).await?))
+}
+
+// POST /subscriptions/run - Generate orders for due subscriptions right away
+pub async fn run_subscriptions(State(db): State<Db>) -> ApiResult<Json<Vec<Order>>> {
+    Ok(Json(run_due_subscriptions(&db, Utc::now()).await?))
+}
+
+/// Bags that active subscriptions will order from now until `until`, per
+/// product variant. `None` collects the roaster's choice subscriptions.
+pub async fn forecast_bags(
+    db: &Db,
+    until: DateTime<Utc>,
+) -> ApiResult<Vec<(Option<(Thing, Option<String>)>, i32)>> {
+    let mut response = db
+        .query("SELECT * FROM subscription WHERE status = $status")
+        .bind(("status", SubscriptionStatus::Active))
+        .await?;
+    let subscriptions: Vec<Subscription> = response.take(0)?;
+
+    let now = Utc::now();
+    type Bags = (Option<(Thing, Option<String>)>, i32);
+    let mut bags: BTreeMap<Option<(String, Option<String>)>, Bags> = BTreeMap::new();
+    for subscription in &subscriptions {
+        let runs = i32::try_from(subscription.runs_between(now, until).len()).unwrap_or(i32::MAX);
+        if runs == 0 {
+            continue;
+        }
+        let item = subscription
+            .product
+            .clone()
+            .map(|product| (product, subscription.variant.clone()));
+        let entry = bags
+            .entry(
+                item.as_ref()
+                    .map(|(product, variant)| (product.to_string(), variant.clone())),
+            )
+            .or_insert_with(|| (item, 0));
+        entry.1 = entry
+            .1
+            .saturating_add(runs.saturating_mul(subscription.quantity));
+    }
+
+    Ok(bags.into_values().collect())
+}
+
+/// Forecasts look at most two years ahead.
+pub const MAX_FORECAST_WEEKS: i64 = 104;
+
+/// The moment `weeks` weeks from now, for forecasts of at most
+/// `MAX_FORECAST_WEEKS`.
+pub fn forecast_until(weeks: i64) -> ApiResult<DateTime<Utc>> {
+    if weeks > MAX_FORECAST_WEEKS {
+        return Err(ApiError::BadRequest {
+            message: format!("Forecasts cover at most {} weeks", MAX_FORECAST_WEEKS),
+        });
+    }
+
+    Utc::now()
+        .checked_add_signed(Duration::weeks(weeks))
+        .ok_or_else(|| ApiError::BadRequest {
+            message: format!("{} weeks is too far ahead to forecast", weeks),
+        })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastQuery {
+    pub weeks: Option<i64>,
+}
+
+// GET /subscriptions/forecast?weeks=N - Bags due per product over the next N weeks
+pub async fn subscription_forecast(
+    State(db): State<Db>,
+    Query(query): Query<ForecastQuery>,
+) -> ApiResult<Json<Vec<SubscriptionForecastLine>>> {
+    let weeks = query.weeks.unwrap_or(4);
+    if weeks <= 0 {
+        return Err(ApiError::BadRequest {
+            message: "Forecast must cover at least one week".to_string(),
+        });
+    }
+
+    let bags = forecast_bags(&db, forecast_until(weeks)?).await?;
+
+    let mut forecast = Vec::with_capacity(bags.len());
+    for (item, bags) in bags {
+        let line = match item {
+            Some((product_id, variant_key)) => {
+                let product: Option<Product> = db.select(record_key(&product_id)).await?;
+                let variant = product
+                    .as_ref()
+                    .and_then(|p| p.variant(variant_key.as_deref()));
+                SubscriptionForecastLine {
+                    name: product
+                        .as_ref()
+                        .map(|p| match variant {
+                            Some(variant) => p.variant_name(variant),
+                            None => p.name.clone(),
+                        })
+                        .unwrap_or_else(|| product_id.to_string()),
+                    grams: variant.map(|v| v.package_size_grams * bags as f64),
+                    product: Some(product_id),
+                    variant: variant_key,
+                    bags,
+                }
+            }
+            None => SubscriptionForecastLine {
+                product: None,
+                variant: None,
+                name: "Roaster's choice".to_string(),
+                bags,
+                grams: None,
+            },
+        };
+        forecast.push(line);
+    }
+
+    Ok(Json(forecast))
+}
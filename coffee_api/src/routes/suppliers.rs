@@ -0,0 +1,326 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    CreateSupplierRequest, GreenCoffee, Supplier, SupplierMerge, SupplierMigrationReport,
+    UpdateSupplierRequest, supplier_key,
+};
+use crate::trace::{Relation, link};
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use surrealdb::sql::Thing;
+
+// Helper function to get table name
+fn table_name() -> String {
+    "supplier".to_string()
+}
+
+// Helper function to create SurrealDB record ID
+fn make_record_id(id: &str) -> (String, String) {
+    (table_name(), id.to_string())
+}
+
+async fn find_supplier(db: &Db, id: &str) -> ApiResult<Supplier> {
+    let existing: Option<Supplier> = db.select(make_record_id(id)).await?;
+
+    existing.ok_or_else(|| ApiError::NotFound {
+        message: format!("Supplier with id '{}' not found", id),
+    })
+}
+
+pub async fn ensure_supplier_exists(db: &Db, supplier: &Thing) -> ApiResult<()> {
+    let existing: Option<Supplier> = match supplier.tb.as_str() {
+        "supplier" => db.select(record_key(supplier)).await?,
+        _ => None,
+    };
+
+    existing.map(|_| ()).ok_or_else(|| ApiError::NotFound {
+        message: format!("Supplier '{}' not found", supplier),
+    })
+}
+
+fn validate_supplier(supplier: &Supplier) -> ApiResult<()> {
+    if supplier_key(&supplier.name).is_empty() {
+        return Err(ApiError::BadRequest {
+            message: "Supplier name must not be empty".to_string(),
+        });
+    }
+    if supplier.lead_time_days.is_some_and(|days| days < 0) {
+        return Err(ApiError::BadRequest {
+            message: "Lead time cannot be negative".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Rejects `supplier` if its name or an alias already belongs to another
+/// supplier, comparing names as [`supplier_key`] does.
+async fn ensure_names_available(db: &Db, supplier: &Supplier) -> ApiResult<()> {
+    let others: Vec<Supplier> = db.select(table_name()).await?;
+
+    for name in std::iter::once(&supplier.name).chain(&supplier.aliases) {
+        if let Some(other) = others
+            .iter()
+            .find(|other| other.id != supplier.id && other.answers_to(name))
+        {
+            return Err(ApiError::Conflict {
+                message: format!("'{}' is already known as supplier '{}'", name, other.name),
+            });
+        }
+    }
+    Ok(())
+}
+
+// GET /suppliers - List all suppliers
+pub async fn list_suppliers(State(db): State<Db>) -> ApiResult<Json<Vec<Supplier>>> {
+    let suppliers: Vec<Supplier> = db.select(table_name()).await?;
+
+    Ok(Json(suppliers))
+}
+
+// GET /suppliers/:id - Get specific supplier
+pub async fn get_supplier(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Supplier>> {
+    Ok(Json(find_supplier(&db, &id).await?))
+}
+
+// POST /suppliers - Create new supplier, unless one by that name exists
+pub async fn create_supplier(
+    State(db): State<Db>,
+    Json(payload): Json<CreateSupplierRequest>,
+) -> ApiResult<Json<Supplier>> {
+    let supplier: Supplier = payload.into();
+    validate_supplier(&supplier)?;
+    ensure_names_available(&db, &supplier).await?;
+
+    let created: Option<Supplier> = db.create(table_name()).content(supplier).await?;
+
+    match created {
+        Some(supplier) => Ok(Json(supplier)),
+        None => Err(ApiError::Internal {
+            message: "Failed to create supplier record".to_string(),
+        }),
+    }
+}
+
+// PUT /suppliers/:id - Update supplier
+pub async fn update_supplier(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateSupplierRequest>,
+) -> ApiResult<Json<Supplier>> {
+    let mut supplier = find_supplier(&db, &id).await?;
+
+    // Update fields if provided
+    if let Some(name) = payload.name {
+        supplier.name = name;
+    }
+    if let Some(aliases) = payload.aliases {
+        supplier.aliases = aliases;
+    }
+    if let Some(contact_name) = payload.contact_name {
+        supplier.contact_name = Some(contact_name);
+    }
+    if let Some(email) = payload.email {
+        supplier.email = Some(email);
+    }
+    if let Some(phone) = payload.phone {
+        supplier.phone = Some(phone);
+    }
+    if let Some(address) = payload.address {
+        supplier.address = Some(address);
+    }
+    if let Some(lead_time_days) = payload.lead_time_days {
+        supplier.lead_time_days = Some(lead_time_days);
+    }
+    if let Some(currency) = payload.currency {
+        supplier.currency = Some(currency);
+    }
+    if let Some(payment_terms) = payload.payment_terms {
+        supplier.payment_terms = Some(payment_terms);
+    }
+    validate_supplier(&supplier)?;
+    ensure_names_available(&db, &supplier).await?;
+    supplier.updated_at = Some(Utc::now());
+
+    let updated: Option<Supplier> = db.update(make_record_id(&id)).content(supplier).await?;
+
+    match updated {
+        Some(supplier) => Ok(Json(supplier)),
+        None => Err(ApiError::Internal {
+            message: "Failed to update supplier record".to_string(),
+        }),
+    }
+}
+
+// DELETE /suppliers/:id - Delete a supplier no green coffee is linked to
+pub async fn delete_supplier(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Value>> {
+    let supplier = find_supplier(&db, &id).await?;
+
+    let mut response = db
+        .query("SELECT * FROM green_coffee WHERE supplier = $supplier")
+        .bind(("supplier", supplier.id.clone()))
+        .await?;
+    let greens: Vec<GreenCoffee> = response.take(0)?;
+    if !greens.is_empty() {
+        return Err(ApiError::Conflict {
+            message: format!(
+                "Supplier '{}' still supplies {} green coffee(s)",
+                id,
+                greens.len()
+            ),
+        });
+    }
+
+    let _: Option<Supplier> = db.delete(make_record_id(&id)).await?;
+
+    Ok(Json(
+        serde_json::json!({"message": "Supplier deleted successfully"}),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MigrateSuppliersQuery {
+    /// Report what would be merged without changing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Points a green at its supplier record and drops the free text. Written as
+/// a targeted update so the green's other fields are left exactly as stored.
+async fn link_green(db: &Db, green: &Thing, supplier: Option<Thing>) -> ApiResult<()> {
+    db.query(
+        "UPDATE $green SET supplier = $supplier, legacy_supplier = NONE, updated_at = time::now()",
+    )
+    .bind(("green", green.clone()))
+    .bind(("supplier", supplier.clone()))
+    .await?
+    .check()?;
+    link(db, green, Relation::SuppliedBy, supplier).await
+}
+
+// POST /suppliers/migrate?dry_run=true - Turn free-text green suppliers into linked supplier records
+pub async fn migrate_legacy_suppliers(
+    State(db): State<Db>,
+    Query(query): Query<MigrateSuppliersQuery>,
+) -> ApiResult<Json<SupplierMigrationReport>> {
+    let mut response = db
+        .query("SELECT * FROM green_coffee WHERE legacy_supplier != NONE")
+        .await?;
+    let greens: Vec<GreenCoffee> = response.take(0)?;
+    let suppliers: Vec<Supplier> = db.select(table_name()).await?;
+
+    // Spellings that reduce to the same key belong to one supplier
+    let mut groups: BTreeMap<String, Vec<GreenCoffee>> = BTreeMap::new();
+    let mut cleared = Vec::new();
+    for green in greens {
+        let key = supplier_key(green.legacy_supplier.as_deref().unwrap_or_default());
+        if key.is_empty() {
+            if let Some(id) = green.id {
+                if !query.dry_run {
+                    link_green(&db, &id, None).await?;
+                }
+                cleared.push(id);
+            }
+            continue;
+        }
+        groups.entry(key).or_default().push(green);
+    }
+
+    let mut merges = Vec::new();
+    for greens in groups.into_values() {
+        // Distinct spellings, the most used first
+        let mut spellings: BTreeMap<String, usize> = BTreeMap::new();
+        for green in &greens {
+            let name = green.legacy_supplier.as_deref().unwrap_or_default().trim();
+            *spellings.entry(name.to_string()).or_default() += 1;
+        }
+        let mut merged_names: Vec<(String, usize)> = spellings.into_iter().collect();
+        merged_names.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        let merged_names: Vec<String> = merged_names.into_iter().map(|(name, _)| name).collect();
+
+        let existing = suppliers
+            .iter()
+            .find(|supplier| supplier.answers_to(&merged_names[0]));
+        let (supplier, created) = match existing {
+            Some(existing) => {
+                let mut supplier = existing.clone();
+                for name in &merged_names {
+                    if *name != supplier.name && !supplier.aliases.contains(name) {
+                        supplier.aliases.push(name.clone());
+                    }
+                }
+                (supplier, false)
+            }
+            None => {
+                let supplier = Supplier::from(CreateSupplierRequest {
+                    name: merged_names[0].clone(),
+                    aliases: Some(merged_names[1..].to_vec()),
+                    contact_name: None,
+                    email: None,
+                    phone: None,
+                    address: None,
+                    lead_time_days: None,
+                    currency: None,
+                    payment_terms: None,
+                });
+                (supplier, true)
+            }
+        };
+
+        let name = supplier.name.clone();
+        let supplier_id = if query.dry_run {
+            supplier.id.clone()
+        } else {
+            let saved = match supplier.id.clone() {
+                Some(id) => {
+                    db.query("UPDATE $supplier SET aliases = $aliases, updated_at = time::now()")
+                        .bind(("supplier", id.clone()))
+                        .bind(("aliases", supplier.aliases))
+                        .await?
+                        .check()?;
+                    Some(id)
+                }
+                None => {
+                    let created: Option<Supplier> =
+                        db.create(table_name()).content(supplier).await?;
+                    created.and_then(|s| s.id)
+                }
+            };
+            Some(saved.ok_or_else(|| ApiError::Internal {
+                message: "Failed to save supplier record".to_string(),
+            })?)
+        };
+
+        let green_ids: Vec<Thing> = greens.into_iter().filter_map(|green| green.id).collect();
+        if !query.dry_run {
+            for green in &green_ids {
+                link_green(&db, green, supplier_id.clone()).await?;
+            }
+        }
+
+        merges.push(SupplierMerge {
+            supplier: supplier_id,
+            name,
+            created,
+            merged_names,
+            greens: green_ids,
+        });
+    }
+
+    Ok(Json(SupplierMigrationReport {
+        dry_run: query.dry_run,
+        suppliers: merges,
+        cleared,
+    }))
+}
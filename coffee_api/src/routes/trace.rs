@@ -0,0 +1,50 @@
+use crate::db::Db;
+use crate::error::{ApiError, ApiResult};
+use crate::models::TraceGraph;
+use crate::trace::trace;
+use axum::{
+    extract::{Path, State},
+    response::Json,
+};
+use surrealdb::sql::Thing;
+
+/// Tables linked into the traceability graph.
+const TRACEABLE: &[&str] = &[
+    "order",
+    "product",
+    "roast",
+    "green_coffee",
+    "purchase_order",
+    "supplier",
+];
+
+// GET /trace/:record - Everything upstream and downstream of a record, e.g. /trace/green_coffee:abc
+pub async fn get_trace(
+    State(db): State<Db>,
+    Path(record): Path<String>,
+) -> ApiResult<Json<TraceGraph>> {
+    let root: Thing = record
+        .parse()
+        .ok()
+        .filter(|root: &Thing| TRACEABLE.contains(&root.tb.as_str()))
+        .ok_or_else(|| ApiError::BadRequest {
+            message: format!(
+                "'{}' is not a record of one of: {}",
+                record,
+                TRACEABLE.join(", ")
+            ),
+        })?;
+
+    let mut response = db
+        .query("SELECT VALUE id FROM $root")
+        .bind(("root", root.clone()))
+        .await?;
+    let existing: Vec<Thing> = response.take(0)?;
+    if existing.is_empty() {
+        return Err(ApiError::NotFound {
+            message: format!("Record '{}' not found", record),
+        });
+    }
+
+    Ok(Json(trace(&db, root).await?))
+}
@@ -0,0 +1,149 @@
+use crate::db::{Db, record_key};
+use crate::error::{ApiError, ApiResult};
+use crate::models::{
+    CreateOrderLineRequest, CreateOrderRequest, Order, Product, StockAlert, Subscription,
+    SubscriptionStatus,
+};
+use crate::notifications::Notifier;
+use crate::routes::alerts::send_low_stock_alerts;
+use crate::routes::orders::{build_order, insert_order, reserve_order_stock};
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use surrealdb::sql::Thing;
+
+/// Days of history the scheduled stock alerts estimate consumption over.
+const ALERT_CONSUMPTION_DAYS: i64 = 30;
+
+/// A period in seconds from `var`. Zero would make `tokio::time::interval`
+/// panic, so it falls back to `default` like an unparsable value.
+fn interval_secs(var: &str, default: u64) -> u64 {
+    std::env::var(var)
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(default)
+}
+
+/// Starts the background tasks: one turns due subscriptions into orders,
+/// the other sends low stock alerts through `notifier`. Their intervals come
+/// from `SUBSCRIPTION_SCHEDULER_INTERVAL_SECS`, hourly by default, and
+/// `ALERT_NOTIFY_INTERVAL_SECS`, daily by default.
+pub fn spawn(db: Db, notifier: Arc<dyn Notifier>) {
+    let period = interval_secs("SUBSCRIPTION_SCHEDULER_INTERVAL_SECS", 3600);
+    let alert_period = interval_secs("ALERT_NOTIFY_INTERVAL_SECS", 86400);
+
+    let alert_db = db.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(alert_period));
+        loop {
+            interval.tick().await;
+            if let Err(e) = notify_low_stock(&alert_db, notifier.as_ref()).await {
+                eprintln!("Stock alert notification error: {}", e);
+            }
+        }
+    });
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(period));
+        loop {
+            interval.tick().await;
+            match run_due_subscriptions(&db, Utc::now()).await {
+                Ok(orders) if !orders.is_empty() => {
+                    println!("Generated {} subscription order(s)", orders.len())
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Subscription scheduler error: {}", e),
+            }
+        }
+    });
+}
+
+/// Tells staff about every item at or below its reorder point.
+pub async fn notify_low_stock(db: &Db, notifier: &dyn Notifier) -> ApiResult<Vec<StockAlert>> {
+    send_low_stock_alerts(db, notifier, ALERT_CONSUMPTION_DAYS).await
+}
+
+/// Roaster's choice goes to the product variant with the most unreserved
+/// stock that can cover the whole quantity.
+async fn roasters_choice(db: &Db, quantity: i32) -> ApiResult<Option<(Thing, String)>> {
+    let products: Vec<Product> = db.select("product").await?;
+
+    Ok(products
+        .iter()
+        .flat_map(|p| p.variants.iter().map(move |v| (p, v)))
+        .filter(|(_, v)| v.available_units() >= quantity)
+        .max_by_key(|(_, v)| v.available_units())
+        .and_then(|(p, v)| Some((p.id.clone()?, v.key.clone()))))
+}
+
+async fn generate_order(db: &Db, subscription: &Subscription) -> ApiResult<Order> {
+    let (product, variant) = match &subscription.product {
+        Some(product) => (product.clone(), subscription.variant.clone()),
+        None => roasters_choice(db, subscription.quantity)
+            .await?
+            .map(|(product, variant)| (product, Some(variant)))
+            .ok_or_else(|| ApiError::Conflict {
+                message: "No product has enough stock for roaster's choice".to_string(),
+            })?,
+    };
+
+    let mut order = build_order(
+        db,
+        CreateOrderRequest {
+            customer: Some(subscription.customer.clone()),
+            lines: vec![CreateOrderLineRequest {
+                product,
+                variant,
+                quantity: subscription.quantity,
+            }],
+            currency: None,
+        },
+    )
+    .await?;
+    order.subscription = subscription.id.clone();
+    order.stock_reserved = reserve_order_stock(db, &order).await?;
+    if !order.stock_reserved {
+        eprintln!(
+            "Not enough stock to reserve subscription order for {:?}",
+            subscription.id
+        );
+    }
+
+    insert_order(db, order).await
+}
+
+/// Creates one order for every active subscription due at `now` and moves
+/// each of them on to its next run date. A subscription that fails is
+/// logged and retried on the next run.
+pub async fn run_due_subscriptions(db: &Db, now: DateTime<Utc>) -> ApiResult<Vec<Order>> {
+    let mut response = db
+        .query("SELECT * FROM subscription WHERE status = $status")
+        .bind(("status", SubscriptionStatus::Active))
+        .await?;
+    let subscriptions: Vec<Subscription> = response.take(0)?;
+
+    let mut orders = Vec::new();
+    for mut subscription in subscriptions.into_iter().filter(|s| s.next_run_at <= now) {
+        let Some(subscription_id) = subscription.id.clone() else {
+            continue;
+        };
+
+        match generate_order(db, &subscription).await {
+            Ok(order) => orders.push(order),
+            Err(e) => {
+                eprintln!("Failed to generate order for {}: {}", subscription_id, e);
+                continue;
+            }
+        }
+
+        subscription.advance_past(now);
+        subscription.updated_at = Some(now);
+        let _: Option<Subscription> = db
+            .update(record_key(&subscription_id))
+            .content(subscription)
+            .await?;
+    }
+
+    Ok(orders)
+}
@@ -0,0 +1,98 @@
+use crate::db::Db;
+use crate::error::ApiResult;
+use crate::models::{Grind, Product, ProductVariant, SkuSettings};
+
+/// Key of the single SKU settings record.
+pub const SKU_SETTINGS_KEY: (&str, &str) = ("sku_settings", "current");
+
+const PLACEHOLDERS: [&str; 3] = ["name", "size", "grind"];
+
+/// The stored SKU settings, or the defaults if none have been saved.
+pub async fn load_sku_settings(db: &Db) -> ApiResult<SkuSettings> {
+    let settings: Option<SkuSettings> = db.select(SKU_SETTINGS_KEY).await?;
+
+    Ok(settings.unwrap_or_default())
+}
+
+/// Checks that `template` only uses known placeholders and tells the variants
+/// of a product apart.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            return Err(format!("SKU template '{}' has an unclosed '{{'", template));
+        };
+        let placeholder = &rest[start + 1..start + end];
+        if !PLACEHOLDERS.contains(&placeholder) {
+            return Err(format!(
+                "SKU template '{}' has unknown placeholder '{{{}}}'",
+                template, placeholder
+            ));
+        }
+        rest = &rest[start + end + 1..];
+    }
+    if !template.contains("{size}") || !template.contains("{grind}") {
+        return Err(format!(
+            "SKU template '{}' must include {{size}} and {{grind}}",
+            template
+        ));
+    }
+    Ok(())
+}
+
+fn grind_code(grind: Grind) -> &'static str {
+    match grind {
+        Grind::WholeBean => "WB",
+        Grind::Espresso => "ES",
+        Grind::Filter => "FI",
+        Grind::Cafetiere => "CA",
+    }
+}
+
+/// The first three letters or digits of each word of `name`, upper-cased and
+/// cut to `length`.
+fn name_code(name: &str, length: usize) -> String {
+    let code: String = name
+        .split_whitespace()
+        .flat_map(|word| {
+            word.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .take(3)
+                .map(|c| c.to_ascii_uppercase())
+        })
+        .take(length)
+        .collect();
+    if code.is_empty() {
+        "SKU".to_string()
+    } else {
+        code
+    }
+}
+
+/// The SKU `settings` give `variant` of `product`.
+pub fn generate_sku(settings: &SkuSettings, product: &Product, variant: &ProductVariant) -> String {
+    settings
+        .template
+        .replace("{name}", &name_code(&product.name, settings.name_length))
+        .replace("{size}", &variant.package_size_grams.to_string())
+        .replace("{grind}", grind_code(variant.grind))
+}
+
+/// Gives every variant of `product` without a SKU a generated one, numbering
+/// it `-2`, `-3`, ... if the generated SKU is already in `taken`.
+pub fn assign_skus(settings: &SkuSettings, product: &mut Product, taken: &mut Vec<String>) {
+    for i in 0..product.variants.len() {
+        if product.variants[i].sku.is_some() {
+            continue;
+        }
+        let base = generate_sku(settings, product, &product.variants[i]);
+        let mut sku = base.clone();
+        let mut n = 1;
+        while taken.contains(&sku) {
+            n += 1;
+            sku = format!("{}-{}", base, n);
+        }
+        taken.push(sku.clone());
+        product.variants[i].sku = Some(sku);
+    }
+}
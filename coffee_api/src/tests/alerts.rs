@@ -0,0 +1,88 @@
+use super::{RecordingNotifier, app_with_notifier, migrated_db, seed_roast, send};
+use crate::scheduler;
+use axum::http::{self, StatusCode};
+use chrono::{Duration, Utc};
+use coffee_shared::models::{GreenCoffee, StockAlert, StockItemKind};
+use serde_json::json;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn low_stock_alerts_test() {
+    let db = migrated_db().await;
+    let notifier = Arc::new(RecordingNotifier::default());
+    let app = app_with_notifier(db.clone(), notifier.clone());
+
+    let mut green_ids = Vec::new();
+    for (name, stock_grams) in [("Low lot", 5000.0), ("Full lot", 20000.0)] {
+        let green_data = json!({
+            "name": name,
+            "origin_country": "Colombia",
+            "stock_grams": stock_grams,
+            "reorder_point_grams": 10000.0
+        });
+        let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+        let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+        green_ids.push(green.id.unwrap());
+    }
+
+    // Two recent 3 kg roasts count towards consumption, the old one does not.
+    // They were recorded before roasting took green out of stock
+    for days_ago in [2, 5, 60] {
+        let roast_data = json!({
+            "name": "Low lot roast",
+            "green_coffee": green_ids[0],
+            "date_roasted": Utc::now() - Duration::days(days_ago),
+            "roast_level": "Medium",
+            "batch_size_grams": 3000.0,
+            "yield_grams": 2550.0
+        });
+        seed_roast(&db, roast_data).await;
+    }
+
+    let product_data = json!({
+        "name": "Decaf 250g",
+        "roast": null,
+        "package_size_grams": 250.0,
+        "price": { "amount": "9.00", "currency": "USD" },
+        "stock_units": 2,
+        "reorder_point_units": 5
+    });
+    send(&app, http::Method::POST, "/products", product_data).await;
+
+    let (status, _) = send(&app, http::Method::GET, "/alerts?days=0", json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let uri = "/alerts?days=1000000000000";
+    let (status, _) = send(&app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, http::Method::GET, "/alerts?days=30", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let alerts: Vec<StockAlert> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(alerts.len(), 2);
+
+    // 6 kg over 30 days is 200 g a day, so 5 kg lasts 25 days
+    assert_eq!(alerts[0].item, green_ids[0]);
+    assert_eq!(alerts[0].kind, StockItemKind::GreenCoffee);
+    assert!((alerts[0].daily_consumption - 200.0).abs() < 1e-9);
+    assert!((alerts[0].days_of_cover.unwrap() - 25.0).abs() < 1e-9);
+
+    assert_eq!(alerts[1].kind, StockItemKind::Product);
+    assert_eq!(alerts[1].stock, 2.0);
+    assert_eq!(alerts[1].days_of_cover, None);
+
+    let (status, body) = send(&app, http::Method::POST, "/alerts/notify", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let notified: Vec<StockAlert> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(notified.len(), 2);
+
+    // The scheduler sends the same alerts without anyone asking
+    scheduler::notify_low_stock(&db, notifier.as_ref())
+        .await
+        .unwrap();
+    let sent = notifier.sent.lock().unwrap();
+    assert_eq!(sent.len(), 2);
+    for batch in sent.iter() {
+        let items: Vec<_> = batch.iter().map(|alert| &alert.item).collect();
+        assert_eq!(items, [&green_ids[0], &alerts[1].item]);
+    }
+}
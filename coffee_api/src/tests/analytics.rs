@@ -0,0 +1,81 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{GreenCoffee, RoastLossReport};
+use serde_json::json;
+
+#[tokio::test]
+async fn roast_loss_report_test() {
+    let app = app().await;
+
+    let green_data = json!({
+        "name": "Brazil Cerrado",
+        "origin_country": "Brazil",
+        "stock_grams": 20000.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    let green_id = green.id.unwrap();
+
+    let roasts = [
+        ("Medium", 850.0, "2024-03-02T09:00:00Z"),
+        ("medium", 845.0, "2024-03-09T09:00:00Z"),
+        ("Medium", 855.0, "2024-04-06T09:00:00Z"),
+        // 25% loss is far beyond the other medium roasts
+        ("Medium", 750.0, "2024-04-13T09:00:00Z"),
+        ("Dark", 800.0, "2024-04-13T10:00:00Z"),
+        // More out than went in: a weighing mistake
+        ("Light", 1020.0, "2024-04-20T09:00:00Z"),
+    ];
+    for (level, yield_grams, date) in roasts {
+        let roast_data = json!({
+            "name": format!("{} roast", level),
+            "green_coffee": green_id,
+            "roast_level": level,
+            "batch_size_grams": 1000.0,
+            "yield_grams": yield_grams,
+            "date_roasted": date
+        });
+        let (status, _) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, body) = send(&app, http::Method::GET, "/analytics/roast-loss", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: RoastLossReport = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(report.roasts.len(), 6);
+    let medium = report
+        .by_roast_level
+        .iter()
+        .find(|summary| summary.key == "medium")
+        .unwrap();
+    assert_eq!(medium.roasts, 4);
+    assert!((medium.average_loss_percent - 17.5).abs() < 1e-9);
+
+    assert_eq!(report.by_green_coffee.len(), 1);
+    assert_eq!(report.by_green_coffee[0].roasts, 6);
+    let months: Vec<&str> = report.by_month.iter().map(|m| m.key.as_str()).collect();
+    assert_eq!(months, ["2024-03", "2024-04"]);
+
+    // A lone dark roast has nothing of its own level to be judged against
+    let dark = report
+        .roasts
+        .iter()
+        .find(|loss| loss.roast_level == "Dark")
+        .unwrap();
+    assert_eq!(dark.expected_loss_percent, None);
+    assert!(!dark.outlier);
+
+    let mut outliers: Vec<f64> = report.outliers.iter().map(|o| o.loss_percent).collect();
+    outliers.sort_by(f64::total_cmp);
+    assert_eq!(outliers.len(), 2);
+    assert!((outliers[0] + 2.0).abs() < 1e-9);
+    assert!((outliers[1] - 25.0).abs() < 1e-9);
+
+    // A wide enough tolerance only leaves the impossible roast
+    let uri = "/analytics/roast-loss?tolerance=15";
+    let (_, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    let report: RoastLossReport = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report.outliers.len(), 1);
+    assert_eq!(report.outliers[0].roast_level, "Light");
+}
@@ -0,0 +1,145 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{BarcodeMatch, Product, SkuSettings};
+use serde_json::json;
+
+#[tokio::test]
+async fn sku_and_barcode_test() {
+    let app = app().await;
+
+    let product_data = json!({
+        "name": "Ethiopia Guji",
+        "description": "Natural process",
+        "roast": null,
+        "variants": [
+            {
+                "package_size_grams": 250.0,
+                "gtin": "4006381333931",
+                "price": { "amount": "12.50", "currency": "USD" },
+                "stock_units": 20
+            },
+            {
+                "package_size_grams": 1000.0,
+                "grind": "espresso",
+                "gtin": "036000291452",
+                "price": { "amount": "40.00", "currency": "USD" },
+                "stock_units": 5
+            }
+        ]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let product_id = product.id.clone().unwrap();
+    let skus: Vec<_> = product.variants.iter().map(|v| v.sku.clone()).collect();
+    assert_eq!(
+        skus,
+        [
+            Some("ETHGUJ-250-WB".to_string()),
+            Some("ETHGUJ-1000-ES".to_string())
+        ]
+    );
+    // The UPC-A is stored as the EAN-13 it prints as
+    assert_eq!(product.variants[1].gtin.as_deref(), Some("0036000291452"));
+
+    // A wrong check digit is rejected, a GTIN already in use conflicts
+    let variants_uri = format!("/products/{}/variants", product_id.id.to_raw());
+    let mut filter = json!({
+        "package_size_grams": 250.0,
+        "grind": "filter",
+        "gtin": "4006381333932",
+        "price": { "amount": "12.50", "currency": "USD" },
+        "stock_units": 8
+    });
+    let (status, _) = send(&app, http::Method::POST, &variants_uri, filter.clone()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    filter["gtin"] = json!("00036000291452");
+    let (status, _) = send(&app, http::Method::POST, &variants_uri, filter).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let settings = json!({ "template": "BEAN-{name}{size}" });
+    let (status, _) = send(&app, http::Method::PUT, "/sku-settings", settings).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let settings = json!({ "template": "{name}/{grind}/{size}", "name_length": 3 });
+    let (status, body) = send(&app, http::Method::PUT, "/sku-settings", settings).await;
+    assert_eq!(status, StatusCode::OK);
+    let settings: SkuSettings = serde_json::from_slice(&body).unwrap();
+    assert_eq!(settings.name_length, 3);
+
+    // Generated SKUs already in use get a number
+    for _ in 0..2 {
+        let product_data = json!({
+            "name": "Ethiopia Sidamo",
+            "roast": null,
+            "package_size_grams": 250.0,
+            "price": { "amount": "11.00", "currency": "USD" },
+            "stock_units": 10
+        });
+        send(&app, http::Method::POST, "/products", product_data).await;
+    }
+    let (_, body) = send(&app, http::Method::GET, "/products", json!({})).await;
+    let products: Vec<Product> = serde_json::from_slice(&body).unwrap();
+    let mut skus: Vec<_> = products
+        .iter()
+        .filter(|product| product.name == "Ethiopia Sidamo")
+        .filter_map(|product| product.variants[0].sku.clone())
+        .collect();
+    skus.sort();
+    assert_eq!(skus, ["ETH/WB/250", "ETH/WB/250-2"]);
+
+    let (status, body) = send(
+        &app,
+        http::Method::GET,
+        "/products/by-barcode/0036000291452",
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let found: BarcodeMatch = serde_json::from_slice(&body).unwrap();
+    assert_eq!(found.product.id, Some(product_id.clone()));
+    assert_eq!(found.variant, "1000g-espresso");
+
+    let (_, body) = send(
+        &app,
+        http::Method::GET,
+        "/products/by-barcode/ETHGUJ-250-WB",
+        json!({}),
+    )
+    .await;
+    let found: BarcodeMatch = serde_json::from_slice(&body).unwrap();
+    assert_eq!(found.variant, "250g-whole-bean");
+
+    let (status, _) = send(
+        &app,
+        http::Method::GET,
+        "/products/by-barcode/4006381333932",
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(
+        &app,
+        http::Method::GET,
+        "/products/by-barcode/5000000000005",
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let uri = format!("{}/250g-whole-bean/barcode", variants_uri);
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let svg = String::from_utf8(body).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(">006381<") && svg.contains(">333931<"));
+
+    let uri = format!(
+        "{}/250g-whole-bean/barcode?format=png&scale=3",
+        variants_uri
+    );
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.starts_with(b"\x89PNG"));
+    // 113 modules wide at 3 pixels each
+    assert_eq!(u32::from_be_bytes(body[16..20].try_into().unwrap()), 339);
+}
@@ -0,0 +1,243 @@
+use super::{app, app_with_db, migrated_db, seed_roast, send};
+use axum::Router;
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{
+    GreenCoffee, InventoryMovement, MovementReason, Product, Roast, RoastCost, TraceGraph,
+};
+use rust_decimal::Decimal;
+use serde_json::json;
+use surrealdb::sql::Thing;
+
+async fn stock_of(app: &Router, green: &Thing) -> f64 {
+    let uri = format!("/greens/{}", green.id.to_raw());
+    let (_, body) = send(app, http::Method::GET, &uri, json!({})).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    green.stock_grams
+}
+
+#[tokio::test]
+async fn blend_stock_cost_and_trace_test() {
+    let app = app().await;
+
+    let settings = json!({
+        "currency": "USD",
+        "packaging_per_unit": "0.50",
+        "labour_per_batch": "4.00",
+        "energy_per_batch": "1.00",
+        "min_margin_percent": "40"
+    });
+    send(&app, http::Method::PUT, "/cost-settings", settings).await;
+
+    let mut green_ids = Vec::new();
+    for (name, stock_grams, price) in [
+        ("Cerrado", 20000.0, "6.00"),
+        ("Huila", 10000.0, "8.00"),
+        ("Sidamo", 5000.0, "10.00"),
+    ] {
+        let green_data = json!({
+            "name": name,
+            "origin_country": "Unknown",
+            "stock_grams": stock_grams,
+            "price_per_kg": { "amount": price, "currency": "USD" }
+        });
+        let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+        let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+        green_ids.push(green.id.unwrap());
+    }
+
+    let espresso = |batch_size_grams: f64, percents: [f64; 3]| {
+        json!({
+            "name": "Espresso blend",
+            "blend": "pre_roast",
+            "components": [
+                { "green_coffee": green_ids[0], "percent": percents[0] },
+                { "green_coffee": green_ids[1], "percent": percents[1] },
+                { "green_coffee": green_ids[2], "percent": percents[2] }
+            ],
+            "roast_level": "Medium-Dark",
+            "batch_size_grams": batch_size_grams,
+            "yield_grams": batch_size_grams * 0.75
+        })
+    };
+
+    let (status, _) = send(
+        &app,
+        http::Method::POST,
+        "/roasts",
+        espresso(10000.0, [60.0, 30.0, 5.0]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(
+        &app,
+        http::Method::POST,
+        "/roasts",
+        espresso(10000.0, [60.0, 30.0, 10.0]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let blend: Roast = serde_json::from_slice(&body).unwrap();
+    let blend_id = blend.id.unwrap();
+    assert_eq!(stock_of(&app, &green_ids[0]).await, 14000.0);
+    assert_eq!(stock_of(&app, &green_ids[1]).await, 7000.0);
+    assert_eq!(stock_of(&app, &green_ids[2]).await, 4000.0);
+
+    // 6 kg of Sidamo is more than is left, so nothing is taken
+    let (status, _) = send(
+        &app,
+        http::Method::POST,
+        "/roasts",
+        espresso(60000.0, [60.0, 30.0, 10.0]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(stock_of(&app, &green_ids[0]).await, 14000.0);
+
+    // 36.00 + 24.00 + 10.00 of green and 5.00 of overheads over 7.5 kg
+    let uri = format!("/roasts/{}/cost", blend_id.id.to_raw());
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let cost: RoastCost = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cost.green_cost.amount, Decimal::new(70, 0));
+    assert_eq!(cost.cost_per_roasted_kg.amount, Decimal::new(10, 0));
+
+    // A post-roast blend of two single-origin roasts
+    let mut roast_ids = Vec::new();
+    for (green_id, batch_size_grams, yield_grams) in [
+        (&green_ids[0], 2000.0, 1600.0),
+        (&green_ids[2], 1000.0, 850.0),
+    ] {
+        let roast_data = json!({
+            "name": "Blend base",
+            "green_coffee": green_id,
+            "roast_level": "Medium",
+            "batch_size_grams": batch_size_grams,
+            "yield_grams": yield_grams
+        });
+        let (_, body) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+        let roast: Roast = serde_json::from_slice(&body).unwrap();
+        roast_ids.push(roast.id.unwrap());
+    }
+    let house_data = json!({
+        "name": "House blend",
+        "blend": "post_roast",
+        "components": [
+            { "roast": roast_ids[0], "percent": 80.0 },
+            { "roast": roast_ids[1], "percent": 20.0 }
+        ],
+        "roast_level": "Medium",
+        "batch_size_grams": 1000.0,
+        "yield_grams": 1000.0
+    });
+    let (status, body) = send(&app, http::Method::POST, "/roasts", house_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let house: Roast = serde_json::from_slice(&body).unwrap();
+    let house_id = house.id.unwrap();
+    assert_eq!(house.components[0].green_coffee, Some(green_ids[0].clone()));
+    assert_eq!(stock_of(&app, &green_ids[0]).await, 12000.0);
+
+    // 800 g of the Cerrado roast and 200 g of the Sidamo roast
+    let uri = format!("/roasts/{}/cost", house_id.id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let cost: RoastCost = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cost.total_cost.amount, Decimal::new(1203, 2));
+    assert!((cost.loss_percent - 19.0).abs() < 1e-9);
+
+    let uri = format!("/trace/{}", house_id);
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let graph: TraceGraph = serde_json::from_slice(&body).unwrap();
+    let blended = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.relation == "blended_from")
+        .count();
+    assert_eq!(blended, 2);
+    assert_eq!(graph.origins.len(), 2);
+
+    let uri = format!("/trace/{}", blend_id);
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let graph: TraceGraph = serde_json::from_slice(&body).unwrap();
+    assert_eq!(graph.origins.len(), 3);
+
+    // Deleting the blend puts its green back
+    let uri = format!("/roasts/{}", blend_id.id.to_raw());
+    let (status, _) = send(&app, http::Method::DELETE, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stock_of(&app, &green_ids[0]).await, 18000.0);
+
+    let (_, body) = send(&app, http::Method::GET, "/inventory-movements", json!({})).await;
+    let movements: Vec<InventoryMovement> = serde_json::from_slice(&body).unwrap();
+    let roasted: f64 = movements
+        .iter()
+        .filter(|movement| movement.reason == MovementReason::Roast)
+        .map(|movement| movement.quantity)
+        .sum();
+    assert_eq!(roasted, -3000.0);
+
+    // Roasts still blended or sold cannot be deleted
+    let product_data = json!({
+        "name": "House blend",
+        "description": "Our everyday coffee",
+        "package_size_grams": 250.0,
+        "price": { "amount": "11.00", "currency": "USD" },
+        "stock_units": 10,
+        "roast": house_id
+    });
+    let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let base_uri = format!("/roasts/{}", roast_ids[1].id.to_raw());
+    let (status, _) = send(&app, http::Method::DELETE, &base_uri, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let house_uri = format!("/roasts/{}", house_id.id.to_raw());
+    let (status, _) = send(&app, http::Method::DELETE, &house_uri, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let uri = format!("/products/{}", product.id.unwrap().id.to_raw());
+    send(&app, http::Method::DELETE, &uri, json!({})).await;
+    let (status, _) = send(&app, http::Method::DELETE, &house_uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, http::Method::DELETE, &base_uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn roasts_from_before_stock_deduction_test() {
+    let db = migrated_db().await;
+    let app = app_with_db(db.clone());
+
+    let green_data =
+        json!({ "name": "Huila", "origin_country": "Colombia", "stock_grams": 5000.0 });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    let green_id = green.id.unwrap();
+
+    let roast_data = json!({
+        "name": "Huila medium",
+        "green_coffee": green_id,
+        "roast_level": "Medium",
+        "batch_size_grams": 1000.0,
+        "yield_grams": 850.0
+    });
+    let old = seed_roast(&db, roast_data.clone()).await.id.unwrap();
+    let (_, body) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+    let new: Roast = serde_json::from_slice(&body).unwrap();
+    let new = new.id.unwrap();
+    assert_eq!(stock_of(&app, &green_id).await, 4000.0);
+
+    // The old roast never took its green, so editing or deleting it gives none back
+    let uri = format!("/roasts/{}", old.id.to_raw());
+    let update = json!({ "batch_size_grams": 1200.0 });
+    let (status, _) = send(&app, http::Method::PUT, &uri, update).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, http::Method::DELETE, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stock_of(&app, &green_id).await, 4000.0);
+
+    let uri = format!("/roasts/{}", new.id.to_raw());
+    let update = json!({ "batch_size_grams": 1500.0 });
+    send(&app, http::Method::PUT, &uri, update).await;
+    assert_eq!(stock_of(&app, &green_id).await, 3500.0);
+    send(&app, http::Method::DELETE, &uri, json!({})).await;
+    assert_eq!(stock_of(&app, &green_id).await, 5000.0);
+}
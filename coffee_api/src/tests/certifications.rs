@@ -0,0 +1,227 @@
+use super::{app, send};
+use axum::Router;
+use axum::http::{self, StatusCode};
+use chrono::{Duration, Utc};
+use coffee_shared::models::{BarcodeMatch, CertificationType, GreenCoffee, Product};
+use serde_json::{Value, json};
+use surrealdb::sql::Thing;
+
+async fn create(app: &Router, uri: &str, data: Value) -> Thing {
+    let (status, body) = send(app, http::Method::POST, uri, data).await;
+    assert_eq!(status, StatusCode::OK);
+    let created: Value = serde_json::from_slice(&body).unwrap();
+    serde_json::from_value(created["id"].clone()).unwrap()
+}
+
+async fn product_of(app: &Router, roast: &Thing, name: &str) -> Thing {
+    let product_data = json!({
+        "name": name,
+        "roast": roast,
+        "package_size_grams": 250.0,
+        "price": { "amount": "14.00", "currency": "USD" },
+        "stock_units": 10
+    });
+    create(app, "/products", product_data).await
+}
+
+async fn certifications_of(app: &Router, product: &Thing) -> Vec<CertificationType> {
+    let uri = format!("/products/{}", product.id.to_raw());
+    let (_, body) = send(app, http::Method::GET, &uri, json!({})).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    product.certifications
+}
+
+async fn listed(app: &Router, uri: &str) -> Vec<String> {
+    let (status, body) = send(app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let items: Vec<Value> = serde_json::from_slice(&body).unwrap();
+    let mut names: Vec<String> = items
+        .iter()
+        .map(|item| item["name"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn certifications_test() {
+    let app = app().await;
+    let next_year = Utc::now() + Duration::days(365);
+    let last_month = Utc::now() - Duration::days(30);
+
+    let sidama_data = json!({
+        "name": "Sidama",
+        "origin_country": "Ethiopia",
+        "stock_grams": 20000.0,
+        "producer": "Bensa cooperative",
+        "farm_gate_price_per_kg": { "amount": "4.80", "currency": "USD" },
+        "certifications": [
+            {
+                "type": "organic",
+                "certificate_number": "ET-BIO-154",
+                "expires_at": next_year,
+                "document": "https://example.com/certs/et-bio-154.pdf"
+            },
+            { "type": "fairtrade", "certificate_number": "FLO-3321", "expires_at": next_year }
+        ]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/greens", sidama_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let sidama: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    assert_eq!(sidama.producer.as_deref(), Some("Bensa cooperative"));
+    assert_eq!(sidama.certifications.len(), 2);
+    let sidama = sidama.id.unwrap();
+
+    // Fairtrade has lapsed on this lot
+    let cajamarca_data = json!({
+        "name": "Cajamarca",
+        "origin_country": "Peru",
+        "stock_grams": 20000.0,
+        "certifications": [
+            { "type": "organic", "certificate_number": "PE-ORG-88", "expires_at": next_year },
+            { "type": "fairtrade", "certificate_number": "FLO-1207", "expires_at": last_month }
+        ]
+    });
+    let cajamarca = create(&app, "/greens", cajamarca_data).await;
+    let cerrado_data = json!({
+        "name": "Cerrado",
+        "origin_country": "Brazil",
+        "stock_grams": 20000.0
+    });
+    let cerrado = create(&app, "/greens", cerrado_data).await;
+
+    let invalid = [
+        json!({ "certifications": [
+            { "type": "organic", "certificate_number": " ", "expires_at": next_year }
+        ] }),
+        json!({ "certifications": [
+            { "type": "organic", "certificate_number": "A-1", "expires_at": next_year },
+            { "type": "organic", "certificate_number": "A-2", "expires_at": next_year }
+        ] }),
+        json!({ "farm_gate_price_per_kg": { "amount": "-1.00", "currency": "USD" } }),
+    ];
+    let uri = format!("/greens/{}", cerrado.id.to_raw());
+    for update in invalid {
+        let (status, _) = send(&app, http::Method::PUT, &uri, update).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let roast = |name: &str, green: &Thing| {
+        json!({
+            "name": name,
+            "green_coffee": green,
+            "roast_level": "Medium",
+            "batch_size_grams": 1000.0,
+            "yield_grams": 850.0
+        })
+    };
+    let blend = |name: &str, method: &str, key: &str, parts: [&Thing; 2]| {
+        json!({
+            "name": name,
+            "blend": method,
+            "components": [
+                { key: parts[0], "percent": 50.0 },
+                { key: parts[1], "percent": 50.0 }
+            ],
+            "roast_level": "Medium",
+            "batch_size_grams": 1000.0,
+            "yield_grams": 850.0
+        })
+    };
+    let sidama_roast = create(&app, "/roasts", roast("Sidama", &sidama)).await;
+    let organic_blend = create(
+        &app,
+        "/roasts",
+        blend(
+            "Organic blend",
+            "pre_roast",
+            "green_coffee",
+            [&sidama, &cajamarca],
+        ),
+    )
+    .await;
+    let mixed_blend = create(
+        &app,
+        "/roasts",
+        blend(
+            "Mixed blend",
+            "pre_roast",
+            "green_coffee",
+            [&sidama, &cerrado],
+        ),
+    )
+    .await;
+    // A post-roast blend whose greens come partly through another blend
+    let house = create(
+        &app,
+        "/roasts",
+        blend(
+            "House",
+            "post_roast",
+            "roast",
+            [&sidama_roast, &organic_blend],
+        ),
+    )
+    .await;
+
+    let single_origin = product_of(&app, &sidama_roast, "Sidama single origin").await;
+    let house_product = product_of(&app, &house, "House blend").await;
+    let mixed_product = product_of(&app, &mixed_blend, "Mixed blend").await;
+
+    assert_eq!(
+        certifications_of(&app, &single_origin).await,
+        [CertificationType::Organic, CertificationType::Fairtrade]
+    );
+    assert_eq!(
+        certifications_of(&app, &house_product).await,
+        [CertificationType::Organic]
+    );
+    assert!(certifications_of(&app, &mixed_product).await.is_empty());
+
+    // Products carry their certifications wherever they are returned
+    let uri = format!("/products/{}", single_origin.id.to_raw());
+    let (status, body) = send(&app, http::Method::PUT, &uri, json!({ "sku": "SID-250" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let updated: Product = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        updated.certifications,
+        [CertificationType::Organic, CertificationType::Fairtrade]
+    );
+    let uri = "/products/by-barcode/SID-250";
+    let (status, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let found: BarcodeMatch = serde_json::from_slice(&body).unwrap();
+    assert_eq!(found.product.certifications, updated.certifications);
+
+    assert_eq!(
+        listed(&app, "/products?certification=organic").await,
+        ["House blend", "Sidama single origin"]
+    );
+    assert_eq!(
+        listed(&app, "/products?certification=fairtrade").await,
+        ["Sidama single origin"]
+    );
+    assert_eq!(
+        listed(&app, "/greens?certification=organic").await,
+        ["Cajamarca", "Sidama"]
+    );
+    assert_eq!(
+        listed(&app, "/greens?certification=fairtrade").await,
+        ["Sidama"]
+    );
+
+    // Renewing the lapsed certificate makes the house blend Fairtrade too
+    let renewed = json!({
+        "certifications": [
+            { "type": "organic", "certificate_number": "PE-ORG-88", "expires_at": next_year },
+            { "type": "fairtrade", "certificate_number": "FLO-1207", "expires_at": next_year }
+        ]
+    });
+    let uri = format!("/greens/{}", cajamarca.id.to_raw());
+    let (status, _) = send(&app, http::Method::PUT, &uri, renewed).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        certifications_of(&app, &house_product).await,
+        [CertificationType::Organic, CertificationType::Fairtrade]
+    );
+}
@@ -0,0 +1,93 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{GreenCoffee, Product, ProductCost, Roast, RoastCost};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+#[tokio::test]
+async fn roast_and_product_cost_test() {
+    let app = app().await;
+
+    let settings = json!({
+        "currency": "USD",
+        "packaging_per_unit": "0.50",
+        "labour_per_batch": "4.00",
+        "energy_per_batch": "1.00",
+        "min_margin_percent": "40"
+    });
+    let (status, _) = send(&app, http::Method::PUT, "/cost-settings", settings).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let green_data = json!({
+        "name": "Guatemala Huehuetenango",
+        "origin_country": "Guatemala",
+        "stock_grams": 10000.0,
+        "price_per_kg": { "amount": "10.00", "currency": "USD" }
+    });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+
+    let roast_data = json!({
+        "name": "Huehue City",
+        "green_coffee": green.id.unwrap(),
+        "roast_level": "Medium",
+        "batch_size_grams": 1000.0,
+        "yield_grams": 800.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+    let roast: Roast = serde_json::from_slice(&body).unwrap();
+    let roast_id = roast.id.unwrap();
+
+    // 10.00 of green plus 5.00 of overheads over 0.8 roasted kilos
+    let uri = format!("/roasts/{}/cost", roast_id.id.to_raw());
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let cost: RoastCost = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cost.total_cost.amount, Decimal::new(15, 0));
+    assert_eq!(cost.cost_per_roasted_kg.amount, Decimal::new(1875, 2));
+    assert!((cost.loss_percent - 20.0).abs() < 1e-9);
+
+    let mut product_ids = Vec::new();
+    for price in ["8.00", "12.00"] {
+        let product_data = json!({
+            "name": format!("Huehue City 250g at {}", price),
+            "package_size_grams": 250.0,
+            "price": { "amount": price, "currency": "USD" },
+            "stock_units": 10,
+            "roast": roast_id
+        });
+        let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+        let product: Product = serde_json::from_slice(&body).unwrap();
+        product_ids.push(product.id.unwrap());
+    }
+
+    // 0.25 kg at 18.75 plus 0.50 packaging
+    let uri = format!("/products/{}/cost", product_ids[0].id.to_raw());
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let cost: ProductCost = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cost.unit_cost.amount, Decimal::new(519, 2));
+    assert_eq!(cost.margin.amount, Decimal::new(281, 2));
+    assert!(cost.below_threshold);
+
+    let (status, body) = send(&app, http::Method::GET, "/products/margins", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let margins: Vec<ProductCost> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(margins.len(), 2);
+    assert_eq!(margins[0].product, product_ids[0]);
+    assert!(!margins[1].below_threshold);
+}
+
+#[tokio::test]
+async fn cost_settings_reject_negative_overheads_test() {
+    let app = app().await;
+
+    let settings = json!({ "packaging_per_unit": "-0.10" });
+    let (status, _) = send(&app, http::Method::PUT, "/cost-settings", settings).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, http::Method::GET, "/cost-settings", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let settings: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(settings["packaging_per_unit"], "0");
+}
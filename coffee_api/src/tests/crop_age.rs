@@ -0,0 +1,139 @@
+use super::{app, send};
+use axum::Router;
+use axum::http::{self, StatusCode};
+use chrono::{Datelike, Duration, Utc};
+use coffee_shared::models::{CropAge, CropAgeStatus, GreenCoffee};
+use serde_json::{Value, json};
+use surrealdb::sql::Thing;
+
+async fn green(app: &Router, data: Value) -> Thing {
+    let (_, body) = send(app, http::Method::POST, "/greens", data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    green.id.unwrap()
+}
+
+fn names(lots: &[CropAge]) -> Vec<&str> {
+    lots.iter().map(|lot| lot.name.as_str()).collect()
+}
+
+#[tokio::test]
+async fn crop_age_test() {
+    let app = app().await;
+    let year = Utc::now().year();
+
+    let settings = json!({
+        "origins": [
+            { "origin_country": "Ethiopia", "harvest_month": 1, "past_crop_months": 12 },
+            { "origin_country": "Brazil", "harvest_month": 6, "past_crop_months": 12 }
+        ],
+        "warning_days": 60
+    });
+    let (status, _) = send(&app, http::Method::PUT, "/crop-age-settings", settings).await;
+    assert_eq!(status, StatusCode::OK);
+    for invalid in [
+        json!({ "origins": [{ "origin_country": "Kenya", "harvest_month": 13, "past_crop_months": 12 }] }),
+        json!({ "default_rule": { "harvest_month": 1, "past_crop_months": u32::MAX } }),
+        json!({ "warning_days": i64::MAX }),
+    ] {
+        let (status, _) = send(&app, http::Method::PUT, "/crop-age-settings", invalid).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let sidama = green(
+        &app,
+        json!({
+            "name": "Sidama",
+            "origin_country": "Ethiopia",
+            "harvest_year": year - 2,
+            "stock_grams": 5000.0
+        }),
+    )
+    .await;
+    green(
+        &app,
+        json!({
+            "name": "Guji remnant",
+            "origin_country": " ethiopia",
+            "harvest_year": year - 2,
+            "stock_grams": 500.0
+        }),
+    )
+    .await;
+    // Aged from its arrival as it has no harvest year
+    green(
+        &app,
+        json!({
+            "name": "Cerrado",
+            "origin_country": "Brazil",
+            "arrival_date": Utc::now() - Duration::days(330),
+            "stock_grams": 8000.0
+        }),
+    )
+    .await;
+    green(
+        &app,
+        json!({
+            "name": "Huila",
+            "origin_country": "Colombia",
+            "arrival_date": Utc::now() - Duration::days(10),
+            "stock_grams": 12000.0
+        }),
+    )
+    .await;
+    green(
+        &app,
+        json!({
+            "name": "Used up",
+            "origin_country": "Ethiopia",
+            "harvest_year": year - 3,
+            "stock_grams": 0.0
+        }),
+    )
+    .await;
+    let undated = green(
+        &app,
+        json!({ "name": "Undated", "origin_country": "Kenya", "stock_grams": 1000.0 }),
+    )
+    .await;
+
+    let uri = format!("/greens/{}/crop-age", sidama.id.to_raw());
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let crop_age: CropAge = serde_json::from_slice(&body).unwrap();
+    assert_eq!(crop_age.status, CropAgeStatus::PastCrop);
+    assert_eq!(crop_age.crop_date.year(), year - 2);
+    assert_eq!(crop_age.past_crop_date.year(), year - 1);
+    assert!(crop_age.days_until_past_crop < 0);
+
+    let uri = format!("/greens/{}/crop-age", undated.id.to_raw());
+    let (status, _) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = send(&app, http::Method::GET, "/greens/past-crop", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let flagged: Vec<CropAge> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(flagged.len(), 3);
+    let cerrado = &flagged[2];
+    assert_eq!(cerrado.name, "Cerrado");
+    assert_eq!(cerrado.status, CropAgeStatus::NearingPastCrop);
+
+    // Only lots already past-crop with no warning window
+    let uri = "/greens/past-crop?days=0";
+    let (_, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    let flagged: Vec<CropAge> = serde_json::from_slice(&body).unwrap();
+    assert!(
+        flagged
+            .iter()
+            .all(|lot| lot.status == CropAgeStatus::PastCrop)
+    );
+    assert_eq!(flagged.len(), 2);
+    let uri = format!("/greens/past-crop?days={}", i64::MAX);
+    let (status, _) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Oldest first, and the smaller of two equally old lots before the other
+    let (status, body) = send(&app, http::Method::GET, "/greens/use-first", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let lots: Vec<CropAge> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(names(&lots), ["Guji remnant", "Sidama", "Cerrado", "Huila"]);
+}
@@ -0,0 +1,124 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{CuppingAggregate, CuppingSession, GreenCoffee, Roast};
+use serde_json::{Value, json};
+
+fn scores(overall: f64) -> Value {
+    json!({
+        "fragrance": 8.0,
+        "flavour": 8.0,
+        "aftertaste": 7.75,
+        "acidity": 8.0,
+        "body": 7.5,
+        "balance": 7.75,
+        "uniformity": 10.0,
+        "clean_cup": 10.0,
+        "sweetness": 10.0,
+        "overall": overall
+    })
+}
+
+#[tokio::test]
+async fn cupping_session_scores_test() {
+    let app = app().await;
+
+    let green_data = json!({
+        "name": "Guji",
+        "origin_country": "Ethiopia",
+        "stock_grams": 60000.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    let green_id = green.id.unwrap();
+
+    let roast_data = json!({
+        "name": "Guji 0412",
+        "green_coffee": green_id,
+        "roast_level": "Light",
+        "batch_size_grams": 12000.0,
+        "yield_grams": 10200.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+    let roast: Roast = serde_json::from_slice(&body).unwrap();
+    let roast_id = roast.id.unwrap();
+
+    let session_data = json!({
+        "name": "Thursday QC",
+        "cuppers": ["Ana", "Ben"],
+        "samples": [
+            { "label": "A", "kind": "arrival", "green_coffee": green_id },
+            { "label": "B", "kind": "production", "roast": roast_id },
+            { "label": "C", "kind": "pre_shipment", "description": "Sidama offer" }
+        ]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/cupping-sessions", session_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let session: CuppingSession = serde_json::from_slice(&body).unwrap();
+    // Production samples pick up the roast's green
+    assert_eq!(session.samples[1].green_coffee, Some(green_id.clone()));
+    let forms_uri = format!(
+        "/cupping-sessions/{}/forms",
+        session.id.unwrap().id.to_raw()
+    );
+
+    let form = json!({"cupper": "Ana", "sample": 0, "scores": scores(8.1)});
+    let (status, _) = send(&app, http::Method::POST, &forms_uri, form).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let form = json!({"cupper": "Cleo", "sample": 0, "scores": scores(8.0)});
+    let (status, _) = send(&app, http::Method::POST, &forms_uri, form).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // Counts that would overflow are rejected rather than wrapping
+    let form = json!({
+        "cupper": "Ana",
+        "sample": 0,
+        "scores": scores(8.0),
+        "defects": { "taint_cups": u32::MAX, "fault_cups": 1 }
+    });
+    let (status, _) = send(&app, http::Method::POST, &forms_uri, form).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    for form in [
+        json!({"cupper": "Ana", "sample": 0, "scores": scores(8.0)}),
+        json!({"cupper": "Ben", "sample": 0, "scores": scores(7.0)}),
+        // Ben re-scores sample A
+        json!({"cupper": "Ben", "sample": 0, "scores": scores(7.5)}),
+        json!({
+            "cupper": "Ana",
+            "sample": 1,
+            "scores": scores(8.0),
+            "defects": { "taint_cups": 1 }
+        }),
+    ] {
+        let (status, _) = send(&app, http::Method::POST, &forms_uri, form).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (_, body) = send(&app, http::Method::GET, "/cupping-sessions", json!({})).await;
+    let sessions: Vec<CuppingSession> = serde_json::from_slice(&body).unwrap();
+    let forms = &sessions[0].forms;
+    assert_eq!(forms.len(), 3);
+    assert_eq!(forms[0].total_score, 85.0);
+    assert_eq!(forms[2].final_score, 83.0);
+
+    let uri = format!("/greens/{}/cupping-scores", green_id.id.to_raw());
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let scores: CuppingAggregate = serde_json::from_slice(&body).unwrap();
+    assert_eq!(scores.sessions, 1);
+    assert_eq!(scores.forms, 3);
+    assert_eq!(scores.min_final_score, Some(83.0));
+    assert_eq!(scores.max_final_score, Some(85.0));
+
+    let uri = format!("{}?kind=arrival", uri);
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let scores: CuppingAggregate = serde_json::from_slice(&body).unwrap();
+    assert_eq!(scores.forms, 2);
+    assert_eq!(scores.mean_final_score, Some(84.75));
+    assert_eq!(scores.mean_scores.unwrap().overall, 7.75);
+
+    let uri = format!("/roasts/{}/cupping-scores", roast_id.id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let scores: CuppingAggregate = serde_json::from_slice(&body).unwrap();
+    assert_eq!(scores.forms, 1);
+    assert_eq!(scores.mean_defect_points, Some(2.0));
+}
@@ -0,0 +1,123 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{Customer, Order, Product, Subscription, SubscriptionStatus};
+use serde_json::{Value, json};
+
+#[tokio::test]
+async fn create_customer_deduplicates_email_test() {
+    let app = app().await;
+
+    let customer_data = json!({
+        "name": "Ada Lovelace",
+        "email": "ada@example.com",
+        "addresses": [{
+            "label": "Home",
+            "line1": "12 Analytical Row",
+            "line2": null,
+            "city": "London",
+            "region": null,
+            "postal_code": "N1 1AA",
+            "country": "GB"
+        }],
+        "marketing_consent": true
+    });
+    let (status, body) = send(&app, http::Method::POST, "/customers", customer_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let customer: Customer = serde_json::from_slice(&body).unwrap();
+    assert_eq!(customer.addresses.len(), 1);
+
+    let duplicate = json!({"name": "A. Lovelace", "email": "  ADA@example.com "});
+    let (status, _) = send(&app, http::Method::POST, "/customers", duplicate).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let malformed = json!({"name": "Charles Babbage", "email": "charles@"});
+    let (status, _) = send(&app, http::Method::POST, "/customers", malformed).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let uri = format!("/customers/{}", customer.id.unwrap().id.to_raw());
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        &uri,
+        json!({"email": "ada at example"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn erase_customer_keeps_orders_test() {
+    let app = app().await;
+
+    let customer_data = json!({"name": "Grace Hopper", "email": "grace@example.com"});
+    let (_, body) = send(&app, http::Method::POST, "/customers", customer_data).await;
+    let customer: Customer = serde_json::from_slice(&body).unwrap();
+    let customer_thing = customer.id.unwrap();
+    let customer_id = customer_thing.id.to_raw();
+
+    let product_data = json!({
+        "name": "Test Product",
+        "package_size_grams": 250.0,
+        "price": { "amount": "10.00", "currency": "EUR" },
+        "stock_units": 10,
+        "roast": null
+    });
+    let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+
+    let subscription_data = json!({
+        "customer": customer_thing,
+        "product": product.id.clone().unwrap(),
+        "quantity": 1,
+        "interval_weeks": 4
+    });
+    let (_, body) = send(
+        &app,
+        http::Method::POST,
+        "/subscriptions",
+        subscription_data,
+    )
+    .await;
+    let subscription: Subscription = serde_json::from_slice(&body).unwrap();
+
+    let order_data = json!({
+        "customer": customer_thing,
+        "lines": [{ "product": product.id.unwrap(), "quantity": 1 }]
+    });
+    let (status, _) = send(&app, http::Method::POST, "/orders", order_data.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Customers with orders must be erased rather than deleted
+    let uri = format!("/customers/{}", customer_id);
+    let (status, _) = send(&app, http::Method::DELETE, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let uri = format!("/customers/{}/export", customer_id);
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let export: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(export["customer"]["email"], "grace@example.com");
+    assert_eq!(export["orders"].as_array().unwrap().len(), 1);
+
+    let uri = format!("/customers/{}/erase", customer_id);
+    let (status, body) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let erased: Customer = serde_json::from_slice(&body).unwrap();
+    assert!(erased.erased_at.is_some());
+    assert_ne!(erased.name, "Grace Hopper");
+    assert!(!erased.email.contains("grace"));
+
+    let uri = format!("/customers/{}/orders", customer_id);
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let orders: Vec<Order> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(orders.len(), 1);
+
+    let uri = format!("/subscriptions/{}", subscription.id.unwrap().id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let subscription: Subscription = serde_json::from_slice(&body).unwrap();
+    assert_eq!(subscription.status, SubscriptionStatus::Cancelled);
+
+    // Erased customers cannot place new orders
+    let (status, _) = send(&app, http::Method::POST, "/orders", order_data).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
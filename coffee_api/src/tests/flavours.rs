@@ -0,0 +1,99 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{FlavourAlias, GreenCoffee, NoteMapping, Product, Roast};
+use serde_json::json;
+
+#[tokio::test]
+async fn filter_by_flavour_category_test() {
+    let app = app().await;
+
+    let mut greens = Vec::new();
+    for (name, notes) in [
+        ("Nyeri", json!(["Citrusy", "Floral"])),
+        ("Yirgacheffe", json!(["lemon zest", "Jasmine"])),
+        ("Cerrado", json!(["Chocolate", "Caramel"])),
+    ] {
+        let green_data = json!({
+            "name": name,
+            "origin_country": "Unknown",
+            "stock_grams": 1000.0,
+            "cupping_notes": notes
+        });
+        let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+        let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+        greens.push(green);
+    }
+
+    // A citrus roast of the chocolatey green
+    let roast_data = json!({
+        "name": "Cerrado light",
+        "green_coffee": greens[2].id,
+        "roast_level": "Light",
+        "batch_size_grams": 1000.0,
+        "yield_grams": 850.0,
+        "notes": ["Grapefruit"]
+    });
+    let (_, body) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+    let roast: Roast = serde_json::from_slice(&body).unwrap();
+    let product_data = json!({
+        "roast": roast.id,
+        "name": "Cerrado Light 250g",
+        "package_size_grams": 250.0,
+        "price": { "amount": "14.00", "currency": "USD" },
+        "stock_units": 10
+    });
+    let (status, _) = send(&app, http::Method::POST, "/products", product_data).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let alias = json!({"alias": "Lemon Zest", "category": "Fruity > Citrus > Lemon"});
+    let (status, body) = send(&app, http::Method::POST, "/flavour-aliases", alias).await;
+    assert_eq!(status, StatusCode::OK);
+    let alias: FlavourAlias = serde_json::from_slice(&body).unwrap();
+    assert_eq!(alias.category, "fruity > citrus > lemon");
+
+    let alias = json!({"alias": "lemon-zest", "category": "fruity"});
+    let (status, _) = send(&app, http::Method::POST, "/flavour-aliases", alias).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let alias = json!({"alias": "Caramel", "category": "sweet > caramel"});
+    let (status, _) = send(&app, http::Method::POST, "/flavour-aliases", alias).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = "/greens?flavour=fruity%20%3E%20citrus";
+    let (status, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let citrus: Vec<GreenCoffee> = serde_json::from_slice(&body).unwrap();
+    let mut names: Vec<&str> = citrus.iter().map(|green| green.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Nyeri", "Yirgacheffe"]);
+
+    let uri = "/greens?flavour=Nutty%2FCocoa";
+    let (_, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    let cocoa: Vec<GreenCoffee> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cocoa.len(), 1);
+    assert_eq!(cocoa[0].name, "Cerrado");
+
+    let uri = "/greens?flavour=fruity%20%3E%20citrusy";
+    let (status, _) = send(&app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Products match on their roast's notes as well as the green's
+    let uri = "/products?flavour=fruity%20%3E%20citrus";
+    let (_, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    let products: Vec<Product> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(products.len(), 1);
+    let uri = "/products?flavour=floral";
+    let (_, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    let products: Vec<Product> = serde_json::from_slice(&body).unwrap();
+    assert!(products.is_empty());
+
+    let (_, body) = send(&app, http::Method::GET, "/flavours/notes", json!({})).await;
+    let notes: Vec<NoteMapping> = serde_json::from_slice(&body).unwrap();
+    let unmapped: Vec<&str> = notes
+        .iter()
+        .filter(|note| note.category.is_none())
+        .map(|note| note.note.as_str())
+        .collect();
+    assert_eq!(unmapped, vec!["Caramel"]);
+    let grapefruit = notes.iter().find(|note| note.note == "Grapefruit").unwrap();
+    assert_eq!(grapefruit.roasts, 1);
+}
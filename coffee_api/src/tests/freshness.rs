@@ -0,0 +1,129 @@
+use super::{app, send};
+use axum::Router;
+use axum::http::{self, StatusCode};
+use chrono::{Duration, Utc};
+use coffee_shared::models::{
+    FifoRecommendations, FreshnessStatus, GreenCoffee, Product, Roast, RoastFreshness,
+    VariantFreshness,
+};
+use serde_json::json;
+use surrealdb::sql::Thing;
+
+async fn roast_days_ago(app: &Router, green: &Thing, name: &str, level: &str, days: i64) -> Thing {
+    let roast_data = json!({
+        "name": name,
+        "green_coffee": green,
+        "date_roasted": Utc::now() - Duration::days(days),
+        "roast_level": level,
+        "batch_size_grams": 1000.0,
+        "yield_grams": 850.0
+    });
+    let (_, body) = send(app, http::Method::POST, "/roasts", roast_data).await;
+    let roast: Roast = serde_json::from_slice(&body).unwrap();
+    roast.id.unwrap()
+}
+
+async fn product_of(app: &Router, roast: &Thing, name: &str, stock_units: i32) -> Thing {
+    let product_data = json!({
+        "name": name,
+        "roast": roast,
+        "package_size_grams": 250.0,
+        "price": { "amount": "12.00", "currency": "USD" },
+        "stock_units": stock_units
+    });
+    let (_, body) = send(app, http::Method::POST, "/products", product_data).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    product.id.unwrap()
+}
+
+#[tokio::test]
+async fn freshness_test() {
+    let app = app().await;
+
+    let green_data = json!({
+        "name": "Cerrado",
+        "origin_country": "Brazil",
+        "stock_grams": 10000.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    let green_id = green.id.unwrap();
+
+    // Dark roasts are ready sooner and keep for less time
+    let settings = json!({
+        "roast_levels": [
+            { "roast_level": "Dark", "rest_days": 2, "peak_days": 14, "shelf_life_days": 60 }
+        ]
+    });
+    let (status, _) = send(&app, http::Method::PUT, "/freshness-settings", settings).await;
+    assert_eq!(status, StatusCode::OK);
+    for invalid in [
+        json!({ "default_rule": { "rest_days": 10, "peak_days": 5, "shelf_life_days": 60 } }),
+        json!({ "default_rule": { "rest_days": 2, "peak_days": 14, "shelf_life_days": i64::MAX } }),
+    ] {
+        let (status, _) = send(&app, http::Method::PUT, "/freshness-settings", invalid).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let fresh = roast_days_ago(&app, &green_id, "Fresh", "Medium", 3).await;
+    let peak = roast_days_ago(&app, &green_id, "Peak", "Medium", 20).await;
+    let old_dark = roast_days_ago(&app, &green_id, "Old dark", " dark", 50).await;
+    let stale = roast_days_ago(&app, &green_id, "Stale", "Medium", 200).await;
+
+    let expected = [
+        (&fresh, FreshnessStatus::Resting),
+        (&peak, FreshnessStatus::Peak),
+        (&old_dark, FreshnessStatus::Ageing),
+        (&stale, FreshnessStatus::Expired),
+    ];
+    for (roast, status) in expected {
+        let uri = format!("/roasts/{}/freshness", roast.id.to_raw());
+        let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+        let freshness: RoastFreshness = serde_json::from_slice(&body).unwrap();
+        assert_eq!(freshness.freshness.status, status);
+    }
+
+    let fresh_product = product_of(&app, &fresh, "Fresh bags", 10).await;
+    let peak_product = product_of(&app, &peak, "Peak bags", 10).await;
+    let dark_product = product_of(&app, &old_dark, "Dark bags", 4).await;
+    let stale_product = product_of(&app, &stale, "Stale bags", 2).await;
+
+    // A product's own rule wins over its roast level's
+    let uri = format!("/products/{}", peak_product.id.to_raw());
+    let rule = json!({ "freshness": { "rest_days": 2, "peak_days": 10, "shelf_life_days": 30 } });
+    let (status, _) = send(&app, http::Method::PUT, &uri, rule).await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/products/{}/freshness", peak_product.id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let variants: Vec<VariantFreshness> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(variants[0].freshness.status, FreshnessStatus::Ageing);
+
+    let (_, body) = send(&app, http::Method::GET, "/freshness/fifo", json!({})).await;
+    let fifo: FifoRecommendations = serde_json::from_slice(&body).unwrap();
+    let packing: Vec<_> = fifo
+        .packing
+        .iter()
+        .map(|r| r.freshness.roast.clone())
+        .collect();
+    assert_eq!(packing, [old_dark.clone(), peak.clone()]);
+    let shipping: Vec<_> = fifo.shipping.iter().map(|v| v.product.clone()).collect();
+    assert_eq!(
+        shipping,
+        [dark_product.clone(), peak_product.clone(), fresh_product]
+    );
+
+    let (_, body) = send(
+        &app,
+        http::Method::GET,
+        "/freshness/expiring?days=14",
+        json!({}),
+    )
+    .await;
+    let expiring: Vec<VariantFreshness> = serde_json::from_slice(&body).unwrap();
+    let expiring: Vec<_> = expiring.iter().map(|v| v.product.clone()).collect();
+    assert_eq!(expiring, [stale_product, peak_product, dark_product]);
+
+    let uri = format!("/freshness/expiring?days={}", i64::MAX);
+    let (status, _) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
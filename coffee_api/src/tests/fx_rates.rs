@@ -0,0 +1,84 @@
+use super::{app, send};
+use axum::body::{Body, to_bytes};
+use axum::{
+    Router,
+    http::{self, Request, StatusCode},
+};
+use coffee_shared::models::{FxRate, Order, Product};
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+async fn import(app: &Router, csv: &str) -> (StatusCode, Vec<u8>) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/fx-rates/import")
+                .header(http::header::CONTENT_TYPE, "text/csv")
+                .body(Body::from(csv.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), 1_000_000).await.unwrap();
+    (status, body.to_vec())
+}
+
+#[tokio::test]
+async fn import_and_convert_prices_test() {
+    let app = app().await;
+
+    // Nothing is saved when any line is invalid
+    let (status, body) = import(&app, "base,quote,rate\nUSD,EUR,0.92\nUSD,XYZ,1.5\n").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error: Value = serde_json::from_slice(&body).unwrap();
+    assert!(error.to_string().contains("Line 3"));
+
+    let (status, body) = import(&app, "base,quote,rate\nUSD,EUR,0.92\nGBP,EUR,1.15\n").await;
+    assert_eq!(status, StatusCode::OK);
+    let rates: Vec<FxRate> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(rates.len(), 2);
+
+    let product_data = json!({
+        "name": "Kenya AA 250g",
+        "package_size_grams": 250.0,
+        "price": { "amount": "10.00", "currency": "USD" },
+        "stock_units": 10,
+        "roast": null
+    });
+    let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let product_id = product.id.unwrap();
+
+    let uri = format!("/products/{}?currency=eur", product_id.id.to_raw());
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let converted: Product = serde_json::from_slice(&body).unwrap();
+    assert_eq!(converted.variants[0].price.amount, Decimal::new(920, 2));
+    assert_eq!(converted.variants[0].price.currency.code(), "EUR");
+
+    let rate_data = json!({ "base": "EUR", "quote": "JPY", "rate": "160" });
+    let (status, _) = send(&app, http::Method::POST, "/fx-rates", rate_data).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Rates are not chained, so there is still no USD to JPY rate
+    let uri = format!("/products/{}?currency=JPY", product_id.id.to_raw());
+    let (status, _) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let order_data = json!({
+        "customer": null,
+        "currency": "EUR",
+        "lines": [{ "product": product_id, "quantity": 3 }]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/orders", order_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.lines[0].unit_price.amount, Decimal::new(920, 2));
+    assert_eq!(order.total.amount, Decimal::new(2760, 2));
+    assert_eq!(order.total.currency.code(), "EUR");
+}
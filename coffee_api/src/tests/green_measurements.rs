@@ -0,0 +1,83 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{
+    GreenCoffee, GreenMeasurement, QualityMetric, QualityRanges, QualityWarning, RangeBreach,
+    RecordedMeasurement,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn green_measurement_warnings_test() {
+    let app = app().await;
+
+    let green_data = json!({
+        "name": "Sidama",
+        "origin_country": "Ethiopia",
+        "stock_grams": 30000.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    let green_id = green.id.unwrap();
+    let uri = format!("/greens/{}/measurements", green_id.id.to_raw());
+
+    let (status, _) = send(&app, http::Method::POST, &uri, json!({"notes": "?"})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // In range on arrival
+    let arrival = json!({
+        "measured_at": "2024-03-01T00:00:00Z",
+        "moisture_percent": 10.8,
+        "density_g_per_l": 720.0,
+        "water_activity": 0.58
+    });
+    let (status, body) = send(&app, http::Method::POST, &uri, arrival).await;
+    assert_eq!(status, StatusCode::OK);
+    let recorded: RecordedMeasurement = serde_json::from_slice(&body).unwrap();
+    assert!(recorded.warnings.is_empty());
+
+    // Drying out over the summer
+    let later = json!({
+        "measured_at": "2024-09-01T00:00:00Z",
+        "moisture_percent": 8.6,
+        "water_activity": 0.47
+    });
+    let (_, body) = send(&app, http::Method::POST, &uri, later).await;
+    let recorded: RecordedMeasurement = serde_json::from_slice(&body).unwrap();
+    assert_eq!(recorded.warnings.len(), 1);
+    let warning = &recorded.warnings[0];
+    assert_eq!(warning.metric, QualityMetric::MoisturePercent);
+    assert_eq!(warning.breach, RangeBreach::Below);
+    assert!((warning.change_since_first.unwrap() + 2.2).abs() < 1e-9);
+
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let measurements: Vec<GreenMeasurement> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(measurements.len(), 2);
+    assert_eq!(measurements[0].moisture_percent, Some(10.8));
+
+    // Tightening water activity adds a second warning
+    let ranges = json!({"water_activity": { "min": 0.5, "max": 0.65 }});
+    let (status, body) = send(&app, http::Method::PUT, "/quality-ranges", ranges).await;
+    assert_eq!(status, StatusCode::OK);
+    let ranges: QualityRanges = serde_json::from_slice(&body).unwrap();
+    assert_eq!(ranges.moisture_percent.min, Some(9.0));
+
+    let bad_ranges = json!({"moisture_percent": { "min": 12.0, "max": 9.0 }});
+    let (status, _) = send(&app, http::Method::PUT, "/quality-ranges", bad_ranges).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let warnings_uri = "/greens/quality-warnings";
+    let (status, body) = send(&app, http::Method::GET, warnings_uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let warnings: Vec<QualityWarning> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(warnings.len(), 2);
+    assert!(
+        warnings
+            .iter()
+            .all(|warning| warning.green_coffee == green_id)
+    );
+    assert!(
+        warnings
+            .iter()
+            .any(|warning| warning.metric == QualityMetric::WaterActivity)
+    );
+}
@@ -1,10 +1,10 @@
-use super::app;
+use super::{app, send};
 use axum::body::to_bytes;
 use axum::{
     body::Body,
     http::{self, Request, StatusCode},
 };
-use coffee_shared::models::GreenCoffee;
+use coffee_shared::models::{GreenCoffee, Supplier};
 use serde_json::json;
 use tower::ServiceExt;
 
@@ -12,6 +12,11 @@ use tower::ServiceExt;
 async fn create_green_coffee_test() {
     let app = app().await;
 
+    let supplier_data = json!({"name": "Test Supplier"});
+    let (_, body) = send(&app, http::Method::POST, "/suppliers", supplier_data).await;
+    let supplier: Supplier = serde_json::from_slice(&body).unwrap();
+    let supplier_id = supplier.id.unwrap();
+
     let green_coffee_data = json!({
         "name": "Test Green Coffee",
         "origin_country": "Ethiopia",
@@ -21,9 +26,8 @@ async fn create_green_coffee_test() {
         "altitude_masl": 2000,
         "harvest_year": 2023,
         "stock_grams": 1000.0,
-        "price_per_kg": 25.0,
-        "price_currency": "USD",
-        "supplier": "Test Supplier",
+        "price_per_kg": { "amount": "25.00", "currency": "USD" },
+        "supplier": supplier_id,
         "cupping_notes": ["Floral", "Citrus"]
     });
 
@@ -45,4 +49,5 @@ async fn create_green_coffee_test() {
     let green_coffee: GreenCoffee = serde_json::from_slice(&body).unwrap();
     assert_eq!(green_coffee.name, "Test Green Coffee");
     assert_eq!(green_coffee.origin_country, "Ethiopia");
+    assert_eq!(green_coffee.supplier, Some(supplier_id));
 }
@@ -0,0 +1,170 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{BagLabel, GreenCoffee, LabelTemplate, Order, Product, Roast};
+use serde_json::json;
+
+#[tokio::test]
+async fn bag_labels_test() {
+    let app = app().await;
+
+    let green_data = json!({
+        "name": "Guji Natural",
+        "origin_country": "Ethiopia",
+        "region": "Guji",
+        "processing_method": "Natural",
+        "stock_grams": 20000.0,
+        "cupping_notes": ["Blueberry", "Jasmine"]
+    });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+
+    let roast_data = json!({
+        "name": "Guji 14",
+        "green_coffee": green.id.unwrap(),
+        "date_roasted": "2026-03-02T09:00:00Z",
+        "roast_level": "Light",
+        "batch_size_grams": 5000.0,
+        "yield_grams": 4200.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+    let roast: Roast = serde_json::from_slice(&body).unwrap();
+
+    let product_data = json!({
+        "name": "Ethiopia Guji",
+        "roast": roast.id.unwrap(),
+        "variants": [
+            {
+                "package_size_grams": 250.0,
+                "gtin": "4006381333931",
+                "price": { "amount": "12.50", "currency": "USD" },
+                "stock_units": 20
+            },
+            {
+                "package_size_grams": 1000.0,
+                "grind": "espresso",
+                "price": { "amount": "40.00", "currency": "USD" },
+                "stock_units": 5
+            }
+        ]
+    });
+    let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let product_id = product.id.unwrap();
+
+    let request = json!({
+        "format": "json",
+        "items": [{ "product": product_id, "variant": "250g-whole-bean", "quantity": 3 }]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/labels", request).await;
+    assert_eq!(status, StatusCode::OK);
+    let labels: Vec<BagLabel> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        labels[0].lines,
+        [
+            "Ethiopia Guji",
+            "Ethiopia, Guji",
+            "Natural",
+            "Blueberry, Jasmine",
+            "250 g Whole bean",
+            "Roasted 02 Mar 2026",
+            "Best before 29 Aug 2026"
+        ]
+    );
+
+    // A custom layout, for a product that keeps for less time
+    let uri = format!("/products/{}", product_id.id.to_raw());
+    let freshness = json!({
+        "freshness": { "rest_days": 5, "peak_days": 21, "shelf_life_days": 90 }
+    });
+    let (status, _) = send(&app, http::Method::PUT, &uri, freshness).await;
+    assert_eq!(status, StatusCode::OK);
+    let template_data = json!({
+        "name": "Small espresso bag",
+        "width_mm": 60.0,
+        "height_mm": 40.0,
+        "lines": ["{name} ({roast_level})", "{net_weight}", "Best before {best_before}"]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/label-templates", template_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let template: LabelTemplate = serde_json::from_slice(&body).unwrap();
+    let template_id = template.id.unwrap();
+
+    let uri = format!("/label-templates/{}", template_id.id.to_raw());
+    let update = json!({ "lines": ["{name}", "{origin_country}"] });
+    let (status, _) = send(&app, http::Method::PUT, &uri, update).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let request = json!({
+        "template": template_id,
+        "format": "json",
+        "items": [{ "product": product_id, "variant": "1000g-espresso", "quantity": 1 }]
+    });
+    let (_, body) = send(&app, http::Method::POST, "/labels", request).await;
+    let labels: Vec<BagLabel> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        labels[0].lines,
+        ["Ethiopia Guji (Light)", "1 kg", "Best before 31 May 2026"]
+    );
+
+    let request = json!({
+        "items": [
+            { "product": product_id, "variant": "250g-whole-bean", "quantity": 2 },
+            { "product": product_id, "variant": "1000g-espresso", "quantity": 1 }
+        ]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/labels", request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.starts_with(b"%PDF-1.4"));
+    let pdf = String::from_utf8_lossy(&body);
+    assert!(pdf.contains("/Count 3"));
+    assert!(pdf.contains("(Roasted 02 Mar 2026)"));
+    // The two bags of the same variant share one content stream
+    assert_eq!(pdf.matches("endstream").count(), 2);
+
+    for items in [
+        json!([{ "product": product_id, "variant": "250g-whole-bean", "quantity": 501 }]),
+        json!((0..5)
+            .map(|_| json!({ "product": product_id, "variant": "250g-whole-bean", "quantity": 500 }))
+            .collect::<Vec<_>>()),
+    ] {
+        let (status, _) = send(&app, http::Method::POST, "/labels", json!({ "items": items })).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    // A packing run labels everything on the pick list
+    let order_data = json!({
+        "lines": [{ "product": product_id, "variant": "250g-whole-bean", "quantity": 4 }]
+    });
+    let (_, body) = send(&app, http::Method::POST, "/orders", order_data).await;
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    let order_id = order.id.unwrap().id.to_raw();
+    send(
+        &app,
+        http::Method::POST,
+        &format!("/orders/{}/pay", order_id),
+        json!({}),
+    )
+    .await;
+    let status_uri = format!("/orders/{}/status", order_id);
+    send(
+        &app,
+        http::Method::PUT,
+        &status_uri,
+        json!({"status": "picking"}),
+    )
+    .await;
+
+    let (status, body) = send(
+        &app,
+        http::Method::GET,
+        "/pick-list/labels?format=zpl",
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let zpl = String::from_utf8(body).unwrap();
+    assert!(zpl.starts_with("^XA"));
+    assert!(zpl.contains("^FDEthiopia Guji^FS"));
+    assert!(zpl.contains("^BEN,120,Y,N^FD400638133393^FS"));
+    assert!(zpl.trim_end().ends_with("^PQ4^XZ"));
+}
@@ -0,0 +1,170 @@
+use super::{app_with_db, migrations_dir, send};
+use crate::db;
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{GreenCoffee, Order, Product, Subscription};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+#[tokio::test]
+async fn upgrade_from_first_release_test() {
+    let db = db::connect().await.unwrap();
+
+    // A database from before migrations were recorded, holding records in
+    // the shape the first three migrations defined
+    let files = db::migration_files(&migrations_dir()).await.unwrap();
+    for path in &files[..3] {
+        db::apply_migration(&db, path).await.unwrap();
+    }
+    db.query(
+        "CREATE green_coffee:sidama CONTENT {
+            name: 'Sidama', origin_country: 'Ethiopia', region: 'Sidama',
+            variety: 'Heirloom', processing_method: 'Washed', altitude_masl: 2000,
+            harvest_year: 2024, stock_grams: 20000.0, price_per_kg: 9.5,
+            price_currency: 'usd', supplier: 'Cafe Imports', cupping_notes: ['jasmine']
+        };
+        CREATE roast:sidama CONTENT {
+            name: 'Sidama light', green_coffee: green_coffee:sidama, roast_level: 'Light',
+            batch_size_grams: 1000.0, yield_grams: 850.0, notes: []
+        };
+        CREATE product:sidama CONTENT {
+            roast: roast:sidama, name: 'Sidama 250g', description: 'Floral',
+            package_size_grams: 250.0, price: 14.5, price_currency: 'EUR', stock_units: 12
+        };",
+    )
+    .await
+    .unwrap()
+    .check()
+    .unwrap();
+
+    db::apply_migrations(&db, &migrations_dir()).await.unwrap();
+    // Already applied migrations are skipped on the next start
+    db::apply_migrations(&db, &migrations_dir()).await.unwrap();
+    // A backfill added between published migrations is applied late on
+    // databases that were already past it
+    db.query("DELETE type::thing('migration', '009a_backfill_reserved_stock')")
+        .await
+        .unwrap();
+    db::apply_migrations(&db, &migrations_dir()).await.unwrap();
+
+    let green: GreenCoffee = db
+        .select(("green_coffee", "sidama"))
+        .await
+        .unwrap()
+        .unwrap();
+    let price = green.price_per_kg.unwrap();
+    assert_eq!(price.amount, Decimal::new(95, 1));
+    assert_eq!(price.currency.code(), "USD");
+    assert_eq!(green.legacy_supplier.as_deref(), Some("Cafe Imports"));
+    assert!(green.certifications.is_empty());
+
+    let product: Product = db.select(("product", "sidama")).await.unwrap().unwrap();
+    assert_eq!(product.variants.len(), 1);
+    let variant = &product.variants[0];
+    assert_eq!(variant.key, "250g-whole-bean");
+    assert_eq!(variant.price.amount, Decimal::new(145, 1));
+    assert_eq!(variant.stock_units, 12);
+    assert_eq!(variant.reserved_units, 0);
+
+    // Migrated records can be written back in full through the API
+    let app = app_with_db(db.clone());
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        "/greens/sidama",
+        json!({ "stock_grams": 18000.0 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        "/products/sidama",
+        json!({ "description": "Jasmine and bergamot" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = send(&app, http::Method::POST, "/suppliers/migrate", json!({})).await;
+    assert_eq!(status, StatusCode::OK, "{}", String::from_utf8_lossy(&body));
+
+    let green: GreenCoffee = db
+        .select(("green_coffee", "sidama"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(green.supplier.is_some());
+    assert_eq!(green.stock_grams, 18000.0);
+}
+
+#[tokio::test]
+async fn variants_stamped_on_upgrade_test() {
+    let db = db::connect().await.unwrap();
+
+    // Orders and subscriptions placed before products had variants
+    let files = db::migration_files(&migrations_dir()).await.unwrap();
+    let split = files
+        .iter()
+        .position(|path| path.ends_with("024_product_variants.surql"))
+        .unwrap();
+    let (before, after) = files.split_at(split);
+    for path in before {
+        db::apply_migration(&db, path).await.unwrap();
+    }
+    db.query(
+        "CREATE product:guji CONTENT {
+            roast: roast:guji, name: 'Guji 250g', description: 'Stone fruit', package_size_grams: 250.0,
+            price: { amount: '12.00', currency: 'USD' }, stock_units: 20
+        };
+        CREATE customer:cafe CONTENT { name: 'Cafe', email: 'cafe@example.com', addresses: [] };
+        CREATE order:open CONTENT {
+            customer: customer:cafe, status: 'paid',
+            lines: [{ product: product:guji, quantity: 2, unit_price: { amount: '12.00', currency: 'USD' } }],
+            total: { amount: '24.00', currency: 'USD' }
+        };
+        CREATE subscription:weekly CONTENT {
+            customer: customer:cafe, product: product:guji, quantity: 1,
+            interval_weeks: 1, next_run_at: time::now(), status: 'active'
+        };",
+    )
+    .await
+    .unwrap()
+    .check()
+    .unwrap();
+    for path in after {
+        db::apply_migration(&db, path).await.unwrap();
+    }
+
+    let order: Order = db.select(("order", "open")).await.unwrap().unwrap();
+    assert_eq!(order.lines[0].variant.as_deref(), Some("250g-whole-bean"));
+    let subscription: Subscription = db
+        .select(("subscription", "weekly"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(subscription.variant.as_deref(), Some("250g-whole-bean"));
+
+    // A second variant can be added, but the one they name cannot go
+    let app = app_with_db(db.clone());
+    let variant_data = json!({
+        "package_size_grams": 1000.0,
+        "grind": "whole_bean",
+        "price": { "amount": "40.00", "currency": "USD" },
+        "stock_units": 5
+    });
+    let uri = "/products/guji/variants";
+    let (status, _) = send(&app, http::Method::POST, uri, variant_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = "/products/guji/variants/250g-whole-bean";
+    let (status, _) = send(&app, http::Method::DELETE, uri, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    db.query("UPDATE order:open SET status = 'cancelled'")
+        .await
+        .unwrap();
+    let (status, _) = send(&app, http::Method::DELETE, uri, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    db.query("UPDATE subscription:weekly SET status = 'cancelled'")
+        .await
+        .unwrap();
+    let (status, _) = send(&app, http::Method::DELETE, uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+}
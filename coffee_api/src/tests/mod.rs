@@ -1,28 +1,297 @@
+pub mod alerts;
+pub mod analytics;
+pub mod barcodes;
+pub mod blends;
+pub mod certifications;
+pub mod costing;
+pub mod crop_age;
+pub mod cupping;
+pub mod customers;
+pub mod flavours;
+pub mod freshness;
+pub mod fx_rates;
+pub mod green_measurements;
 pub mod greens;
+pub mod labels;
+pub mod migrations;
+pub mod orders;
+pub mod price_lists;
+pub mod production;
 pub mod products;
+pub mod purchase_orders;
+pub mod roast_profiles;
 pub mod roasts;
+pub mod subscriptions;
+pub mod suppliers;
+pub mod trace;
+pub mod variants;
 
-use crate::db;
+use crate::db::{self, Db};
+use crate::models::{CreateRoastRequest, Roast, StockAlert};
+use crate::notifications::{LogNotifier, Notifier, NotifyError};
+use crate::payments::FakePaymentProvider;
 use crate::routes::*;
-use axum::{Router, routing::get};
+use crate::state::AppState;
+use async_trait::async_trait;
+use axum::body::{Body, to_bytes};
+use axum::{
+    Router,
+    http::{self, Request, StatusCode},
+    routing::{delete, get, post, put},
+};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
 
-pub async fn app() -> Router {
+pub const WEBHOOK_SECRET: &str = "test-webhook-secret";
+
+/// The migrations the server applies, found from this crate so tests don't
+/// depend on the working directory.
+pub fn migrations_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../coffee_shared/migrations")
+}
+
+/// A fresh database with every migration applied.
+pub async fn migrated_db() -> Db {
     let db = db::connect().await.unwrap();
+    db::apply_migrations(&db, &migrations_dir()).await.unwrap();
+    db
+}
+
+/// Saves a roast the way it was recorded before roasting took green out of
+/// stock, with no movements against its lots.
+pub async fn seed_roast(db: &Db, data: Value) -> Roast {
+    let request: CreateRoastRequest = serde_json::from_value(data).unwrap();
+    let roast: Roast = request.into();
+    let created: Option<Roast> = db.create("roast").content(roast).await.unwrap();
+    created.unwrap()
+}
+
+/// Keeps every batch of alerts it is asked to send.
+#[derive(Default)]
+pub struct RecordingNotifier {
+    pub sent: Mutex<Vec<Vec<StockAlert>>>,
+}
+
+#[async_trait]
+impl Notifier for RecordingNotifier {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn notify(&self, alerts: &[StockAlert]) -> Result<(), NotifyError> {
+        self.sent.lock().unwrap().push(alerts.to_vec());
+        Ok(())
+    }
+}
+
+pub async fn app() -> Router {
+    app_with_db(migrated_db().await)
+}
+
+// Builds the router around `db`, for tests that seed records directly
+pub fn app_with_db(db: Db) -> Router {
+    app_with_notifier(db, Arc::new(LogNotifier))
+}
+
+// Builds the router around `db`, sending stock alerts through `notifier`
+pub fn app_with_notifier(db: Db, notifier: Arc<dyn Notifier>) -> Router {
+    let payments = Arc::new(FakePaymentProvider::new(WEBHOOK_SECRET));
     Router::new()
+        .route("/suppliers", get(list_suppliers).post(create_supplier))
+        .route("/suppliers/migrate", post(migrate_legacy_suppliers))
+        .route(
+            "/suppliers/{id}",
+            get(get_supplier)
+                .put(update_supplier)
+                .delete(delete_supplier),
+        )
+        .route(
+            "/purchase-orders",
+            get(list_purchase_orders).post(create_purchase_order),
+        )
+        .route("/purchase-orders/outstanding", get(list_outstanding_green))
+        .route("/purchase-orders/{id}", get(get_purchase_order))
+        .route(
+            "/purchase-orders/{id}/receipts",
+            post(receive_purchase_order),
+        )
+        .route("/purchase-orders/{id}/cancel", post(cancel_purchase_order))
         .route("/roasts", get(list_roasts).post(create_roast))
         .route(
             "/roasts/{id}",
             get(get_roast).put(update_roast).delete(delete_roast),
         )
+        .route("/roasts/{id}/cost", get(get_roast_cost))
+        .route("/roasts/{id}/cupping-scores", get(get_roast_cupping_scores))
+        .route(
+            "/roasts/{id}/profile",
+            get(get_roast_profile).post(import_roast_profile),
+        )
+        .route("/roasts/{id}/profile/compare", post(compare_roast_profile))
+        .route("/analytics/roast-loss", get(roast_loss_report))
+        .route("/flavours", get(list_flavours))
+        .route("/flavours/notes", get(list_flavour_notes))
+        .route(
+            "/flavour-aliases",
+            get(list_flavour_aliases).post(create_flavour_alias),
+        )
+        .route("/flavour-aliases/{id}", delete(delete_flavour_alias))
+        .route(
+            "/cupping-sessions",
+            get(list_cupping_sessions).post(create_cupping_session),
+        )
+        .route(
+            "/cupping-sessions/{id}",
+            get(get_cupping_session).delete(delete_cupping_session),
+        )
+        .route("/cupping-sessions/{id}/forms", post(submit_cupping_form))
         .route("/products", get(list_products).post(create_product))
         .route(
             "/products/{id}",
             get(get_product).put(update_product).delete(delete_product),
         )
+        .route("/products/by-barcode/{code}", get(get_product_by_barcode))
+        .route("/products/{id}/variants", post(create_product_variant))
+        .route(
+            "/products/{id}/variants/{variant}",
+            put(update_product_variant).delete(delete_product_variant),
+        )
+        .route(
+            "/products/{id}/variants/{variant}/barcode",
+            get(get_variant_barcode),
+        )
+        .route(
+            "/sku-settings",
+            get(get_sku_settings).put(update_sku_settings),
+        )
+        .route("/products/{id}/cost", get(get_product_cost))
+        .route("/products/margins", get(list_product_margins))
+        .route(
+            "/cost-settings",
+            get(get_cost_settings).put(update_cost_settings),
+        )
         .route("/greens", get(list_greens).post(create_green))
         .route(
             "/greens/{id}",
             get(get_green).put(update_green).delete(delete_green),
         )
-        .with_state(db)
+        .route("/greens/quality-warnings", get(list_quality_warnings))
+        .route("/greens/past-crop", get(list_past_crop_greens))
+        .route("/greens/use-first", get(list_use_first_greens))
+        .route("/greens/{id}/crop-age", get(get_green_crop_age))
+        .route("/greens/{id}/cupping-scores", get(get_green_cupping_scores))
+        .route(
+            "/greens/{id}/measurements",
+            get(list_green_measurements).post(create_green_measurement),
+        )
+        .route(
+            "/quality-ranges",
+            get(get_quality_ranges).put(update_quality_ranges),
+        )
+        .route(
+            "/crop-age-settings",
+            get(get_crop_age_settings).put(update_crop_age_settings),
+        )
+        .route("/customers", get(list_customers).post(create_customer))
+        .route(
+            "/customers/{id}",
+            get(get_customer)
+                .put(update_customer)
+                .delete(delete_customer),
+        )
+        .route("/customers/{id}/orders", get(list_customer_orders))
+        .route("/customers/{id}/export", get(export_customer))
+        .route("/customers/{id}/erase", post(erase_customer))
+        .route(
+            "/price-lists",
+            get(list_price_lists).post(create_price_list),
+        )
+        .route(
+            "/price-lists/{id}",
+            get(get_price_list)
+                .put(update_price_list)
+                .delete(delete_price_list),
+        )
+        .route("/price-quote", post(quote_price))
+        .route("/fx-rates", get(list_fx_rates).post(create_fx_rate))
+        .route("/fx-rates/import", post(import_fx_rates))
+        .route(
+            "/subscriptions",
+            get(list_subscriptions).post(create_subscription),
+        )
+        .route("/subscriptions/forecast", get(subscription_forecast))
+        .route("/subscriptions/run", post(run_subscriptions))
+        .route(
+            "/subscriptions/{id}",
+            get(get_subscription).put(update_subscription),
+        )
+        .route("/subscriptions/{id}/pause", post(pause_subscription))
+        .route("/subscriptions/{id}/resume", post(resume_subscription))
+        .route("/subscriptions/{id}/skip", post(skip_subscription))
+        .route("/subscriptions/{id}/cancel", post(cancel_subscription))
+        .route("/orders", get(list_orders).post(create_order))
+        .route("/orders/{id}", get(get_order))
+        .route("/orders/{id}/pay", post(pay_order))
+        .route("/orders/{id}/refund", post(refund_order))
+        .route("/orders/{id}/status", put(update_order_status))
+        .route("/pick-list", get(get_pick_list))
+        .route("/pick-list/labels", get(print_pick_list_labels))
+        .route(
+            "/freshness-settings",
+            get(get_freshness_settings).put(update_freshness_settings),
+        )
+        .route("/freshness/fifo", get(get_fifo_recommendations))
+        .route("/freshness/expiring", get(get_expiring_stock))
+        .route("/roasts/{id}/freshness", get(get_roast_freshness))
+        .route("/products/{id}/freshness", get(get_product_freshness))
+        .route("/labels", post(print_labels))
+        .route(
+            "/label-templates",
+            get(list_label_templates).post(create_label_template),
+        )
+        .route(
+            "/label-templates/{id}",
+            get(get_label_template)
+                .put(update_label_template)
+                .delete(delete_label_template),
+        )
+        .route("/production-plan", get(get_production_plan))
+        .route("/alerts", get(list_alerts))
+        .route("/alerts/notify", post(notify_alerts))
+        .route("/trace/{record}", get(get_trace))
+        .route("/inventory-movements", get(list_inventory_movements))
+        .route("/payments", get(list_payments))
+        .route("/payments/webhook", post(payment_webhook))
+        .with_state(AppState {
+            db,
+            payments,
+            notifier,
+        })
+}
+
+// Sends a JSON request through the router and returns the status and raw body
+pub async fn send(
+    app: &Router,
+    method: http::Method,
+    uri: &str,
+    body: Value,
+) -> (StatusCode, Vec<u8>) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), 1_000_000).await.unwrap();
+    (status, body.to_vec())
 }
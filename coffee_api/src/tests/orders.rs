@@ -0,0 +1,310 @@
+use super::{WEBHOOK_SECRET, app, app_with_db, migrated_db, send};
+use axum::Router;
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+};
+use coffee_shared::models::{
+    InventoryMovement, Order, OrderStatus, Payment, PaymentStatus, PickListLine, Product,
+};
+use rust_decimal::Decimal;
+use serde_json::json;
+use tower::ServiceExt;
+
+async fn create_pending_order(app: &Router) -> Order {
+    let product_data = json!({
+        "name": "Test Product",
+        "description": "A test product description.",
+        "package_size_grams": 250.0,
+        "price": { "amount": "12.50", "currency": "USD" },
+        "stock_units": 100,
+        "roast": null
+    });
+    let (status, body) = send(app, http::Method::POST, "/products", product_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let product: Product = serde_json::from_slice(&body).unwrap();
+
+    let order_data = json!({
+        "lines": [{ "product": product.id.unwrap(), "quantity": 2 }]
+    });
+    let (status, body) = send(app, http::Method::POST, "/orders", order_data).await;
+    assert_eq!(status, StatusCode::OK);
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn pay_and_refund_order_test() {
+    let app = app().await;
+
+    let order = create_pending_order(&app).await;
+    assert_eq!(order.status, OrderStatus::Pending);
+    assert_eq!(order.total.amount, Decimal::new(2500, 2));
+    assert_eq!(order.total.currency.code(), "USD");
+    let order_id = order.id.unwrap().id.to_raw();
+
+    // A pending order has nothing to refund
+    let uri = format!("/orders/{}/refund", order_id);
+    let (status, _) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let uri = format!("/orders/{}/pay", order_id);
+    let (status, body) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+
+    let (status, body) = send(&app, http::Method::GET, "/payments", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let payments: Vec<Payment> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payments.len(), 1);
+    assert_eq!(payments[0].status, PaymentStatus::Captured);
+    assert_eq!(payments[0].provider, "fake");
+
+    let uri = format!("/orders/{}/refund", order_id);
+    let (status, body) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.status, OrderStatus::Refunded);
+}
+
+#[tokio::test]
+async fn retry_pay_reuses_authorization_test() {
+    let db = migrated_db().await;
+    let app = app_with_db(db.clone());
+
+    let order = create_pending_order(&app).await;
+    let order_id = order.id.clone().unwrap().id.to_raw();
+
+    // An authorization left behind by an attempt whose capture failed
+    let authorized = Payment {
+        id: None,
+        order: order.id.clone().unwrap(),
+        provider: "fake".to_string(),
+        provider_reference: "fake_lost".to_string(),
+        amount: order.total,
+        status: PaymentStatus::Authorized,
+        created_at: None,
+        updated_at: None,
+    };
+    let _: Option<Payment> = db.create("payment").content(authorized).await.unwrap();
+
+    // The retry captures that authorization instead of taking a new one
+    let uri = format!("/orders/{}/pay", order_id);
+    let (status, _) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, body) = send(&app, http::Method::GET, "/payments", json!({})).await;
+    let payments: Vec<Payment> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payments.len(), 1);
+    assert_eq!(payments[0].provider_reference, "fake_lost");
+}
+
+#[tokio::test]
+async fn payment_webhook_test() {
+    let app = app().await;
+
+    let order = create_pending_order(&app).await;
+    let order_id = order.id.unwrap().id.to_raw();
+    let uri = format!("/orders/{}/pay", order_id);
+    let (status, _) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = send(&app, http::Method::GET, "/payments", json!({})).await;
+    let payments: Vec<Payment> = serde_json::from_slice(&body).unwrap();
+    let event = json!({
+        "reference": payments[0].provider_reference,
+        "status": "refunded"
+    });
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/payments/webhook")
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .header("x-fake-signature", "wrong-secret")
+                .body(Body::from(event.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/payments/webhook")
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .header("x-fake-signature", WEBHOOK_SECRET)
+                .body(Body::from(event.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let uri = format!("/orders/{}", order_id);
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.status, OrderStatus::Refunded);
+}
+
+#[tokio::test]
+async fn capture_after_cancel_is_refunded_test() {
+    let db = migrated_db().await;
+    let app = app_with_db(db.clone());
+
+    let order = create_pending_order(&app).await;
+    let order_id = order.id.unwrap().id.to_raw();
+    let uri = format!("/orders/{}/pay", order_id);
+    send(&app, http::Method::POST, &uri, json!({})).await;
+    let uri = format!("/orders/{}/status", order_id);
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        &uri,
+        json!({"status": "cancelled"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The capture webhook arrives after the order was cancelled
+    db.query("UPDATE payment SET status = 'authorized'")
+        .await
+        .unwrap();
+    let (_, body) = send(&app, http::Method::GET, "/payments", json!({})).await;
+    let payments: Vec<Payment> = serde_json::from_slice(&body).unwrap();
+    let event = json!({
+        "reference": payments[0].provider_reference,
+        "status": "captured"
+    });
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri("/payments/webhook")
+                    .header(http::header::CONTENT_TYPE, "application/json")
+                    .header("x-fake-signature", WEBHOOK_SECRET)
+                    .body(Body::from(event.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let (_, body) = send(&app, http::Method::GET, "/payments", json!({})).await;
+    let payments: Vec<Payment> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payments[0].status, PaymentStatus::Refunded);
+    let uri = format!("/orders/{}", order_id);
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.status, OrderStatus::Refunded);
+}
+
+#[tokio::test]
+async fn fulfil_order_test() {
+    let db = migrated_db().await;
+    let app = app_with_db(db.clone());
+
+    let order = create_pending_order(&app).await;
+    let product_id = order.lines[0].product.clone();
+    let order_id = order.id.unwrap().id.to_raw();
+    let status_uri = format!("/orders/{}/status", order_id);
+
+    // Unpaid orders cannot be picked
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        &status_uri,
+        json!({"status": "picking"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let uri = format!("/orders/{}/pay", order_id);
+    let (status, _) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        &status_uri,
+        json!({"status": "picking"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send(&app, http::Method::GET, "/pick-list", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let pick_list: Vec<PickListLine> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(pick_list.len(), 1);
+    assert_eq!(pick_list[0].product, product_id);
+    assert_eq!(pick_list[0].quantity, 2);
+
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        &status_uri,
+        json!({"status": "packed"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        &status_uri,
+        json!({"status": "shipped"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Stock held for other orders cannot ship with this one
+    let hold = |units: i32| {
+        db.query("UPDATE $product SET variants[0].reserved_units = $units")
+            .bind(("product", product_id.clone()))
+            .bind(("units", units))
+    };
+    hold(99).await.unwrap().check().unwrap();
+    let shipment = json!({"status": "shipped", "tracking_number": "1Z999", "carrier": "UPS"});
+    let (status, _) = send(&app, http::Method::PUT, &status_uri, shipment.clone()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    // Nor can stock be counted down below what is held
+    let product_uri = format!("/products/{}", product_id.id.to_raw());
+    let recount = json!({ "stock_units": 98 });
+    let (status, _) = send(&app, http::Method::PUT, &product_uri, recount).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    hold(0).await.unwrap().check().unwrap();
+
+    let (status, body) = send(&app, http::Method::PUT, &status_uri, shipment).await;
+    assert_eq!(status, StatusCode::OK);
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.status, OrderStatus::Shipped);
+    assert_eq!(order.tracking_number.as_deref(), Some("1Z999"));
+
+    let uri = format!("/products/{}", product_id.id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    assert_eq!(product.variants[0].stock_units, 98);
+
+    let (_, body) = send(&app, http::Method::GET, "/inventory-movements", json!({})).await;
+    let movements: Vec<InventoryMovement> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(movements.len(), 1);
+    assert_eq!(movements[0].item, product_id);
+    assert_eq!(movements[0].quantity, -2.0);
+
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        &status_uri,
+        json!({"status": "delivered"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,117 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{Customer, Order, PriceList, PriceQuote, Product};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+#[tokio::test]
+async fn wholesale_price_resolution_test() {
+    let app = app().await;
+
+    let product_data = json!({
+        "name": "House Espresso 1kg",
+        "package_size_grams": 1000.0,
+        "price": { "amount": "40.00", "currency": "EUR" },
+        "stock_units": 50,
+        "roast": null
+    });
+    let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let product_id = product.id.unwrap();
+
+    let tier_data = json!({
+        "name": "Wholesale A",
+        "kind": "wholesale",
+        "customer": null,
+        "entries": [
+            { "product": product_id, "min_quantity": 1, "price": "30.00" },
+            { "product": product_id, "min_quantity": 10, "price": "27.50" }
+        ]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/price-lists", tier_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let tier: PriceList = serde_json::from_slice(&body).unwrap();
+
+    let customer_data = json!({
+        "name": "Corner Café",
+        "email": "orders@corner.example",
+        "price_list": tier.id.unwrap()
+    });
+    let (_, body) = send(&app, http::Method::POST, "/customers", customer_data).await;
+    let customer: Customer = serde_json::from_slice(&body).unwrap();
+    let customer_id = customer.id.unwrap();
+
+    let quote = |customer: Option<_>, quantity: i32| json!({ "product": product_id, "customer": customer, "quantity": quantity });
+
+    let (_, body) = send(&app, http::Method::POST, "/price-quote", quote(None, 5)).await;
+    let retail: PriceQuote = serde_json::from_slice(&body).unwrap();
+    assert_eq!(retail.unit_price.amount, Decimal::new(40, 0));
+    assert!(retail.price_list.is_none());
+
+    let request = quote(Some(customer_id.clone()), 5);
+    let (_, body) = send(&app, http::Method::POST, "/price-quote", request).await;
+    let wholesale: PriceQuote = serde_json::from_slice(&body).unwrap();
+    assert_eq!(wholesale.unit_price.amount, Decimal::new(30, 0));
+
+    let request = quote(Some(customer_id.clone()), 12);
+    let (_, body) = send(&app, http::Method::POST, "/price-quote", request).await;
+    let quantity_break: PriceQuote = serde_json::from_slice(&body).unwrap();
+    assert_eq!(quantity_break.unit_price.amount, Decimal::new(275, 1));
+    assert_eq!(quantity_break.total.amount, Decimal::new(330, 0));
+    assert_eq!(quantity_break.total.currency.code(), "EUR");
+
+    // A customer override beats the tier
+    let override_data = json!({
+        "name": "Corner Café contract",
+        "kind": "customer",
+        "customer": customer_id,
+        "entries": [{ "product": product_id, "min_quantity": 1, "price": "25.00" }]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/price-lists", override_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let contract: PriceList = serde_json::from_slice(&body).unwrap();
+
+    // Only existing wholesale lists can be assigned as a customer's tier
+    let uri = format!("/customers/{}", customer_id.id.to_raw());
+    let assign = json!({ "price_list": contract.id.unwrap() });
+    let (status, _) = send(&app, http::Method::PUT, &uri, assign).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let assign = json!({ "price_list": { "tb": "price_list", "id": { "String": "missing" } } });
+    let (status, _) = send(&app, http::Method::PUT, &uri, assign).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let order_data = json!({
+        "customer": customer_id,
+        "lines": [{ "product": product_id, "quantity": 2 }]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/orders", order_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.lines[0].unit_price.amount, Decimal::new(25, 0));
+    assert_eq!(order.total.amount, Decimal::new(50, 0));
+}
+
+#[tokio::test]
+async fn overflowing_totals_rejected_test() {
+    let app = app().await;
+
+    let product_data = json!({
+        "name": "Priceless",
+        "package_size_grams": 250.0,
+        "price": { "amount": "79228162514264337593543950335", "currency": "USD" },
+        "stock_units": 10,
+        "roast": null
+    });
+    let (status, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let product_id = product.id.unwrap();
+
+    let quote = json!({ "product": product_id, "customer": null, "quantity": 2 });
+    let (status, _) = send(&app, http::Method::POST, "/price-quote", quote).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let order_data = json!({ "lines": [{ "product": product_id, "quantity": 2 }] });
+    let (status, _) = send(&app, http::Method::POST, "/orders", order_data).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
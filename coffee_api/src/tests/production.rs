@@ -0,0 +1,121 @@
+use super::{app_with_db, migrated_db, seed_roast, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{Customer, GreenCoffee, Product, ProductionPlan};
+use serde_json::json;
+
+#[tokio::test]
+async fn production_plan_test() {
+    let db = migrated_db().await;
+    let app = app_with_db(db.clone());
+
+    let green_data = json!({
+        "name": "Guji",
+        "origin_country": "Ethiopia",
+        "stock_grams": 3000.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    let green_id = green.id.unwrap();
+
+    // Past roasts of this green lost 20% and 16%, recorded before roasting
+    // took green out of stock
+    let mut roast_ids = Vec::new();
+    for yield_grams in [800.0, 840.0] {
+        let roast_data = json!({
+            "name": "Guji medium",
+            "green_coffee": green_id,
+            "roast_level": "Medium",
+            "batch_size_grams": 1000.0,
+            "yield_grams": yield_grams
+        });
+        let roast = seed_roast(&db, roast_data).await;
+        roast_ids.push(roast.id.unwrap());
+    }
+
+    let product_data = json!({
+        "name": "Guji 250g",
+        "roast": roast_ids[0],
+        "package_size_grams": 250.0,
+        "price": { "amount": "12.00", "currency": "USD" },
+        "stock_units": 4,
+        "target_stock_units": 10
+    });
+    let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let product_id = product.id.unwrap();
+
+    let product_data = json!({
+        "name": "Mystery bag",
+        "roast": null,
+        "package_size_grams": 250.0,
+        "price": { "amount": "10.00", "currency": "USD" },
+        "stock_units": 0,
+        "target_stock_units": 3
+    });
+    let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    let unroasted: Product = serde_json::from_slice(&body).unwrap();
+
+    let customer_data = json!({"name": "Cafe", "email": "cafe@example.com"});
+    let (_, body) = send(&app, http::Method::POST, "/customers", customer_data).await;
+    let customer: Customer = serde_json::from_slice(&body).unwrap();
+    let customer_id = customer.id.unwrap();
+
+    let order_data = json!({
+        "customer": customer_id,
+        "lines": [{ "product": product_id, "quantity": 6 }]
+    });
+    let (status, _) = send(&app, http::Method::POST, "/orders", order_data).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let subscription_data = json!({
+        "customer": customer_id,
+        "product": product_id,
+        "quantity": 2,
+        "interval_weeks": 2
+    });
+    send(
+        &app,
+        http::Method::POST,
+        "/subscriptions",
+        subscription_data,
+    )
+    .await;
+
+    let uri = "/production-plan?batch_capacity_grams=0";
+    let (status, _) = send(&app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let uri = "/production-plan?weeks=100000000";
+    let (status, _) = send(&app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = "/production-plan?weeks=1&batch_capacity_grams=2000";
+    let (status, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let plan: ProductionPlan = serde_json::from_slice(&body).unwrap();
+
+    // 6 ordered + 2 for the subscription + 10 target - 4 in stock
+    let demand = plan
+        .products
+        .iter()
+        .find(|demand| demand.product == product_id)
+        .unwrap();
+    assert_eq!(demand.open_order_units, 6);
+    assert_eq!(demand.forecast_units, 2);
+    assert_eq!(demand.units_to_make, 14);
+    assert_eq!(demand.roasted_grams, 3500.0);
+
+    // 3500 g roasted at the typical 18% loss needs 4269 g green, in three
+    // batches that fit the 2 kg roaster
+    assert_eq!(plan.roasts.len(), 1);
+    let roast = &plan.roasts[0];
+    assert_eq!(roast.green_coffee, green_id);
+    assert!((roast.expected_loss_percent - 18.0).abs() < 1e-9);
+    assert_eq!(roast.green_grams, 4269.0);
+    assert_eq!(roast.batches, 3);
+    assert_eq!(roast.batch_size_grams, 1423.0);
+    assert!(roast.short_of_green);
+
+    assert_eq!(plan.greens.len(), 1);
+    assert_eq!(plan.greens[0].shortfall_grams, 1269.0);
+    assert_eq!(plan.unplanned_products, vec![unroasted.id.unwrap()]);
+}
@@ -22,9 +22,8 @@ async fn create_product_test() {
         "altitude_masl": 1700,
         "harvest_year": 2022,
         "stock_grams": 1000.0,
-        "price_per_kg": 20.0,
-        "price_currency": "USD",
-        "supplier": "Cafe Imports",
+        "price_per_kg": { "amount": "20.00", "currency": "USD" },
+        "supplier": null,
         "cupping_notes": ["Chocolate", "Caramel"]
     });
 
@@ -88,8 +87,7 @@ async fn create_product_test() {
         "details": ["Detail 1", "Detail 2"],
         "images": [{}, {}],
         "package_size_grams": 250.0,
-        "price": 12.99,
-        "price_currency": "USD",
+        "price": { "amount": "12.99", "currency": "USD" },
         "stock_units": 100,
         "roast": roast_id
     });
@@ -0,0 +1,195 @@
+use super::{app, app_with_db, migrated_db, send};
+use axum::http::{self, StatusCode};
+use chrono::{DateTime, Utc};
+use coffee_shared::models::{
+    GreenCoffee, InventoryMovement, MovementReason, OutstandingLine, PurchaseOrder,
+    PurchaseOrderStatus, Supplier,
+};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+#[tokio::test]
+async fn receive_purchase_order_test() {
+    let app = app().await;
+
+    let supplier_data = json!({"name": "Cafe Imports"});
+    let (_, body) = send(&app, http::Method::POST, "/suppliers", supplier_data).await;
+    let supplier: Supplier = serde_json::from_slice(&body).unwrap();
+    let supplier_id = supplier.id.unwrap();
+
+    let green_data = json!({
+        "name": "Huila",
+        "origin_country": "Colombia",
+        "stock_grams": 1000.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let huila: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    let huila_id = huila.id.unwrap();
+
+    let order_data = json!({
+        "supplier": supplier_id,
+        "reference": "CI-2024-118",
+        "expected_arrival": "2024-09-01T00:00:00Z",
+        "lines": [
+            {
+                "lot": "Guji lot 4",
+                "origin_country": "Ethiopia",
+                "contracted_grams": 60000.0,
+                "price_per_kg": { "amount": "6.50", "currency": "USD" }
+            },
+            {
+                "lot": "Huila",
+                "origin_country": "Colombia",
+                "green_coffee": huila_id,
+                "contracted_grams": 30000.0,
+                "price_per_kg": { "amount": "5.00", "currency": "USD" }
+            }
+        ]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/purchase-orders", order_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let order: PurchaseOrder = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.status, PurchaseOrderStatus::Open);
+    let receipts_uri = format!(
+        "/purchase-orders/{}/receipts",
+        order.id.unwrap().id.to_raw()
+    );
+
+    let receipt = json!({"lines": [{ "line": 2, "grams": 1000.0 }]});
+    let (status, _) = send(&app, http::Method::POST, &receipts_uri, receipt).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Part of the new lot and all of the top-up arrive first, booked in late
+    let received_at = "2024-09-03T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+    let receipt = json!({
+        "received_at": received_at,
+        "lines": [
+            { "line": 0, "grams": 20000.0 },
+            { "line": 1, "grams": 30000.0 }
+        ]
+    });
+    let (status, body) = send(&app, http::Method::POST, &receipts_uri, receipt).await;
+    assert_eq!(status, StatusCode::OK);
+    let order: PurchaseOrder = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.status, PurchaseOrderStatus::PartiallyReceived);
+    assert_eq!(order.receipts.len(), 2);
+    assert_eq!(order.lines[1].green_coffee, Some(huila_id.clone()));
+
+    let guji_id = order.lines[0].green_coffee.clone().unwrap();
+    let uri = format!("/greens/{}", guji_id.id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let guji: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    assert_eq!(guji.name, "Guji lot 4");
+    assert_eq!(guji.stock_grams, 20000.0);
+    assert_eq!(guji.supplier, Some(supplier_id.clone()));
+    assert_eq!(guji.arrival_date, Some(received_at));
+
+    let uri = format!("/greens/{}", huila_id.id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let huila: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    assert_eq!(huila.stock_grams, 31000.0);
+    assert_eq!(huila.arrival_date, Some(received_at));
+
+    let uri = "/purchase-orders/outstanding";
+    let (status, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let outstanding: Vec<OutstandingLine> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(outstanding.len(), 1);
+    assert_eq!(outstanding[0].lot, "Guji lot 4");
+    assert_eq!(outstanding[0].outstanding_grams, 40000.0);
+    assert_eq!(
+        outstanding[0].outstanding_value.amount,
+        Decimal::new(26000, 2)
+    );
+
+    // The rest tops up the green created by the first receipt
+    let receipt = json!({"lines": [{ "line": 0, "grams": 40000.0 }]});
+    let (_, body) = send(&app, http::Method::POST, &receipts_uri, receipt).await;
+    let order: PurchaseOrder = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.status, PurchaseOrderStatus::Received);
+    assert_eq!(order.lines[0].green_coffee, Some(guji_id));
+
+    let receipt = json!({"lines": [{ "line": 0, "grams": 1.0 }]});
+    let (status, _) = send(&app, http::Method::POST, &receipts_uri, receipt).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (_, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    let outstanding: Vec<OutstandingLine> = serde_json::from_slice(&body).unwrap();
+    assert!(outstanding.is_empty());
+
+    let uri = "/inventory-movements";
+    let (_, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    let movements: Vec<InventoryMovement> = serde_json::from_slice(&body).unwrap();
+    let received: f64 = movements
+        .iter()
+        .filter(|movement| movement.reason == MovementReason::Receipt)
+        .map(|movement| movement.quantity)
+        .sum();
+    assert_eq!(received, 90000.0);
+}
+
+#[tokio::test]
+async fn receipt_with_missing_green_writes_nothing_test() {
+    let db = migrated_db().await;
+    let app = app_with_db(db.clone());
+
+    let supplier_data = json!({"name": "Cafe Imports"});
+    let (_, body) = send(&app, http::Method::POST, "/suppliers", supplier_data).await;
+    let supplier: Supplier = serde_json::from_slice(&body).unwrap();
+
+    let green_data = json!({"name": "Huila", "origin_country": "Colombia", "stock_grams": 0.0});
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let huila: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    let huila_id = huila.id.unwrap();
+
+    let order_data = json!({
+        "supplier": supplier.id.unwrap(),
+        "lines": [
+            {
+                "lot": "Guji lot 4",
+                "origin_country": "Ethiopia",
+                "contracted_grams": 60000.0,
+                "price_per_kg": { "amount": "6.50", "currency": "USD" }
+            },
+            {
+                "lot": "Huila",
+                "origin_country": "Colombia",
+                "green_coffee": huila_id,
+                "contracted_grams": 30000.0,
+                "price_per_kg": { "amount": "5.00", "currency": "USD" }
+            }
+        ]
+    });
+    let (_, body) = send(&app, http::Method::POST, "/purchase-orders", order_data).await;
+    let order: PurchaseOrder = serde_json::from_slice(&body).unwrap();
+    let order_id = order.id.unwrap().id.to_raw();
+
+    // The green the second line tops up has gone since the order was placed
+    let _: Option<GreenCoffee> = db
+        .delete(("green_coffee", huila_id.id.to_raw()))
+        .await
+        .unwrap();
+
+    let receipts_uri = format!("/purchase-orders/{}/receipts", order_id);
+    let receipt = json!({
+        "lines": [
+            { "line": 0, "grams": 20000.0 },
+            { "line": 1, "grams": 30000.0 }
+        ]
+    });
+    let (status, _) = send(&app, http::Method::POST, &receipts_uri, receipt).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Neither line was received, so nothing needs undoing
+    let (_, body) = send(&app, http::Method::GET, "/greens", json!({})).await;
+    let greens: Vec<GreenCoffee> = serde_json::from_slice(&body).unwrap();
+    assert!(greens.is_empty());
+    let (_, body) = send(&app, http::Method::GET, "/inventory-movements", json!({})).await;
+    let movements: Vec<InventoryMovement> = serde_json::from_slice(&body).unwrap();
+    assert!(movements.is_empty());
+    let uri = format!("/purchase-orders/{}", order_id);
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let order: PurchaseOrder = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.status, PurchaseOrderStatus::Open);
+    assert!(order.receipts.is_empty());
+}
@@ -0,0 +1,178 @@
+use super::{app, send};
+use axum::body::{Body, to_bytes};
+use axum::{
+    Router,
+    http::{self, Request, StatusCode},
+};
+use coffee_shared::models::{GreenCoffee, ProfileVerdict, Roast, RoastEventKind, RoastProfile};
+use serde_json::json;
+use tower::ServiceExt;
+
+// A trimmed down Artisan log: readings every 10 s, charge at the second
+// reading, dry end, first crack and drop marked in `timeindex`. Bean
+// temperatures after charge are raised by `offset`.
+fn alog(offset: f64) -> String {
+    let bean_temps = [
+        200.0, 150.0, 120.0, 110.0, 115.0, 130.0, 150.0, 170.0, 190.0, 200.0, 205.0,
+    ]
+    .map(|t| format!("{:.1}", t + offset))
+    .join(", ");
+
+    format!(
+        r#"{{'recording_version': u'2.8.4', 'mode': 'C', 'roastertype': u'Diedrich IR-2.5',
+ 'beans': 'Kenya AA\nlot 7', 'flavors': [5.0, 5.0], 'heavyFC': False, 'weight': [1000.0, 850.0, 'g'],
+ 'timex': [0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 70.0, 80.0, 90.0, 100.0, 110.0],
+ 'temp1': [220.0, 230.0, 210.0, 205.0, 208.0, 212.0, 218.0, 224.0, 230.0, 236.0, 240.0, 242.0],
+ 'temp2': [-1, {}],
+ 'timeindex': [1, 6, 9, 0, 0, 0, 11, 0],
+ 'computed': {{'CHARGE_BT': 200.0, 'TP_idx': 4, 'det': None}},
+ 'extratimex': [], 'etypes': ('Air', 'Drum', 'Damper', 'Burner', '--')}}
+"#,
+        bean_temps
+    )
+}
+
+async fn import(app: &Router, uri: &str, alog: &str) -> (StatusCode, Vec<u8>) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri(uri)
+                .body(Body::from(alog.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), 1_000_000).await.unwrap();
+    (status, body.to_vec())
+}
+
+#[tokio::test]
+async fn import_artisan_profile_test() {
+    let app = app().await;
+
+    let roast_data = json!({
+        "name": "Kenya AA lot 7",
+        "roast_level": "Medium",
+        "batch_size_grams": 1000.0,
+        "yield_grams": 850.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+    let roast: Roast = serde_json::from_slice(&body).unwrap();
+    let uri = format!("/roasts/{}/profile", roast.id.unwrap().id.to_raw());
+
+    let (status, _) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = import(&app, &uri, "{'timex': [0.0, 10.0").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // Deep nesting is refused rather than overflowing the stack
+    let nested = format!("{{'timex': {}}}", "[".repeat(100_000));
+    let (status, _) = import(&app, &uri, &nested).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = import(&app, &uri, &alog(0.0)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let profile: RoastProfile = serde_json::from_slice(&body).unwrap();
+    assert_eq!(profile.temperature_unit, "C");
+    assert_eq!(profile.samples.len(), 12);
+
+    // Times are relative to charge and missing readings are dropped
+    assert_eq!(profile.samples[0].time_seconds, -10.0);
+    assert_eq!(profile.samples[0].bean_temp, None);
+    let rate_of_rise = profile.samples[11].rate_of_rise.unwrap();
+    assert!((rate_of_rise - 70.0).abs() < 1e-9);
+
+    let kinds: Vec<RoastEventKind> = profile.events.iter().map(|e| e.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            RoastEventKind::Charge,
+            RoastEventKind::TurningPoint,
+            RoastEventKind::DryEnd,
+            RoastEventKind::FirstCrack,
+            RoastEventKind::Drop,
+        ]
+    );
+    let turning_point = profile.event(RoastEventKind::TurningPoint).unwrap();
+    assert_eq!(turning_point.time_seconds, 30.0);
+    assert_eq!(turning_point.bean_temp, Some(110.0));
+    assert_eq!(
+        profile.event(RoastEventKind::Drop).unwrap().time_seconds,
+        100.0
+    );
+}
+
+#[tokio::test]
+async fn compare_with_reference_profile_test() {
+    let app = app().await;
+
+    let green_data = json!({
+        "name": "Kenya AA",
+        "origin_country": "Kenya",
+        "stock_grams": 10000.0
+    });
+    let (_, body) = send(&app, http::Method::POST, "/greens", green_data).await;
+    let green: GreenCoffee = serde_json::from_slice(&body).unwrap();
+    let green_id = green.id.unwrap();
+
+    let mut roast_ids = Vec::new();
+    for name in ["Reference", "Close", "Hot"] {
+        let roast_data = json!({
+            "name": name,
+            "green_coffee": green_id,
+            "roast_level": "Medium",
+            "batch_size_grams": 1000.0,
+            "yield_grams": 850.0
+        });
+        let (_, body) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+        let roast: Roast = serde_json::from_slice(&body).unwrap();
+        roast_ids.push(roast.id.unwrap());
+    }
+    let reference_id = roast_ids[0].clone();
+    let [reference, close, hot] = [0, 1, 2].map(|i| roast_ids[i].id.to_raw());
+
+    let uri = format!("/roasts/{}/profile", reference);
+    let (status, _) = import(&app, &uri, &alog(0.0)).await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/greens/{}", green_id.id.to_raw());
+    let update = json!({ "reference_roast": reference_id });
+    let (status, _) = send(&app, http::Method::PUT, &uri, update).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Importing compares against the reference straight away
+    let uri = format!("/roasts/{}/profile", close);
+    import(&app, &uri, &alog(1.0)).await;
+    let (_, body) = send(
+        &app,
+        http::Method::GET,
+        &format!("/roasts/{}", close),
+        json!({}),
+    )
+    .await;
+    let roast: Roast = serde_json::from_slice(&body).unwrap();
+    let comparison = roast.profile_comparison.unwrap();
+    assert_eq!(comparison.verdict, ProfileVerdict::Pass);
+    assert!((comparison.bean_temp_rmse - 1.0).abs() < 1e-9);
+    assert_eq!(comparison.development_time_ratio_diff, Some(0.0));
+
+    let uri = format!("/roasts/{}/profile", hot);
+    import(&app, &uri, &alog(8.0)).await;
+    let uri = format!("/roasts/{}/profile/compare", hot);
+    let (status, body) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let roast: Roast = serde_json::from_slice(&body).unwrap();
+    let comparison = roast.profile_comparison.unwrap();
+    assert_eq!(comparison.verdict, ProfileVerdict::Fail);
+    assert!((comparison.drop_temp_delta.unwrap() - 8.0).abs() < 1e-9);
+
+    let uri = format!("/roasts/{}/profile/compare", reference);
+    let (status, _) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -22,9 +22,8 @@ async fn create_roast_test() {
         "altitude_masl": 1700,
         "harvest_year": 2022,
         "stock_grams": 1000.0,
-        "price_per_kg": 20.0,
-        "price_currency": "USD",
-        "supplier": "Cafe Imports",
+        "price_per_kg": { "amount": "20.00", "currency": "USD" },
+        "supplier": null,
         "cupping_notes": ["Chocolate", "Caramel"]
     });
 
@@ -0,0 +1,122 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{
+    Customer, Order, Product, Subscription, SubscriptionForecastLine, SubscriptionStatus,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn subscription_generates_orders_test() {
+    let app = app().await;
+
+    let customer_data = json!({"name": "Subscriber", "email": "subscriber@example.com"});
+    let (_, body) = send(&app, http::Method::POST, "/customers", customer_data).await;
+    let customer: Customer = serde_json::from_slice(&body).unwrap();
+
+    let product_data = json!({
+        "name": "House Espresso",
+        "package_size_grams": 250.0,
+        "price": { "amount": "11.00", "currency": "GBP" },
+        "stock_units": 5,
+        "roast": null
+    });
+    let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let product_id = product.id.unwrap();
+
+    let subscription_data = json!({
+        "customer": customer.id.unwrap(),
+        "product": product_id,
+        "quantity": 2,
+        "interval_weeks": 2
+    });
+    let (status, body) = send(
+        &app,
+        http::Method::POST,
+        "/subscriptions",
+        subscription_data,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let subscription: Subscription = serde_json::from_slice(&body).unwrap();
+    assert_eq!(subscription.status, SubscriptionStatus::Active);
+    let subscription_id = subscription.id.unwrap().id.to_raw();
+
+    // Due now, then every two weeks: three bag-pairs in the next five weeks
+    let uri = "/subscriptions/forecast?weeks=5";
+    let (status, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let forecast: Vec<SubscriptionForecastLine> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(forecast.len(), 1);
+    assert_eq!(forecast[0].bags, 6);
+    assert_eq!(forecast[0].grams, Some(1500.0));
+
+    for weeks in ["105", "100000000"] {
+        let uri = format!("/subscriptions/forecast?weeks={}", weeks);
+        let (status, _) = send(&app, http::Method::GET, &uri, json!({})).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let (status, body) = send(&app, http::Method::POST, "/subscriptions/run", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let orders: Vec<Order> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(orders.len(), 1);
+    assert!(orders[0].stock_reserved);
+    assert_eq!(orders[0].lines[0].quantity, 2);
+
+    let uri = format!("/products/{}", product_id.id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    assert_eq!(product.variants[0].reserved_units, 2);
+
+    // Refunding the order gives its reserved stock back
+    let order_uri = format!("/orders/{}", orders[0].id.clone().unwrap().id.to_raw());
+    let (status, _) = send(
+        &app,
+        http::Method::POST,
+        &format!("{}/pay", order_uri),
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = send(
+        &app,
+        http::Method::POST,
+        &format!("{}/refund", order_uri),
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let refunded: Order = serde_json::from_slice(&body).unwrap();
+    assert!(!refunded.stock_reserved);
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    assert_eq!(product.variants[0].reserved_units, 0);
+
+    // Already moved on to the next run, so nothing more is due
+    let (_, body) = send(&app, http::Method::POST, "/subscriptions/run", json!({})).await;
+    let orders: Vec<Order> = serde_json::from_slice(&body).unwrap();
+    assert!(orders.is_empty());
+
+    let uri = format!("/subscriptions/{}/pause", subscription_id);
+    let (status, body) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let subscription: Subscription = serde_json::from_slice(&body).unwrap();
+    assert_eq!(subscription.status, SubscriptionStatus::Paused);
+
+    let uri = format!("/subscriptions/{}", subscription_id);
+    let update = json!({ "roasters_choice": true, "product": product_id });
+    let (status, _) = send(&app, http::Method::PUT, &uri, update).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let update = json!({ "roasters_choice": true });
+    let (status, body) = send(&app, http::Method::PUT, &uri, update).await;
+    assert_eq!(status, StatusCode::OK);
+    let subscription: Subscription = serde_json::from_slice(&body).unwrap();
+    assert_eq!(subscription.product, None);
+    assert_eq!(subscription.variant, None);
+
+    let uri = "/subscriptions/forecast?weeks=5";
+    let (_, body) = send(&app, http::Method::GET, uri, json!({})).await;
+    let forecast: Vec<SubscriptionForecastLine> = serde_json::from_slice(&body).unwrap();
+    assert!(forecast.is_empty());
+}
@@ -0,0 +1,97 @@
+use super::{app_with_db, migrated_db, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{GreenCoffee, Supplier, SupplierMigrationReport};
+use serde_json::json;
+
+#[tokio::test]
+async fn migrate_free_text_suppliers_test() {
+    let db = migrated_db().await;
+    let app = app_with_db(db.clone());
+
+    let supplier_data = json!({
+        "name": "Royal Coffee",
+        "lead_time_days": 45,
+        "currency": "USD",
+        "payment_terms": "Net 30"
+    });
+    let (status, body) = send(&app, http::Method::POST, "/suppliers", supplier_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let royal: Supplier = serde_json::from_slice(&body).unwrap();
+
+    // Greens saved before suppliers were records
+    for (name, supplier) in [
+        ("Huila", "Cafe Imports"),
+        ("Sidama", "Café Imports"),
+        ("Nyeri", " café imports, Inc. "),
+        ("Cerrado", "ROYAL COFFEE LLC"),
+        ("Unknown", "  "),
+    ] {
+        db.query("CREATE green_coffee CONTENT $green")
+            .bind((
+                "green",
+                json!({
+                    "name": name,
+                    "origin_country": "Unknown",
+                    "stock_grams": 1000.0,
+                    "legacy_supplier": supplier
+                }),
+            ))
+            .await
+            .unwrap();
+    }
+
+    let uri = "/suppliers/migrate?dry_run=true";
+    let (status, body) = send(&app, http::Method::POST, uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: SupplierMigrationReport = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report.suppliers.len(), 2);
+    assert!(
+        report
+            .suppliers
+            .iter()
+            .all(|merge| merge.created || merge.supplier.is_some())
+    );
+    let (_, body) = send(&app, http::Method::GET, "/suppliers", json!({})).await;
+    let suppliers: Vec<Supplier> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(suppliers.len(), 1);
+
+    let (status, body) = send(&app, http::Method::POST, "/suppliers/migrate", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: SupplierMigrationReport = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report.cleared.len(), 1);
+
+    // Three spellings collapse into one new supplier
+    let cafe = report.suppliers.iter().find(|merge| merge.created).unwrap();
+    assert_eq!(cafe.merged_names.len(), 3);
+    assert_eq!(cafe.greens.len(), 3);
+
+    // The existing supplier picks up the new spelling as an alias
+    let merge = report
+        .suppliers
+        .iter()
+        .find(|merge| !merge.created)
+        .unwrap();
+    assert_eq!(merge.supplier, royal.id);
+    let uri = format!("/suppliers/{}", royal.id.clone().unwrap().id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let royal: Supplier = serde_json::from_slice(&body).unwrap();
+    assert_eq!(royal.aliases, vec!["ROYAL COFFEE LLC".to_string()]);
+    assert_eq!(royal.lead_time_days, Some(45));
+
+    let (_, body) = send(&app, http::Method::GET, "/greens", json!({})).await;
+    let greens: Vec<GreenCoffee> = serde_json::from_slice(&body).unwrap();
+    assert!(greens.iter().all(|green| green.legacy_supplier.is_none()));
+    let linked = greens
+        .iter()
+        .filter(|green| green.supplier == cafe.supplier)
+        .count();
+    assert_eq!(linked, 3);
+
+    // Names are unique once accents and case are ignored
+    let supplier_data = json!({"name": "CAFE IMPORTS"});
+    let (status, _) = send(&app, http::Method::POST, "/suppliers", supplier_data).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, _) = send(&app, http::Method::DELETE, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
@@ -0,0 +1,122 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{
+    Order, Product, PurchaseOrder, Roast, Supplier, TraceDirection, TraceGraph,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn trace_bag_to_farm_test() {
+    let app = app().await;
+
+    let supplier_data = json!({"name": "Royal Coffee"});
+    let (_, body) = send(&app, http::Method::POST, "/suppliers", supplier_data).await;
+    let supplier: Supplier = serde_json::from_slice(&body).unwrap();
+    let supplier_id = supplier.id.unwrap();
+
+    let order_data = json!({
+        "supplier": supplier_id,
+        "reference": "RC-77",
+        "lines": [{
+            "lot": "Santa Barbara",
+            "origin_country": "Honduras",
+            "contracted_grams": 30000.0,
+            "price_per_kg": { "amount": "7.00", "currency": "USD" }
+        }]
+    });
+    let (_, body) = send(&app, http::Method::POST, "/purchase-orders", order_data).await;
+    let purchase_order: PurchaseOrder = serde_json::from_slice(&body).unwrap();
+    let purchase_order_id = purchase_order.id.unwrap();
+    let uri = format!(
+        "/purchase-orders/{}/receipts",
+        purchase_order_id.id.to_raw()
+    );
+    let receipt = json!({"lines": [{ "line": 0, "grams": 30000.0 }]});
+    let (_, body) = send(&app, http::Method::POST, &uri, receipt).await;
+    let purchase_order: PurchaseOrder = serde_json::from_slice(&body).unwrap();
+    let green_id = purchase_order.lines[0].green_coffee.clone().unwrap();
+
+    let mut product_ids = Vec::new();
+    for name in ["Santa Barbara 0901", "Santa Barbara 0908"] {
+        let roast_data = json!({
+            "name": name,
+            "green_coffee": green_id,
+            "roast_level": "Medium",
+            "batch_size_grams": 12000.0,
+            "yield_grams": 10000.0
+        });
+        let (_, body) = send(&app, http::Method::POST, "/roasts", roast_data).await;
+        let roast: Roast = serde_json::from_slice(&body).unwrap();
+        let product_data = json!({
+            "roast": roast.id,
+            "name": format!("{} 250g", name),
+            "package_size_grams": 250.0,
+            "price": { "amount": "13.00", "currency": "USD" },
+            "stock_units": 40
+        });
+        let (_, body) = send(&app, http::Method::POST, "/products", product_data).await;
+        let product: Product = serde_json::from_slice(&body).unwrap();
+        product_ids.push(product.id.unwrap());
+    }
+
+    let order_data = json!({"lines": [{ "product": product_ids[0], "quantity": 2 }]});
+    let (_, body) = send(&app, http::Method::POST, "/orders", order_data).await;
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    let order_id = order.id.unwrap();
+
+    // From the bag back to the farm
+    let uri = format!("/trace/{}", order_id);
+    let (status, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let graph: TraceGraph = serde_json::from_slice(&body).unwrap();
+    let upstream: Vec<String> = graph
+        .nodes
+        .iter()
+        .filter(|node| node.direction == TraceDirection::Upstream)
+        .map(|node| node.id.tb.clone())
+        .collect();
+    assert_eq!(
+        upstream,
+        vec![
+            "product",
+            "roast",
+            "green_coffee",
+            "purchase_order",
+            "supplier"
+        ]
+    );
+    assert_eq!(graph.origins.len(), 1);
+    assert_eq!(graph.origins[0].origin_country, "Honduras");
+
+    // Everything a recall of the lot would touch
+    let uri = format!("/trace/{}", green_id);
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let graph: TraceGraph = serde_json::from_slice(&body).unwrap();
+    let downstream = |table: &str| {
+        graph
+            .nodes
+            .iter()
+            .filter(|node| node.direction == TraceDirection::Downstream && node.id.tb == table)
+            .count()
+    };
+    assert_eq!(downstream("roast"), 2);
+    assert_eq!(downstream("product"), 2);
+    assert_eq!(downstream("order"), 1);
+    let supplier_node = graph
+        .nodes
+        .iter()
+        .find(|node| node.id == supplier_id)
+        .unwrap();
+    assert_eq!(supplier_node.name.as_deref(), Some("Royal Coffee"));
+    assert!(
+        graph
+            .edges
+            .iter()
+            .any(|edge| edge.relation == "bought_on" && edge.to == purchase_order_id)
+    );
+
+    let (status, _) = send(&app, http::Method::GET, "/trace/payment:x", json!({})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, http::Method::GET, "/trace/roast:missing", json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
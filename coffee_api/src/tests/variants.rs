@@ -0,0 +1,156 @@
+use super::{app, send};
+use axum::http::{self, StatusCode};
+use coffee_shared::models::{Grind, InventoryMovement, Order, PickListLine, Product};
+use rust_decimal::Decimal;
+use serde_json::json;
+
+#[tokio::test]
+async fn product_variants_test() {
+    let app = app().await;
+
+    let product_data = json!({
+        "name": "Ethiopia Guji",
+        "description": "Natural process",
+        "roast": null,
+        "variants": [
+            {
+                "package_size_grams": 250.0,
+                "price": { "amount": "12.50", "currency": "USD" },
+                "stock_units": 20
+            },
+            {
+                "package_size_grams": 1000.0,
+                "grind": "espresso",
+                "sku": "GUJI-1KG-ESP",
+                "price": { "amount": "40.00", "currency": "USD" },
+                "stock_units": 5
+            }
+        ]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/products", product_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let product_id = product.id.unwrap();
+    let keys: Vec<&str> = product.variants.iter().map(|v| v.key.as_str()).collect();
+    assert_eq!(keys, ["250g-whole-bean", "1000g-espresso"]);
+
+    // The same size and grind twice is rejected
+    let variants_uri = format!("/products/{}/variants", product_id.id.to_raw());
+    let duplicate = json!({
+        "package_size_grams": 250.0,
+        "price": { "amount": "13.00", "currency": "USD" },
+        "stock_units": 1
+    });
+    let (status, _) = send(&app, http::Method::POST, &variants_uri, duplicate).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let filter = json!({
+        "package_size_grams": 250.0,
+        "grind": "filter",
+        "price": { "amount": "12.50", "currency": "USD" },
+        "stock_units": 8
+    });
+    let (status, body) = send(&app, http::Method::POST, &variants_uri, filter).await;
+    assert_eq!(status, StatusCode::OK);
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    assert_eq!(product.variants.len(), 3);
+
+    let uri = format!("{}/1000g-espresso", variants_uri);
+    let update = json!({ "price": { "amount": "38.00", "currency": "USD" } });
+    let (status, body) = send(&app, http::Method::PUT, &uri, update).await;
+    assert_eq!(status, StatusCode::OK);
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let espresso = product.variant(Some("1000g-espresso")).unwrap();
+    assert_eq!(espresso.price.amount, Decimal::new(3800, 2));
+    assert_eq!(espresso.sku.as_deref(), Some("GUJI-1KG-ESP"));
+
+    // A product with several variants needs one named on the order line
+    let order_data = json!({ "lines": [{ "product": product_id, "quantity": 1 }] });
+    let (status, _) = send(&app, http::Method::POST, "/orders", order_data).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let order_data = json!({
+        "lines": [{ "product": product_id, "variant": "500g-filter", "quantity": 1 }]
+    });
+    let (status, _) = send(&app, http::Method::POST, "/orders", order_data).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let order_data = json!({
+        "lines": [
+            { "product": product_id, "variant": "1000g-espresso", "quantity": 2 },
+            { "product": product_id, "variant": "250g-whole-bean", "quantity": 3 }
+        ]
+    });
+    let (status, body) = send(&app, http::Method::POST, "/orders", order_data).await;
+    assert_eq!(status, StatusCode::OK);
+    let order: Order = serde_json::from_slice(&body).unwrap();
+    assert_eq!(order.total.amount, Decimal::new(11350, 2));
+    let order_id = order.id.unwrap().id.to_raw();
+
+    let uri = format!("/orders/{}/pay", order_id);
+    let (status, _) = send(&app, http::Method::POST, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Stock cannot go negative
+    let uri = format!("{}/1000g-espresso", variants_uri);
+    let (status, _) = send(&app, http::Method::PUT, &uri, json!({ "stock_units": -1 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let status_uri = format!("/orders/{}/status", order_id);
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        &status_uri,
+        json!({"status": "picking"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = send(&app, http::Method::GET, "/pick-list", json!({})).await;
+    let pick_list: Vec<PickListLine> = serde_json::from_slice(&body).unwrap();
+    assert_eq!(pick_list.len(), 2);
+    let espresso_pick = pick_list
+        .iter()
+        .find(|line| line.variant == "1000g-espresso")
+        .unwrap();
+    assert_eq!(espresso_pick.grind, Grind::Espresso);
+    assert_eq!(espresso_pick.quantity, 2);
+
+    let (status, _) = send(
+        &app,
+        http::Method::PUT,
+        &status_uri,
+        json!({"status": "packed"}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let shipment = json!({"status": "shipped", "tracking_number": "1Z999", "carrier": "UPS"});
+    let (status, _) = send(&app, http::Method::PUT, &status_uri, shipment).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let uri = format!("/products/{}", product_id.id.to_raw());
+    let (_, body) = send(&app, http::Method::GET, &uri, json!({})).await;
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    let stock: Vec<i32> = product.variants.iter().map(|v| v.stock_units).collect();
+    assert_eq!(stock, [17, 3, 8]);
+
+    let (_, body) = send(&app, http::Method::GET, "/inventory-movements", json!({})).await;
+    let movements: Vec<InventoryMovement> = serde_json::from_slice(&body).unwrap();
+    assert!(movements.iter().any(
+        |movement| movement.variant.as_deref() == Some("1000g-espresso")
+            && movement.quantity == -2.0
+    ));
+
+    // Flat variant fields only apply to single-variant products
+    let (status, _) = send(&app, http::Method::PUT, &uri, json!({ "stock_units": 50 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let uri = format!("{}/250g-filter", variants_uri);
+    let (status, body) = send(&app, http::Method::DELETE, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let product: Product = serde_json::from_slice(&body).unwrap();
+    assert_eq!(product.variants.len(), 2);
+    let (status, _) = send(&app, http::Method::DELETE, &uri, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
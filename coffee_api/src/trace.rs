@@ -0,0 +1,258 @@
+use crate::db::Db;
+use crate::error::ApiResult;
+use crate::models::{
+    GreenCoffee, Order, Product, PurchaseOrder, Roast, TraceDirection, TraceEdge, TraceGraph,
+    TraceNode,
+};
+use serde::Deserialize;
+use surrealdb::sql::Thing;
+
+/// Graph relations kept alongside the record links they mirror. Every
+/// relation points upstream, from what was made towards what it was made
+/// from, so following `->` walks towards the farm and `<-` towards customers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    /// order -> product
+    Ordered,
+    /// product -> roast
+    PackedFrom,
+    /// roast -> green_coffee
+    RoastedFrom,
+    /// roast -> roast, for post-roast blends
+    BlendedFrom,
+    /// green_coffee -> supplier
+    SuppliedBy,
+    /// green_coffee -> purchase_order
+    BoughtOn,
+    /// purchase_order -> supplier
+    PlacedWith,
+}
+
+impl Relation {
+    pub fn table(self) -> &'static str {
+        match self {
+            Relation::Ordered => "ordered",
+            Relation::PackedFrom => "packed_from",
+            Relation::RoastedFrom => "roasted_from",
+            Relation::BlendedFrom => "blended_from",
+            Relation::SuppliedBy => "supplied_by",
+            Relation::BoughtOn => "bought_on",
+            Relation::PlacedWith => "placed_with",
+        }
+    }
+}
+
+/// Replaces the `relation` edges leaving `from` with one edge to each of `to`.
+pub async fn link(
+    db: &Db,
+    from: &Thing,
+    relation: Relation,
+    to: impl IntoIterator<Item = Thing>,
+) -> ApiResult<()> {
+    let mut to: Vec<Thing> = to.into_iter().collect();
+    to.sort();
+    to.dedup();
+
+    let table = relation.table();
+    db.query(format!(
+        "DELETE $from->{table}; IF array::len($to) > 0 {{ RELATE $from->{table}->$to }};"
+    ))
+    .bind(("from", from.clone()))
+    .bind(("to", to))
+    .await?
+    .check()?;
+    Ok(())
+}
+
+/// Replaces the `relation` edges arriving at `to` with one edge from each of
+/// `from`.
+pub async fn link_into(
+    db: &Db,
+    to: &Thing,
+    relation: Relation,
+    from: impl IntoIterator<Item = Thing>,
+) -> ApiResult<()> {
+    let mut from: Vec<Thing> = from.into_iter().collect();
+    from.sort();
+    from.dedup();
+
+    let table = relation.table();
+    db.query(format!(
+        "DELETE $to<-{table}; IF array::len($from) > 0 {{ RELATE $from->{table}->$to }};"
+    ))
+    .bind(("from", from))
+    .bind(("to", to.clone()))
+    .await?
+    .check()?;
+    Ok(())
+}
+
+/// Mirrors the record links of a saved order as relations.
+pub async fn relate_order(db: &Db, order: &Order) -> ApiResult<()> {
+    if let Some(id) = &order.id {
+        let products = order.lines.iter().map(|line| line.product.clone());
+        link(db, id, Relation::Ordered, products).await?;
+    }
+    Ok(())
+}
+
+/// Mirrors the record links of a saved product as relations.
+pub async fn relate_product(db: &Db, product: &Product) -> ApiResult<()> {
+    if let Some(id) = &product.id {
+        link(db, id, Relation::PackedFrom, product.roast.clone()).await?;
+    }
+    Ok(())
+}
+
+/// Mirrors the record links of a saved roast as relations. Post-roast blends
+/// link to their component roasts, which link on to the greens.
+pub async fn relate_roast(db: &Db, roast: &Roast) -> ApiResult<()> {
+    if let Some(id) = &roast.id {
+        let greens = roast.green_usage().into_iter().map(|(green, _)| green);
+        link(db, id, Relation::RoastedFrom, greens).await?;
+        let roasts = roast
+            .components
+            .iter()
+            .filter_map(|component| component.roast.clone());
+        link(db, id, Relation::BlendedFrom, roasts).await?;
+    }
+    Ok(())
+}
+
+/// Mirrors the record links of a saved green coffee as relations.
+pub async fn relate_green(db: &Db, green: &GreenCoffee) -> ApiResult<()> {
+    if let Some(id) = &green.id {
+        link(db, id, Relation::SuppliedBy, green.supplier.clone()).await?;
+    }
+    Ok(())
+}
+
+/// Mirrors the record links of a saved purchase order as relations, including
+/// the greens its lines have been received into.
+pub async fn relate_purchase_order(db: &Db, order: &PurchaseOrder) -> ApiResult<()> {
+    if let Some(id) = &order.id {
+        link(db, id, Relation::PlacedWith, [order.supplier.clone()]).await?;
+        let greens = order
+            .lines
+            .iter()
+            .filter_map(|line| line.green_coffee.clone());
+        link_into(db, id, Relation::BoughtOn, greens).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct Edge {
+    id: Thing,
+    #[serde(rename = "in")]
+    from: Thing,
+    #[serde(rename = "out")]
+    to: Thing,
+}
+
+#[derive(Debug, Deserialize)]
+struct Named {
+    id: Thing,
+    name: Option<String>,
+    reference: Option<String>,
+}
+
+/// Adds everything reachable from `root` by following relations one way to
+/// `nodes`: upstream along `->`, downstream along `<-`.
+async fn walk(
+    db: &Db,
+    root: &Thing,
+    direction: TraceDirection,
+    nodes: &mut Vec<TraceNode>,
+    edges: &mut Vec<TraceEdge>,
+) -> ApiResult<()> {
+    let query = match direction {
+        TraceDirection::Downstream => "SELECT VALUE <-?.{id, in, out} FROM $records",
+        _ => "SELECT VALUE ->?.{id, in, out} FROM $records",
+    };
+
+    let mut frontier = vec![root.clone()];
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut response = db.query(query).bind(("records", frontier)).await?;
+        let found: Vec<Vec<Edge>> = response.take(0)?;
+
+        frontier = Vec::new();
+        for edge in found.into_iter().flatten() {
+            let next = match direction {
+                TraceDirection::Downstream => edge.from.clone(),
+                _ => edge.to.clone(),
+            };
+            let trace_edge = TraceEdge {
+                relation: edge.id.tb,
+                from: edge.from,
+                to: edge.to,
+            };
+            if !edges.contains(&trace_edge) {
+                edges.push(trace_edge);
+            }
+            if !nodes.iter().any(|node| node.id == next) {
+                nodes.push(TraceNode {
+                    id: next.clone(),
+                    name: None,
+                    direction,
+                    depth,
+                });
+                frontier.push(next);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The records `root` was made from and the records made from it.
+pub async fn trace(db: &Db, root: Thing) -> ApiResult<TraceGraph> {
+    let mut nodes = vec![TraceNode {
+        id: root.clone(),
+        name: None,
+        direction: TraceDirection::Root,
+        depth: 0,
+    }];
+    let mut edges = Vec::new();
+    walk(db, &root, TraceDirection::Upstream, &mut nodes, &mut edges).await?;
+    walk(
+        db,
+        &root,
+        TraceDirection::Downstream,
+        &mut nodes,
+        &mut edges,
+    )
+    .await?;
+
+    let ids: Vec<Thing> = nodes.iter().map(|node| node.id.clone()).collect();
+    let mut response = db
+        .query("SELECT id, name, reference FROM $ids")
+        .bind(("ids", ids.clone()))
+        .await?;
+    let named: Vec<Named> = response.take(0)?;
+    for node in &mut nodes {
+        node.name = named
+            .iter()
+            .find(|record| record.id == node.id)
+            .and_then(|record| record.name.clone().or(record.reference.clone()));
+    }
+    nodes.sort_by_key(|node| (node.direction as u8, node.depth, node.id.to_string()));
+
+    let green_ids: Vec<Thing> = ids
+        .into_iter()
+        .filter(|id| id.tb == "green_coffee")
+        .collect();
+    let mut response = db
+        .query("SELECT * FROM $greens")
+        .bind(("greens", green_ids))
+        .await?;
+    let origins: Vec<GreenCoffee> = response.take(0)?;
+
+    Ok(TraceGraph {
+        root,
+        nodes,
+        edges,
+        origins,
+    })
+}
@@ -0,0 +1,23 @@
+//! Serializers for timestamps stored in the database. The SurrealDB client
+//! only turns a `DateTime` into a datetime value at the top level of what it
+//! is sent; inside a record it arrives as a string, which `datetime` fields
+//! reject. Going through `sql::Datetime` stores a datetime wherever the field
+//! sits, while JSON output is unchanged.
+
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Serializer};
+use surrealdb::sql::Datetime;
+
+pub fn serialize<S: Serializer>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    Datetime::from(*value).serialize(serializer)
+}
+
+pub fn serialize_option<S: Serializer>(
+    value: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => serializer.serialize_some(&Datetime::from(*value)),
+        None => serializer.serialize_none(),
+    }
+}
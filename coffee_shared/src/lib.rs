@@ -1,3 +1,6 @@
+pub mod datetime;
 pub mod models;
+pub mod money;
 
 pub use models::*;
+pub use money::*;
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StockItemKind {
+    GreenCoffee,
+    Product,
+}
+
+/// A green lot or product whose stock has fallen below its reorder point.
+/// Quantities are grams for green coffee and units for products.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StockAlert {
+    pub item: Thing,
+    /// The variant of a product item.
+    #[serde(default)]
+    pub variant: Option<String>,
+    pub kind: StockItemKind,
+    pub name: String,
+    pub stock: f64,
+    pub reorder_point: f64,
+    /// Average use per day over the lookback window.
+    pub daily_consumption: f64,
+    /// Days until the stock runs out at that rate; `None` when nothing was
+    /// used in the window.
+    pub days_of_cover: Option<f64>,
+}
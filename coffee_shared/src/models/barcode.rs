@@ -0,0 +1,45 @@
+use super::Product;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+/// How SKUs are built for variants saved without one. The template may use
+/// `{name}`, an abbreviation of the product name, and `{size}` and `{grind}`
+/// for the variant, e.g. `{name}-{size}-{grind}` gives `ETHGUJ-250-WB`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkuSettings {
+    pub id: Option<Thing>,
+    pub template: String,
+    /// Most characters of the product name kept for `{name}`.
+    pub name_length: usize,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for SkuSettings {
+    fn default() -> Self {
+        Self {
+            id: None,
+            template: "{name}-{size}-{grind}".to_string(),
+            name_length: 6,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSkuSettingsRequest {
+    pub template: Option<String>,
+    pub name_length: Option<usize>,
+}
+
+/// The product and variant a scanned barcode belongs to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarcodeMatch {
+    pub product: Product,
+    pub variant: String,
+}
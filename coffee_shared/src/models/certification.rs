@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificationType {
+    Organic,
+    Fairtrade,
+    RainforestAlliance,
+    BirdFriendly,
+}
+
+impl CertificationType {
+    pub const ALL: [CertificationType; 4] = [
+        CertificationType::Organic,
+        CertificationType::Fairtrade,
+        CertificationType::RainforestAlliance,
+        CertificationType::BirdFriendly,
+    ];
+}
+
+/// A certificate held for a green lot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Certification {
+    #[serde(rename = "type")]
+    pub certification_type: CertificationType,
+    pub certificate_number: String,
+    #[serde(serialize_with = "crate::datetime::serialize")]
+    pub expires_at: DateTime<Utc>,
+    /// Link to a copy of the certificate.
+    #[serde(default)]
+    pub document: Option<String>,
+}
+
+impl Certification {
+    pub fn is_valid(&self, at: DateTime<Utc>) -> bool {
+        at < self.expires_at
+    }
+}
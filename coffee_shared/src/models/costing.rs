@@ -0,0 +1,74 @@
+use crate::money::{Currency, Money};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+/// Overheads added on top of green coffee when costing roasts and bags. All
+/// amounts are in `currency`, which is also the currency costs are reported in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostSettings {
+    pub id: Option<Thing>,
+    pub currency: Currency,
+    pub packaging_per_unit: Decimal,
+    pub labour_per_batch: Decimal,
+    pub energy_per_batch: Decimal,
+    /// Products whose margin is below this percentage of their price are flagged.
+    pub min_margin_percent: Decimal,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for CostSettings {
+    fn default() -> Self {
+        Self {
+            id: None,
+            currency: "USD".parse().expect("USD is an ISO 4217 currency"),
+            packaging_per_unit: Decimal::ZERO,
+            labour_per_batch: Decimal::ZERO,
+            energy_per_batch: Decimal::ZERO,
+            min_margin_percent: Decimal::from(30),
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCostSettingsRequest {
+    pub currency: Option<Currency>,
+    pub packaging_per_unit: Option<Decimal>,
+    pub labour_per_batch: Option<Decimal>,
+    pub energy_per_batch: Option<Decimal>,
+    pub min_margin_percent: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoastCost {
+    pub roast: Thing,
+    pub green_cost: Money,
+    /// Labour and energy for the batch.
+    pub overheads: Money,
+    pub total_cost: Money,
+    /// Weight lost in roasting as a percentage of the green batch.
+    pub loss_percent: f64,
+    pub cost_per_roasted_kg: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductCost {
+    pub product: Thing,
+    pub variant: String,
+    pub roast: Thing,
+    pub cost_per_roasted_kg: Money,
+    /// Roasted coffee in one unit plus its packaging.
+    pub unit_cost: Money,
+    /// The variant's price in the costing currency.
+    pub price: Money,
+    pub margin: Money,
+    pub margin_percent: Decimal,
+    pub below_threshold: bool,
+}
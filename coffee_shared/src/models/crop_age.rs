@@ -0,0 +1,111 @@
+use chrono::{DateTime, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CropAgeStatus {
+    Current,
+    /// Within the warning window of its past-crop date.
+    NearingPastCrop,
+    PastCrop,
+}
+
+/// When an origin's harvest comes in and how long its green keeps before
+/// it counts as past-crop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRule {
+    /// Month (1-12) the harvest year's crop is taken to be in.
+    pub harvest_month: u32,
+    /// Months after the harvest, or after arrival for lots without a
+    /// harvest year, that a lot becomes past-crop.
+    pub past_crop_months: u32,
+}
+
+impl CropRule {
+    /// The date a lot's crop is counted from: its harvest month if it has a
+    /// harvest year, else the day it arrived.
+    pub fn crop_date(
+        &self,
+        harvest_year: Option<i32>,
+        arrival_date: Option<DateTime<Utc>>,
+    ) -> Option<DateTime<Utc>> {
+        match harvest_year {
+            Some(year) => NaiveDate::from_ymd_opt(year, self.harvest_month, 1)
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|date| date.and_utc()),
+            None => arrival_date,
+        }
+    }
+
+    pub fn past_crop_date(&self, crop_date: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        crop_date.checked_add_months(Months::new(self.past_crop_months))
+    }
+}
+
+/// A rule for every lot from one origin country. Countries are matched
+/// ignoring case and surrounding spaces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OriginCropRule {
+    pub origin_country: String,
+    #[serde(flatten)]
+    pub rule: CropRule,
+}
+
+/// Harvest timing and crop life by origin, and how early to warn about lots
+/// going past-crop.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CropAgeSettings {
+    pub id: Option<Thing>,
+    pub default_rule: CropRule,
+    #[serde(default)]
+    pub origins: Vec<OriginCropRule>,
+    /// Days before its past-crop date that a lot is flagged.
+    pub warning_days: i64,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for CropAgeSettings {
+    fn default() -> Self {
+        Self {
+            id: None,
+            default_rule: CropRule {
+                harvest_month: 1,
+                past_crop_months: 12,
+            },
+            origins: Vec::new(),
+            warning_days: 60,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCropAgeSettingsRequest {
+    pub default_rule: Option<CropRule>,
+    pub origins: Option<Vec<OriginCropRule>>,
+    pub warning_days: Option<i64>,
+}
+
+/// How old a green lot's crop is and when it goes past-crop.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CropAge {
+    pub green_coffee: Thing,
+    pub name: String,
+    pub origin_country: String,
+    pub harvest_year: Option<i32>,
+    pub arrival_date: Option<DateTime<Utc>>,
+    pub stock_grams: f64,
+    pub crop_date: DateTime<Utc>,
+    pub age_days: i64,
+    /// Estimated from the origin's crop rule.
+    pub past_crop_date: DateTime<Utc>,
+    /// Negative once the lot is past-crop.
+    pub days_until_past_crop: i64,
+    pub status: CropAgeStatus,
+}
@@ -0,0 +1,273 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleKind {
+    /// Green coffee as it arrived.
+    Arrival,
+    /// Offer sample cupped before a lot ships, which may not be in stock yet.
+    PreShipment,
+    /// A production roast.
+    Production,
+}
+
+/// One coffee on the cupping table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CuppingSample {
+    /// What the cuppers see, usually a code so the table is blind.
+    pub label: String,
+    pub kind: SampleKind,
+    /// For production samples, the green the roast was made from.
+    pub green_coffee: Option<Thing>,
+    pub roast: Option<Thing>,
+    /// Free-text description of a pre-shipment offer.
+    pub description: Option<String>,
+}
+
+/// The ten scored attributes of the SCA cupping form. Quality attributes are
+/// scored 6.00 to 10.00 in quarter points; uniformity, clean cup and sweetness
+/// score 2 points for each of five cups.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CuppingScores {
+    pub fragrance: f64,
+    pub flavour: f64,
+    pub aftertaste: f64,
+    pub acidity: f64,
+    pub body: f64,
+    pub balance: f64,
+    pub uniformity: f64,
+    pub clean_cup: f64,
+    pub sweetness: f64,
+    pub overall: f64,
+}
+
+impl CuppingScores {
+    pub fn quality_attributes(&self) -> [(&'static str, f64); 7] {
+        [
+            ("fragrance", self.fragrance),
+            ("flavour", self.flavour),
+            ("aftertaste", self.aftertaste),
+            ("acidity", self.acidity),
+            ("body", self.body),
+            ("balance", self.balance),
+            ("overall", self.overall),
+        ]
+    }
+
+    pub fn cup_attributes(&self) -> [(&'static str, f64); 3] {
+        [
+            ("uniformity", self.uniformity),
+            ("clean_cup", self.clean_cup),
+            ("sweetness", self.sweetness),
+        ]
+    }
+
+    pub fn total(&self) -> f64 {
+        self.quality_attributes()
+            .iter()
+            .chain(self.cup_attributes().iter())
+            .map(|(_, score)| score)
+            .sum()
+    }
+
+    /// Mean of each attribute across `scores`, or `None` if there are none.
+    pub fn mean<'a>(scores: impl IntoIterator<Item = &'a CuppingScores>) -> Option<Self> {
+        let mut sum = Self {
+            fragrance: 0.0,
+            flavour: 0.0,
+            aftertaste: 0.0,
+            acidity: 0.0,
+            body: 0.0,
+            balance: 0.0,
+            uniformity: 0.0,
+            clean_cup: 0.0,
+            sweetness: 0.0,
+            overall: 0.0,
+        };
+        let mut count = 0;
+        for scores in scores {
+            sum.fragrance += scores.fragrance;
+            sum.flavour += scores.flavour;
+            sum.aftertaste += scores.aftertaste;
+            sum.acidity += scores.acidity;
+            sum.body += scores.body;
+            sum.balance += scores.balance;
+            sum.uniformity += scores.uniformity;
+            sum.clean_cup += scores.clean_cup;
+            sum.sweetness += scores.sweetness;
+            sum.overall += scores.overall;
+            count += 1;
+        }
+        if count == 0 {
+            return None;
+        }
+
+        let n = f64::from(count);
+        Some(Self {
+            fragrance: sum.fragrance / n,
+            flavour: sum.flavour / n,
+            aftertaste: sum.aftertaste / n,
+            acidity: sum.acidity / n,
+            body: sum.body / n,
+            balance: sum.balance / n,
+            uniformity: sum.uniformity / n,
+            clean_cup: sum.clean_cup / n,
+            sweetness: sum.sweetness / n,
+            overall: sum.overall / n,
+        })
+    }
+}
+
+/// Cups poured for each sample on the SCA form.
+pub const CUPS_PER_SAMPLE: u32 = 5;
+
+/// Cups with off-flavours. A taint costs 2 points per cup and a fault 4.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CuppingDefects {
+    #[serde(default)]
+    pub taint_cups: u32,
+    #[serde(default)]
+    pub fault_cups: u32,
+}
+
+impl CuppingDefects {
+    /// Cups affected by either defect, saturating rather than wrapping for
+    /// counts no form could hold.
+    pub fn cups(&self) -> u32 {
+        self.taint_cups.saturating_add(self.fault_cups)
+    }
+
+    pub fn points(&self) -> f64 {
+        f64::from(
+            self.taint_cups
+                .saturating_mul(2)
+                .saturating_add(self.fault_cups.saturating_mul(4)),
+        )
+    }
+}
+
+/// One cupper's scores for one sample.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CuppingForm {
+    pub cupper: String,
+    /// Index into the session's samples.
+    pub sample: usize,
+    pub scores: CuppingScores,
+    #[serde(default)]
+    pub defects: CuppingDefects,
+    pub notes: Option<String>,
+    /// Sum of the ten attributes.
+    pub total_score: f64,
+    /// Total score less defects.
+    pub final_score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CuppingSession {
+    pub id: Option<Thing>,
+    pub name: String,
+    #[serde(serialize_with = "crate::datetime::serialize")]
+    pub cupped_at: DateTime<Utc>,
+    pub cuppers: Vec<String>,
+    pub samples: Vec<CuppingSample>,
+    #[serde(default)]
+    pub forms: Vec<CuppingForm>,
+    pub notes: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCuppingSampleRequest {
+    pub label: String,
+    pub kind: SampleKind,
+    pub green_coffee: Option<Thing>,
+    pub roast: Option<Thing>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCuppingSessionRequest {
+    pub name: String,
+    /// Defaults to now.
+    pub cupped_at: Option<DateTime<Utc>>,
+    pub cuppers: Vec<String>,
+    pub samples: Vec<CreateCuppingSampleRequest>,
+    pub notes: Option<String>,
+}
+
+impl From<CreateCuppingSessionRequest> for CuppingSession {
+    fn from(req: CreateCuppingSessionRequest) -> Self {
+        Self {
+            id: None,
+            name: req.name,
+            cupped_at: req.cupped_at.unwrap_or_else(Utc::now),
+            cuppers: req.cuppers,
+            samples: req
+                .samples
+                .into_iter()
+                .map(|sample| CuppingSample {
+                    label: sample.label,
+                    kind: sample.kind,
+                    green_coffee: sample.green_coffee,
+                    roast: sample.roast,
+                    description: sample.description,
+                })
+                .collect(),
+            forms: Vec::new(),
+            notes: req.notes,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitCuppingFormRequest {
+    pub cupper: String,
+    pub sample: usize,
+    pub scores: CuppingScores,
+    #[serde(default)]
+    pub defects: CuppingDefects,
+    pub notes: Option<String>,
+}
+
+impl From<SubmitCuppingFormRequest> for CuppingForm {
+    fn from(req: SubmitCuppingFormRequest) -> Self {
+        let total_score = req.scores.total();
+        Self {
+            cupper: req.cupper,
+            sample: req.sample,
+            scores: req.scores,
+            defects: req.defects,
+            notes: req.notes,
+            total_score,
+            final_score: total_score - req.defects.points(),
+        }
+    }
+}
+
+/// Scores across every form for one green or roast.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CuppingAggregate {
+    pub subject: Thing,
+    pub sessions: usize,
+    pub forms: usize,
+    pub mean_final_score: Option<f64>,
+    pub min_final_score: Option<f64>,
+    pub max_final_score: Option<f64>,
+    pub mean_scores: Option<CuppingScores>,
+    pub mean_defect_points: Option<f64>,
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Address {
+    pub label: Option<String>,
+    pub line1: String,
+    pub line2: Option<String>,
+    pub city: String,
+    pub region: Option<String>,
+    pub postal_code: String,
+    pub country: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Customer {
+    pub id: Option<Thing>,
+    pub name: String,
+    pub email: String,
+    pub addresses: Vec<Address>,
+    pub marketing_consent: bool,
+    pub notes: Option<String>,
+    /// Wholesale price list this customer buys from.
+    #[serde(default)]
+    pub price_list: Option<Thing>,
+    /// Set once the customer's personal data has been erased.
+    #[serde(default, serialize_with = "crate::datetime::serialize_option")]
+    pub erased_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomerRequest {
+    pub name: String,
+    pub email: String,
+    #[serde(default)]
+    pub addresses: Vec<Address>,
+    #[serde(default)]
+    pub marketing_consent: bool,
+    pub notes: Option<String>,
+    pub price_list: Option<Thing>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCustomerRequest {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub addresses: Option<Vec<Address>>,
+    pub marketing_consent: Option<bool>,
+    pub notes: Option<String>,
+    pub price_list: Option<Thing>,
+}
+
+impl From<CreateCustomerRequest> for Customer {
+    fn from(req: CreateCustomerRequest) -> Self {
+        Self {
+            id: None,
+            name: req.name,
+            email: normalize_email(&req.email),
+            addresses: req.addresses,
+            marketing_consent: req.marketing_consent,
+            notes: req.notes,
+            price_list: req.price_list,
+            erased_at: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+/// Emails are compared case-insensitively, so they are stored trimmed and
+/// lowercased.
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
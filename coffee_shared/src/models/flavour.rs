@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+/// Separator between levels of a flavour category path, as in
+/// "fruity > citrus > lemon".
+pub const FLAVOUR_PATH_SEPARATOR: &str = " > ";
+
+/// Form used to compare tasting notes and category names: lowercase, with
+/// punctuation and repeated spaces collapsed, so "Green/Vegetative" and
+/// "green vegetative" are the same.
+pub fn flavour_key(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A node of the flavour wheel with everything beneath it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlavourCategory {
+    pub name: String,
+    /// Keys of this node and its ancestors, e.g. "fruity > citrus".
+    pub path: String,
+    pub children: Vec<FlavourCategory>,
+}
+
+/// Free text that should be read as a flavour category, such as "citrusy"
+/// for "fruity > citrus".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlavourAlias {
+    pub id: Option<Thing>,
+    pub alias: String,
+    pub category: String,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateFlavourAliasRequest {
+    pub alias: String,
+    /// Category path; any spelling that resolves to a category is accepted.
+    pub category: String,
+}
+
+impl From<CreateFlavourAliasRequest> for FlavourAlias {
+    fn from(req: CreateFlavourAliasRequest) -> Self {
+        Self {
+            id: None,
+            alias: req.alias,
+            category: req.category,
+            created_at: None,
+        }
+    }
+}
+
+/// A tasting note in use on greens or roasts and the category it maps to,
+/// if any.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteMapping {
+    pub note: String,
+    pub category: Option<String>,
+    pub greens: usize,
+    pub roasts: usize,
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FreshnessStatus {
+    /// Still degassing and not ready to brew.
+    Resting,
+    Peak,
+    /// Past its peak but before its best-before date.
+    Ageing,
+    Expired,
+}
+
+/// How long roasted coffee rests, stays at its peak and keeps, all counted
+/// in days from the roast date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FreshnessRule {
+    pub rest_days: i64,
+    /// Day the peak ends, after the rest.
+    pub peak_days: i64,
+    /// Day of the best-before date.
+    pub shelf_life_days: i64,
+}
+
+impl FreshnessRule {
+    pub fn status(&self, roast_date: DateTime<Utc>, at: DateTime<Utc>) -> FreshnessStatus {
+        let age = at - roast_date;
+        if age < Duration::days(self.rest_days) {
+            FreshnessStatus::Resting
+        } else if age < Duration::days(self.peak_days) {
+            FreshnessStatus::Peak
+        } else if age < Duration::days(self.shelf_life_days) {
+            FreshnessStatus::Ageing
+        } else {
+            FreshnessStatus::Expired
+        }
+    }
+
+    pub fn freshness(
+        &self,
+        roast: Thing,
+        roast_date: DateTime<Utc>,
+        at: DateTime<Utc>,
+    ) -> Freshness {
+        Freshness {
+            roast,
+            roast_date,
+            age_days: (at - roast_date).num_days(),
+            status: self.status(roast_date, at),
+            rested_at: roast_date + Duration::days(self.rest_days),
+            peak_until: roast_date + Duration::days(self.peak_days),
+            best_before: roast_date + Duration::days(self.shelf_life_days),
+        }
+    }
+}
+
+/// A rule for every roast of one level. Levels are matched ignoring case and
+/// surrounding spaces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoastLevelFreshness {
+    pub roast_level: String,
+    #[serde(flatten)]
+    pub rule: FreshnessRule,
+}
+
+/// Rest periods and shelf life. A product's own rule wins over its roast
+/// level's, which wins over the default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FreshnessSettings {
+    pub id: Option<Thing>,
+    pub default_rule: FreshnessRule,
+    #[serde(default)]
+    pub roast_levels: Vec<RoastLevelFreshness>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for FreshnessSettings {
+    fn default() -> Self {
+        Self {
+            id: None,
+            default_rule: FreshnessRule {
+                rest_days: 7,
+                peak_days: 30,
+                shelf_life_days: 180,
+            },
+            roast_levels: Vec::new(),
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateFreshnessSettingsRequest {
+    pub default_rule: Option<FreshnessRule>,
+    pub roast_levels: Option<Vec<RoastLevelFreshness>>,
+}
+
+/// Where a roast batch is in its life.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Freshness {
+    pub roast: Thing,
+    pub roast_date: DateTime<Utc>,
+    pub age_days: i64,
+    pub status: FreshnessStatus,
+    pub rested_at: DateTime<Utc>,
+    pub peak_until: DateTime<Utc>,
+    pub best_before: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoastFreshness {
+    pub name: String,
+    pub roast_level: String,
+    pub yield_grams: f64,
+    #[serde(flatten)]
+    pub freshness: Freshness,
+}
+
+/// Stock of one product variant and the freshness of the roast it was
+/// packed from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantFreshness {
+    pub product: Thing,
+    pub variant: String,
+    pub name: String,
+    pub stock_units: i32,
+    pub available_units: i32,
+    #[serde(flatten)]
+    pub freshness: Freshness,
+}
+
+/// What to use first, oldest roast first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FifoRecommendations {
+    /// Rested batches that have not expired, to pack from.
+    pub packing: Vec<RoastFreshness>,
+    /// Variants with unreserved stock that has not expired, to ship from.
+    pub shipping: Vec<VariantFreshness>,
+}
@@ -0,0 +1,53 @@
+use crate::money::Currency;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FxRateSource {
+    Manual,
+    Csv,
+}
+
+/// One unit of `base` buys `rate` units of `quote`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxRate {
+    pub id: Option<Thing>,
+    pub base: Currency,
+    pub quote: Currency,
+    pub rate: Decimal,
+    pub source: FxRateSource,
+    #[serde(serialize_with = "crate::datetime::serialize")]
+    pub effective_at: DateTime<Utc>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateFxRateRequest {
+    pub base: Currency,
+    pub quote: Currency,
+    pub rate: Decimal,
+    /// Defaults to now.
+    pub effective_at: Option<DateTime<Utc>>,
+}
+
+impl From<CreateFxRateRequest> for FxRate {
+    fn from(req: CreateFxRateRequest) -> Self {
+        Self {
+            id: None,
+            base: req.base,
+            quote: req.quote,
+            rate: req.rate,
+            source: FxRateSource::Manual,
+            effective_at: req.effective_at.unwrap_or_else(Utc::now),
+            created_at: None,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::models::{Certification, CertificationType};
+use crate::money::Money;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::Thing;
@@ -12,17 +14,54 @@ pub struct GreenCoffee {
     pub processing_method: Option<String>,
     pub altitude_masl: Option<i32>,
     pub harvest_year: Option<i32>,
+    /// Day the lot was first received.
+    #[serde(default, serialize_with = "crate::datetime::serialize_option")]
+    pub arrival_date: Option<DateTime<Utc>>,
     pub stock_grams: f64,
-    pub price_per_kg: Option<f64>,
-    pub price_currency: Option<String>,
-    pub supplier: Option<String>,
-    pub cupping_notes: Option<Vec<String>>,
+    pub price_per_kg: Option<Money>,
+    /// Farm or cooperative that grew the lot.
+    #[serde(default)]
+    pub producer: Option<String>,
+    /// Price paid to the producer at the farm gate, for price transparency.
+    #[serde(default)]
+    pub farm_gate_price_per_kg: Option<Money>,
+    #[serde(default)]
+    pub certifications: Vec<Certification>,
+    pub supplier: Option<Thing>,
+    /// Free-text supplier from before suppliers were records, kept until
+    /// `POST /suppliers/migrate` links it to one.
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legacy_supplier: Option<String>,
+    pub cupping_notes: Option<Vec<String>>,
+    /// Roast whose profile other roasts of this coffee are compared against.
+    #[serde(default)]
+    pub reference_roast: Option<Thing>,
+    /// Stock level, in grams, at which the lot should be reordered.
+    #[serde(default)]
+    pub reorder_point_grams: Option<f64>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+impl GreenCoffee {
+    /// Whether the lot holds an unexpired certificate of `certification_type`.
+    pub fn is_certified(&self, certification_type: CertificationType, at: DateTime<Utc>) -> bool {
+        self.certifications.iter().any(|certification| {
+            certification.certification_type == certification_type && certification.is_valid(at)
+        })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateGreenCoffeeRequest {
     pub name: String,
@@ -32,11 +71,17 @@ pub struct CreateGreenCoffeeRequest {
     pub processing_method: Option<String>,
     pub altitude_masl: Option<i32>,
     pub harvest_year: Option<i32>,
+    #[serde(default)]
+    pub arrival_date: Option<DateTime<Utc>>,
     pub stock_grams: f64,
-    pub price_per_kg: Option<f64>,
-    pub price_currency: Option<String>,
-    pub supplier: Option<String>,
+    pub price_per_kg: Option<Money>,
+    pub producer: Option<String>,
+    pub farm_gate_price_per_kg: Option<Money>,
+    #[serde(default)]
+    pub certifications: Vec<Certification>,
+    pub supplier: Option<Thing>,
     pub cupping_notes: Option<Vec<String>>,
+    pub reorder_point_grams: Option<f64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -48,11 +93,16 @@ pub struct UpdateGreenCoffeeRequest {
     pub processing_method: Option<String>,
     pub altitude_masl: Option<i32>,
     pub harvest_year: Option<i32>,
+    pub arrival_date: Option<DateTime<Utc>>,
     pub stock_grams: Option<f64>,
-    pub price_per_kg: Option<f64>,
-    pub price_currency: Option<String>,
-    pub supplier: Option<String>,
+    pub price_per_kg: Option<Money>,
+    pub producer: Option<String>,
+    pub farm_gate_price_per_kg: Option<Money>,
+    pub certifications: Option<Vec<Certification>>,
+    pub supplier: Option<Thing>,
     pub cupping_notes: Option<Vec<String>>,
+    pub reference_roast: Option<Thing>,
+    pub reorder_point_grams: Option<f64>,
 }
 
 impl From<CreateGreenCoffeeRequest> for GreenCoffee {
@@ -66,11 +116,17 @@ impl From<CreateGreenCoffeeRequest> for GreenCoffee {
             processing_method: req.processing_method,
             altitude_masl: req.altitude_masl,
             harvest_year: req.harvest_year,
+            arrival_date: req.arrival_date,
             stock_grams: req.stock_grams,
             price_per_kg: req.price_per_kg,
-            price_currency: req.price_currency,
+            producer: req.producer,
+            farm_gate_price_per_kg: req.farm_gate_price_per_kg,
+            certifications: req.certifications,
             supplier: req.supplier,
+            legacy_supplier: None,
             cupping_notes: req.cupping_notes,
+            reference_roast: None,
+            reorder_point_grams: req.reorder_point_grams,
             created_at: None,
             updated_at: None,
         }
@@ -0,0 +1,198 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QualityMetric {
+    MoisturePercent,
+    DensityGPerL,
+    WaterActivity,
+}
+
+impl QualityMetric {
+    pub const ALL: [QualityMetric; 3] = [
+        QualityMetric::MoisturePercent,
+        QualityMetric::DensityGPerL,
+        QualityMetric::WaterActivity,
+    ];
+}
+
+/// One reading of a green lot's condition, taken on arrival or as it ages.
+/// Metrics that were not measured are `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GreenMeasurement {
+    pub id: Option<Thing>,
+    pub green_coffee: Thing,
+    #[serde(serialize_with = "crate::datetime::serialize")]
+    pub measured_at: DateTime<Utc>,
+    pub moisture_percent: Option<f64>,
+    /// Free-settled bulk density.
+    pub density_g_per_l: Option<f64>,
+    pub water_activity: Option<f64>,
+    pub notes: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl GreenMeasurement {
+    pub fn value(&self, metric: QualityMetric) -> Option<f64> {
+        match metric {
+            QualityMetric::MoisturePercent => self.moisture_percent,
+            QualityMetric::DensityGPerL => self.density_g_per_l,
+            QualityMetric::WaterActivity => self.water_activity,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateGreenMeasurementRequest {
+    /// Defaults to now.
+    pub measured_at: Option<DateTime<Utc>>,
+    pub moisture_percent: Option<f64>,
+    pub density_g_per_l: Option<f64>,
+    pub water_activity: Option<f64>,
+    pub notes: Option<String>,
+}
+
+/// Inclusive bounds for one metric; a missing bound is not checked.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AcceptableRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl AcceptableRange {
+    pub fn between(min: f64, max: f64) -> Self {
+        Self {
+            min: Some(min),
+            max: Some(max),
+        }
+    }
+
+    pub fn breach(&self, value: f64) -> Option<RangeBreach> {
+        if self.min.is_some_and(|min| value < min) {
+            Some(RangeBreach::Below)
+        } else if self.max.is_some_and(|max| value > max) {
+            Some(RangeBreach::Above)
+        } else {
+            None
+        }
+    }
+}
+
+/// Acceptable condition of green coffee in storage. The defaults follow common
+/// storage guidance: below 9% moisture a lot is drying out, above 12% or a
+/// water activity over 0.65 it is at risk of mould.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityRanges {
+    pub id: Option<Thing>,
+    pub moisture_percent: AcceptableRange,
+    pub density_g_per_l: AcceptableRange,
+    pub water_activity: AcceptableRange,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for QualityRanges {
+    fn default() -> Self {
+        Self {
+            id: None,
+            moisture_percent: AcceptableRange::between(9.0, 12.0),
+            density_g_per_l: AcceptableRange::between(600.0, 850.0),
+            water_activity: AcceptableRange::between(0.45, 0.65),
+            updated_at: None,
+        }
+    }
+}
+
+impl QualityRanges {
+    pub fn range(&self, metric: QualityMetric) -> AcceptableRange {
+        match metric {
+            QualityMetric::MoisturePercent => self.moisture_percent,
+            QualityMetric::DensityGPerL => self.density_g_per_l,
+            QualityMetric::WaterActivity => self.water_activity,
+        }
+    }
+
+    /// Warnings for every metric whose latest reading in `measurements` is out
+    /// of range. `measurements` must all belong to `green_coffee`.
+    pub fn warnings(
+        &self,
+        green_coffee: &Thing,
+        name: &str,
+        measurements: &[GreenMeasurement],
+    ) -> Vec<QualityWarning> {
+        let mut warnings = Vec::new();
+        for metric in QualityMetric::ALL {
+            let mut readings: Vec<(DateTime<Utc>, f64)> = measurements
+                .iter()
+                .filter_map(|m| m.value(metric).map(|value| (m.measured_at, value)))
+                .collect();
+            readings.sort_by_key(|(measured_at, _)| *measured_at);
+            let (Some(&(_, first)), Some(&(measured_at, value))) =
+                (readings.first(), readings.last())
+            else {
+                continue;
+            };
+
+            let range = self.range(metric);
+            if let Some(breach) = range.breach(value) {
+                warnings.push(QualityWarning {
+                    green_coffee: green_coffee.clone(),
+                    name: name.to_string(),
+                    metric,
+                    value,
+                    breach,
+                    range,
+                    measured_at,
+                    change_since_first: (readings.len() > 1).then_some(value - first),
+                });
+            }
+        }
+        warnings
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateQualityRangesRequest {
+    pub moisture_percent: Option<AcceptableRange>,
+    pub density_g_per_l: Option<AcceptableRange>,
+    pub water_activity: Option<AcceptableRange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeBreach {
+    Below,
+    Above,
+}
+
+/// A green lot whose latest reading of a metric is outside its acceptable range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityWarning {
+    pub green_coffee: Thing,
+    pub name: String,
+    pub metric: QualityMetric,
+    pub value: f64,
+    pub breach: RangeBreach,
+    pub range: AcceptableRange,
+    pub measured_at: DateTime<Utc>,
+    /// How far the metric has moved since the lot's first reading of it.
+    pub change_since_first: Option<f64>,
+}
+
+/// A saved reading and any warnings the lot now has.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedMeasurement {
+    pub measurement: GreenMeasurement,
+    pub warnings: Vec<QualityWarning>,
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementReason {
+    Shipment,
+    Receipt,
+    Roast,
+}
+
+/// A single change to the stock of a product or green coffee lot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryMovement {
+    pub id: Option<Thing>,
+    pub item: Thing,
+    /// The variant of a product item.
+    #[serde(default)]
+    pub variant: Option<String>,
+    /// Signed change in stock: units for products, grams for green coffee.
+    pub quantity: f64,
+    pub reason: MovementReason,
+    pub reference: Option<Thing>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+}
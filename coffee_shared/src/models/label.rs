@@ -0,0 +1,148 @@
+use super::Grind;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+/// Layout of a bag label. Each line is printed as given with placeholders
+/// filled in: `{name}`, `{origin}`, `{process}`, `{notes}`, `{net_weight}`,
+/// `{grind}`, `{roast_level}`, `{roast_date}`, `{best_before}` and `{sku}`.
+/// Lines whose placeholders are all empty are left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelTemplate {
+    pub id: Option<Thing>,
+    pub name: String,
+    pub width_mm: f64,
+    pub height_mm: f64,
+    /// Print density of the thermal printer ZPL is sent to.
+    pub dpi: u32,
+    pub lines: Vec<String>,
+    /// Print the variant's EAN-13 under the text, if it has one.
+    pub show_barcode: bool,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Default for LabelTemplate {
+    fn default() -> Self {
+        Self {
+            id: None,
+            name: "Standard bag".to_string(),
+            width_mm: 100.0,
+            height_mm: 70.0,
+            dpi: 203,
+            lines: [
+                "{name}",
+                "{origin}",
+                "{process}",
+                "{notes}",
+                "{net_weight} {grind}",
+                "Roasted {roast_date}",
+                "Best before {best_before}",
+            ]
+            .map(String::from)
+            .to_vec(),
+            show_barcode: true,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateLabelTemplateRequest {
+    pub name: String,
+    pub width_mm: f64,
+    pub height_mm: f64,
+    pub dpi: Option<u32>,
+    pub lines: Option<Vec<String>>,
+    pub show_barcode: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateLabelTemplateRequest {
+    pub name: Option<String>,
+    pub width_mm: Option<f64>,
+    pub height_mm: Option<f64>,
+    pub dpi: Option<u32>,
+    pub lines: Option<Vec<String>>,
+    pub show_barcode: Option<bool>,
+}
+
+impl From<CreateLabelTemplateRequest> for LabelTemplate {
+    fn from(req: CreateLabelTemplateRequest) -> Self {
+        let defaults = LabelTemplate::default();
+
+        Self {
+            id: None,
+            name: req.name,
+            width_mm: req.width_mm,
+            height_mm: req.height_mm,
+            dpi: req.dpi.unwrap_or(defaults.dpi),
+            lines: req.lines.unwrap_or(defaults.lines),
+            show_barcode: req.show_barcode.unwrap_or(defaults.show_barcode),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelFormat {
+    #[default]
+    Pdf,
+    Zpl,
+    /// The label contents without a layout, for previews.
+    Json,
+}
+
+/// Bags of one product variant to label.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LabelItem {
+    pub product: Thing,
+    /// Required when the product has more than one variant.
+    pub variant: Option<String>,
+    /// The batch the bags were packed from, if not the product's roast.
+    pub roast: Option<Thing>,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrintLabelsRequest {
+    /// Uses the standard layout when not given.
+    pub template: Option<Thing>,
+    #[serde(default)]
+    pub format: LabelFormat,
+    pub items: Vec<LabelItem>,
+}
+
+/// What is printed on the bags of one product variant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BagLabel {
+    pub product: Thing,
+    pub variant: String,
+    pub roast: Thing,
+    pub name: String,
+    pub origin: String,
+    pub process: Option<String>,
+    pub tasting_notes: Vec<String>,
+    pub net_weight_grams: f64,
+    pub grind: Grind,
+    pub roast_date: DateTime<Utc>,
+    pub best_before: DateTime<Utc>,
+    pub sku: Option<String>,
+    pub gtin: Option<String>,
+    /// The template's lines with this label's values filled in.
+    pub lines: Vec<String>,
+    pub quantity: i32,
+}
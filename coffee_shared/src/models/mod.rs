@@ -1,7 +1,51 @@
+pub mod alert;
+pub mod barcode;
+pub mod certification;
+pub mod costing;
+pub mod crop_age;
+pub mod cupping;
+pub mod customer;
+pub mod flavour;
+pub mod freshness;
+pub mod fx_rate;
 pub mod green_coffee;
+pub mod green_measurement;
+pub mod inventory_movement;
+pub mod label;
+pub mod order;
+pub mod payment;
+pub mod price_list;
 pub mod product;
+pub mod production;
+pub mod purchase_order;
 pub mod roast;
+pub mod roast_profile;
+pub mod subscription;
+pub mod supplier;
+pub mod trace;
 
+pub use alert::*;
+pub use barcode::*;
+pub use certification::*;
+pub use costing::*;
+pub use crop_age::*;
+pub use cupping::*;
+pub use customer::*;
+pub use flavour::*;
+pub use freshness::*;
+pub use fx_rate::*;
 pub use green_coffee::*;
+pub use green_measurement::*;
+pub use inventory_movement::*;
+pub use label::*;
+pub use order::*;
+pub use payment::*;
+pub use price_list::*;
 pub use product::*;
+pub use production::*;
+pub use purchase_order::*;
 pub use roast::*;
+pub use roast_profile::*;
+pub use subscription::*;
+pub use supplier::*;
+pub use trace::*;
@@ -0,0 +1,118 @@
+use crate::models::Grind;
+use crate::money::{Currency, Money};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Pending,
+    Paid,
+    Picking,
+    Packed,
+    Shipped,
+    Delivered,
+    Cancelled,
+    Refunded,
+}
+
+impl OrderStatus {
+    /// Whether an order in this status may move to `next`. Orders can be
+    /// cancelled until they ship, and refunded once paid unless they are
+    /// still on their way to the customer.
+    pub fn can_transition_to(self, next: OrderStatus) -> bool {
+        use OrderStatus::*;
+
+        matches!(
+            (self, next),
+            (Pending, Paid)
+                | (Paid, Picking)
+                | (Picking, Packed)
+                | (Packed, Shipped)
+                | (Shipped, Delivered)
+                | (Pending | Paid | Picking | Packed, Cancelled)
+                | (Paid | Cancelled | Delivered, Refunded)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderLine {
+    pub product: Thing,
+    /// Key of the product variant ordered.
+    #[serde(default)]
+    pub variant: Option<String>,
+    pub quantity: i32,
+    pub unit_price: Money,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub id: Option<Thing>,
+    #[serde(default)]
+    pub customer: Option<Thing>,
+    /// The subscription that generated this order, if any.
+    #[serde(default)]
+    pub subscription: Option<Thing>,
+    pub lines: Vec<OrderLine>,
+    pub total: Money,
+    pub status: OrderStatus,
+    /// Whether the order's units are counted in `ProductVariant.reserved_units`.
+    #[serde(default)]
+    pub stock_reserved: bool,
+    #[serde(default)]
+    pub carrier: Option<String>,
+    #[serde(default)]
+    pub tracking_number: Option<String>,
+    #[serde(default, serialize_with = "crate::datetime::serialize_option")]
+    pub shipped_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOrderLineRequest {
+    pub product: Thing,
+    /// Required when the product has more than one variant.
+    pub variant: Option<String>,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOrderRequest {
+    pub customer: Option<Thing>,
+    pub lines: Vec<CreateOrderLineRequest>,
+    /// Defaults to the currency of the first product; other products are
+    /// converted at the current exchange rate.
+    pub currency: Option<Currency>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateOrderStatusRequest {
+    pub status: OrderStatus,
+    /// Required when moving an order to `shipped`.
+    pub tracking_number: Option<String>,
+    pub carrier: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickListLine {
+    pub product: Thing,
+    pub variant: String,
+    pub name: String,
+    pub package_size_grams: f64,
+    pub grind: Grind,
+    pub quantity: i32,
+    pub orders: Vec<Thing>,
+}
@@ -0,0 +1,48 @@
+use crate::money::Money;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentStatus {
+    Authorized,
+    Captured,
+    Refunded,
+    Failed,
+}
+
+impl PaymentStatus {
+    /// Whether a payment in this status may move to `next`. Provider events
+    /// can arrive late or out of order, so anything else is treated as stale.
+    pub fn can_transition_to(self, next: PaymentStatus) -> bool {
+        matches!(
+            (self, next),
+            (PaymentStatus::Authorized, PaymentStatus::Captured)
+                | (PaymentStatus::Authorized, PaymentStatus::Failed)
+                | (PaymentStatus::Captured, PaymentStatus::Refunded)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Payment {
+    pub id: Option<Thing>,
+    pub order: Thing,
+    pub provider: String,
+    pub provider_reference: String,
+    pub amount: Money,
+    pub status: PaymentStatus,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,112 @@
+use crate::money::Money;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceListKind {
+    /// Quantity breaks that apply to every customer.
+    Retail,
+    /// A tier that customers are assigned to through `Customer.price_list`.
+    Wholesale,
+    /// Negotiated prices for the single customer in `PriceList.customer`.
+    Customer,
+}
+
+/// The price of a product from `min_quantity` units upwards. Prices are in
+/// the product's own currency. A break without a variant applies to every
+/// variant of the product.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceBreak {
+    pub product: Thing,
+    #[serde(default)]
+    pub variant: Option<String>,
+    pub min_quantity: i32,
+    pub price: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceList {
+    pub id: Option<Thing>,
+    pub name: String,
+    pub kind: PriceListKind,
+    pub customer: Option<Thing>,
+    pub entries: Vec<PriceBreak>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl PriceList {
+    /// The price of the largest quantity break for `variant` of `product`
+    /// that `quantity` reaches, if this list prices the variant at all.
+    pub fn price_for(&self, product: &Thing, variant: &str, quantity: i32) -> Option<Decimal> {
+        self.entries
+            .iter()
+            .filter(|entry| &entry.product == product && entry.min_quantity <= quantity)
+            .filter(|entry| entry.variant.as_deref().is_none_or(|key| key == variant))
+            .max_by_key(|entry| entry.min_quantity)
+            .map(|entry| entry.price)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePriceListRequest {
+    pub name: String,
+    pub kind: PriceListKind,
+    pub customer: Option<Thing>,
+    #[serde(default)]
+    pub entries: Vec<PriceBreak>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePriceListRequest {
+    pub name: Option<String>,
+    pub entries: Option<Vec<PriceBreak>>,
+}
+
+impl From<CreatePriceListRequest> for PriceList {
+    fn from(req: CreatePriceListRequest) -> Self {
+        Self {
+            id: None,
+            name: req.name,
+            kind: req.kind,
+            customer: req.customer,
+            entries: req.entries,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PriceQuoteRequest {
+    pub product: Thing,
+    /// Required when the product has more than one variant.
+    pub variant: Option<String>,
+    pub customer: Option<Thing>,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceQuote {
+    pub product: Thing,
+    pub variant: String,
+    pub customer: Option<Thing>,
+    pub quantity: i32,
+    pub unit_price: Money,
+    pub total: Money,
+    /// The list the price came from, or `None` for the product's base price.
+    pub price_list: Option<Thing>,
+}
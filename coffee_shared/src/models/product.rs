@@ -1,7 +1,68 @@
+use super::{CertificationType, FreshnessRule};
+use crate::money::Money;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::Thing;
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grind {
+    #[default]
+    WholeBean,
+    Espresso,
+    Filter,
+    Cafetiere,
+}
+
+impl Grind {
+    pub fn slug(self) -> &'static str {
+        match self {
+            Grind::WholeBean => "whole-bean",
+            Grind::Espresso => "espresso",
+            Grind::Filter => "filter",
+            Grind::Cafetiere => "cafetiere",
+        }
+    }
+}
+
+/// Identifies a variant within its product, e.g. `250g-whole-bean`.
+pub fn variant_key(package_size_grams: f64, grind: Grind) -> String {
+    format!("{}g-{}", package_size_grams, grind.slug())
+}
+
+/// One bag size and grind of a product, with its own price and stock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductVariant {
+    /// Derived from the package size and grind, see [`variant_key`].
+    pub key: String,
+    pub package_size_grams: f64,
+    #[serde(default)]
+    pub grind: Grind,
+    #[serde(default)]
+    pub sku: Option<String>,
+    /// EAN-13 printed on the bag, stored as 13 digits.
+    #[serde(default)]
+    pub gtin: Option<String>,
+    pub price: Money,
+    pub stock_units: i32,
+    /// Units held back for orders that have not shipped yet.
+    #[serde(default)]
+    pub reserved_units: i32,
+    /// Units to keep on the shelf beyond open orders and subscriptions.
+    #[serde(default)]
+    pub target_stock_units: Option<i32>,
+    /// Unreserved units at which the variant should be roasted again.
+    #[serde(default)]
+    pub reorder_point_units: Option<i32>,
+}
+
+impl ProductVariant {
+    /// Stock that is neither shipped nor reserved for an open order.
+    pub fn available_units(&self) -> i32 {
+        self.stock_units - self.reserved_units
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Product {
     pub id: Option<Thing>,
@@ -11,16 +72,105 @@ pub struct Product {
     pub category: Option<String>,
     pub colours: Option<Vec<String>>,
     pub details: Option<Vec<String>>,
-    pub package_size_grams: f64,
-    pub price: f64,
-    pub price_currency: Option<String>,
-    pub stock_units: i32,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub variants: Vec<ProductVariant>,
+    /// Rest period and shelf life for this product, instead of its roast
+    /// level's.
+    #[serde(default)]
+    pub freshness: Option<FreshnessRule>,
+    /// Certifications held by every green the product was roasted from,
+    /// worked out when products are read.
+    #[serde(default)]
+    pub certifications: Vec<CertificationType>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+impl Product {
+    /// The variant with `key`, or the only variant when no key is given.
+    pub fn variant(&self, key: Option<&str>) -> Option<&ProductVariant> {
+        match key {
+            Some(key) => self.variants.iter().find(|variant| variant.key == key),
+            None if self.variants.len() == 1 => self.variants.first(),
+            None => None,
+        }
+    }
+
+    pub fn variant_mut(&mut self, key: Option<&str>) -> Option<&mut ProductVariant> {
+        match key {
+            Some(key) => self.variants.iter_mut().find(|variant| variant.key == key),
+            None if self.variants.len() == 1 => self.variants.first_mut(),
+            None => None,
+        }
+    }
+
+    /// "Name (250g whole-bean)", or just the name for single-variant products.
+    pub fn variant_name(&self, variant: &ProductVariant) -> String {
+        if self.variants.len() > 1 {
+            format!(
+                "{} ({}g {})",
+                self.name,
+                variant.package_size_grams,
+                variant.grind.slug()
+            )
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateProductVariantRequest {
+    pub package_size_grams: f64,
+    #[serde(default)]
+    pub grind: Grind,
+    /// Generated from the SKU settings when not given.
+    pub sku: Option<String>,
+    /// A GTIN-12, GTIN-13 or GTIN-14, stored as an EAN-13.
+    pub gtin: Option<String>,
+    pub price: Money,
+    pub stock_units: i32,
+    pub target_stock_units: Option<i32>,
+    pub reorder_point_units: Option<i32>,
+}
+
+impl From<CreateProductVariantRequest> for ProductVariant {
+    fn from(req: CreateProductVariantRequest) -> Self {
+        Self {
+            key: variant_key(req.package_size_grams, req.grind),
+            package_size_grams: req.package_size_grams,
+            grind: req.grind,
+            sku: req.sku,
+            gtin: req.gtin,
+            price: req.price,
+            stock_units: req.stock_units,
+            reserved_units: 0,
+            target_stock_units: req.target_stock_units,
+            reorder_point_units: req.reorder_point_units,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateProductVariantRequest {
+    pub sku: Option<String>,
+    pub gtin: Option<String>,
+    pub price: Option<Money>,
+    pub stock_units: Option<i32>,
+    pub target_stock_units: Option<i32>,
+    pub reorder_point_units: Option<i32>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateProductRequest {
     pub roast: Option<Thing>,
@@ -29,10 +179,13 @@ pub struct CreateProductRequest {
     pub category: Option<String>,
     pub colours: Option<Vec<String>>,
     pub details: Option<Vec<String>>,
-    pub package_size_grams: f64,
-    pub price: f64,
-    pub price_currency: Option<String>,
-    pub stock_units: i32,
+    pub freshness: Option<FreshnessRule>,
+    #[serde(default)]
+    pub variants: Vec<CreateProductVariantRequest>,
+    /// Shorthand for a product with a single variant, used when `variants`
+    /// is empty.
+    #[serde(flatten)]
+    pub single: Option<CreateProductVariantRequest>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -43,14 +196,20 @@ pub struct UpdateProductRequest {
     pub category: Option<String>,
     pub colours: Option<Vec<String>>,
     pub details: Option<Vec<String>>,
-    pub package_size_grams: Option<f64>,
-    pub price: Option<f64>,
-    pub price_currency: Option<String>,
-    pub stock_units: Option<i32>,
+    pub freshness: Option<FreshnessRule>,
+    /// Variant fields, accepted for single-variant products only.
+    #[serde(flatten)]
+    pub variant: UpdateProductVariantRequest,
 }
 
 impl From<CreateProductRequest> for Product {
     fn from(req: CreateProductRequest) -> Self {
+        let variants = if req.variants.is_empty() {
+            req.single.into_iter().map(ProductVariant::from).collect()
+        } else {
+            req.variants.into_iter().map(ProductVariant::from).collect()
+        };
+
         Self {
             id: None,
             roast: req.roast,
@@ -59,10 +218,9 @@ impl From<CreateProductRequest> for Product {
             category: req.category,
             colours: req.colours,
             details: req.details,
-            package_size_grams: req.package_size_grams,
-            price: req.price,
-            price_currency: req.price_currency,
-            stock_units: req.stock_units,
+            variants,
+            freshness: req.freshness,
+            certifications: Vec::new(),
             created_at: None,
             updated_at: None,
         }
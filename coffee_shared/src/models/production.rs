@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+/// Units of one product variant needed over the planning horizon.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductDemand {
+    pub product: Thing,
+    pub variant: String,
+    pub name: String,
+    /// Units on orders that have not shipped yet.
+    pub open_order_units: i32,
+    /// Units subscriptions are expected to order within the horizon.
+    pub forecast_units: i32,
+    pub target_stock_units: i32,
+    pub stock_units: i32,
+    /// Demand plus target less stock on hand, never negative.
+    pub units_to_make: i32,
+    pub roasted_grams: f64,
+}
+
+/// Roasting needed for one coffee, a green roasted to one level, and the
+/// batches proposed to cover it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoastRequirement {
+    pub green_coffee: Thing,
+    pub name: String,
+    pub roast_level: String,
+    pub products: Vec<Thing>,
+    pub roasted_grams: f64,
+    /// Typical loss of past roasts of this green at this level.
+    pub expected_loss_percent: f64,
+    pub green_grams: f64,
+    pub batches: u32,
+    pub batch_size_grams: f64,
+    /// Whether the green on hand cannot cover every coffee roasted from it.
+    pub short_of_green: bool,
+}
+
+/// Green stock against what the plan would roast from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GreenAvailability {
+    pub green_coffee: Thing,
+    pub name: String,
+    pub required_grams: f64,
+    pub stock_grams: f64,
+    pub shortfall_grams: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductionPlan {
+    pub weeks: i64,
+    pub batch_capacity_grams: f64,
+    pub products: Vec<ProductDemand>,
+    pub roasts: Vec<RoastRequirement>,
+    pub greens: Vec<GreenAvailability>,
+    /// Roaster's choice bags in the forecast; they are not tied to a coffee
+    /// until the order is generated.
+    pub roasters_choice_bags: i32,
+    /// Products that need making but have no roast or green to plan from.
+    pub unplanned_products: Vec<Thing>,
+}
@@ -0,0 +1,145 @@
+use crate::money::Money;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PurchaseOrderStatus {
+    Open,
+    PartiallyReceived,
+    Received,
+    Cancelled,
+}
+
+/// One contracted lot. Weights are in grams like green stock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrderLine {
+    /// Lot name or mark, used as the green coffee name on first receipt.
+    pub lot: String,
+    pub origin_country: String,
+    /// Green coffee the lot is received into, set by the first receipt
+    /// unless the order tops up an existing green.
+    pub green_coffee: Option<Thing>,
+    pub contracted_grams: f64,
+    pub price_per_kg: Money,
+    #[serde(default)]
+    pub received_grams: f64,
+}
+
+impl PurchaseOrderLine {
+    pub fn outstanding_grams(&self) -> f64 {
+        (self.contracted_grams - self.received_grams).max(0.0)
+    }
+}
+
+/// Coffee that arrived against a purchase order line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GreenReceipt {
+    /// Index into the purchase order's lines.
+    pub line: usize,
+    pub green_coffee: Thing,
+    pub grams: f64,
+    #[serde(serialize_with = "crate::datetime::serialize")]
+    pub received_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseOrder {
+    pub id: Option<Thing>,
+    pub supplier: Thing,
+    /// Contract or shipment reference from the supplier.
+    pub reference: Option<String>,
+    pub lines: Vec<PurchaseOrderLine>,
+    #[serde(serialize_with = "crate::datetime::serialize_option")]
+    pub expected_arrival: Option<DateTime<Utc>>,
+    pub status: PurchaseOrderStatus,
+    #[serde(default)]
+    pub receipts: Vec<GreenReceipt>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePurchaseOrderLineRequest {
+    pub lot: String,
+    pub origin_country: String,
+    /// Existing green coffee to top up instead of creating a new one.
+    pub green_coffee: Option<Thing>,
+    pub contracted_grams: f64,
+    pub price_per_kg: Money,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePurchaseOrderRequest {
+    pub supplier: Thing,
+    pub reference: Option<String>,
+    pub lines: Vec<CreatePurchaseOrderLineRequest>,
+    pub expected_arrival: Option<DateTime<Utc>>,
+}
+
+impl From<CreatePurchaseOrderRequest> for PurchaseOrder {
+    fn from(req: CreatePurchaseOrderRequest) -> Self {
+        Self {
+            id: None,
+            supplier: req.supplier,
+            reference: req.reference,
+            lines: req
+                .lines
+                .into_iter()
+                .map(|line| PurchaseOrderLine {
+                    lot: line.lot,
+                    origin_country: line.origin_country,
+                    green_coffee: line.green_coffee,
+                    contracted_grams: line.contracted_grams,
+                    price_per_kg: line.price_per_kg,
+                    received_grams: 0.0,
+                })
+                .collect(),
+            expected_arrival: req.expected_arrival,
+            status: PurchaseOrderStatus::Open,
+            receipts: Vec::new(),
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReceiveLineRequest {
+    pub line: usize,
+    pub grams: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReceivePurchaseOrderRequest {
+    pub lines: Vec<ReceiveLineRequest>,
+    /// Defaults to now.
+    pub received_at: Option<DateTime<Utc>>,
+}
+
+/// Contracted coffee still to arrive on one purchase order line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutstandingLine {
+    pub purchase_order: Thing,
+    pub supplier: Thing,
+    pub reference: Option<String>,
+    pub line: usize,
+    pub lot: String,
+    pub contracted_grams: f64,
+    pub received_grams: f64,
+    pub outstanding_grams: f64,
+    /// Contract value of the outstanding weight.
+    pub outstanding_value: Money,
+    pub expected_arrival: Option<DateTime<Utc>>,
+}
@@ -1,27 +1,111 @@
+use crate::models::ProfileComparison;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::Thing;
 
+/// How the components of a blend come together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMethod {
+    /// The greens are mixed and roasted together in one batch.
+    PreRoast,
+    /// Separate roasts are mixed after roasting.
+    PostRoast,
+}
+
+/// One part of a blend and its share by weight.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoastComponent {
+    /// The green lot going into a pre-roast blend. Filled from the component
+    /// roast for post-roast blends made from a single green.
+    #[serde(default)]
+    pub green_coffee: Option<Thing>,
+    /// The roast going into a post-roast blend.
+    #[serde(default)]
+    pub roast: Option<Thing>,
+    pub percent: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Roast {
     pub id: Option<Thing>,
     pub name: String,
+    /// The green lot of a single-origin roast. Blends list theirs in
+    /// `components` instead.
     pub green_coffee: Option<Thing>,
+    #[serde(default)]
+    pub blend: Option<BlendMethod>,
+    #[serde(default)]
+    pub components: Vec<RoastComponent>,
+    #[serde(serialize_with = "crate::datetime::serialize_option")]
     pub date_roasted: Option<DateTime<Utc>>,
     pub roast_level: String,
     pub batch_size_grams: f64,
     pub yield_grams: f64,
     pub notes: Option<Vec<String>>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    /// Latest comparison of this roast's profile against its green's reference.
+    #[serde(default)]
+    pub profile_comparison: Option<ProfileComparison>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
     pub created_at: Option<DateTime<Utc>>,
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+impl Roast {
+    /// Weight lost in roasting as a percentage of the green batch, if the
+    /// batch size is known.
+    pub fn loss_percent(&self) -> Option<f64> {
+        (self.batch_size_grams > 0.0)
+            .then(|| (self.batch_size_grams - self.yield_grams) / self.batch_size_grams * 100.0)
+    }
+
+    /// Every green lot in the roast, including those of blend components.
+    pub fn greens(&self) -> impl Iterator<Item = &Thing> {
+        self.green_coffee.iter().chain(
+            self.components
+                .iter()
+                .filter_map(|component| component.green_coffee.as_ref()),
+        )
+    }
+
+    /// Grams of each green lot taken out of stock by this batch. Post-roast
+    /// blends take none, their component roasts already did.
+    pub fn green_usage(&self) -> Vec<(Thing, f64)> {
+        match self.blend {
+            None => self
+                .green_coffee
+                .iter()
+                .map(|green| (green.clone(), self.batch_size_grams))
+                .collect(),
+            Some(BlendMethod::PreRoast) => self
+                .components
+                .iter()
+                .filter_map(|component| {
+                    let grams = self.batch_size_grams * component.percent / 100.0;
+                    Some((component.green_coffee.clone()?, grams))
+                })
+                .collect(),
+            Some(BlendMethod::PostRoast) => Vec::new(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateRoastRequest {
     pub name: String,
     pub green_coffee: Option<Thing>,
+    pub blend: Option<BlendMethod>,
+    #[serde(default)]
+    pub components: Vec<RoastComponent>,
     pub date_roasted: Option<DateTime<Utc>>,
     pub roast_level: String,
     pub batch_size_grams: f64,
@@ -33,6 +117,8 @@ pub struct CreateRoastRequest {
 pub struct UpdateRoastRequest {
     pub name: Option<String>,
     pub green_coffee: Option<Thing>,
+    pub blend: Option<BlendMethod>,
+    pub components: Option<Vec<RoastComponent>>,
     pub date_roasted: Option<DateTime<Utc>>,
     pub roast_level: Option<String>,
     pub batch_size_grams: Option<f64>,
@@ -46,13 +132,48 @@ impl From<CreateRoastRequest> for Roast {
             id: None,
             name: req.name,
             green_coffee: req.green_coffee,
+            blend: req.blend,
+            components: req.components,
             date_roasted: req.date_roasted,
             roast_level: req.roast_level,
             batch_size_grams: req.batch_size_grams,
             yield_grams: req.yield_grams,
             notes: req.notes,
+            profile_comparison: None,
             created_at: None,
             updated_at: None,
         }
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoastLoss {
+    pub roast: Thing,
+    pub name: String,
+    pub roast_level: String,
+    pub green_coffee: Option<Thing>,
+    pub date_roasted: Option<DateTime<Utc>>,
+    pub loss_percent: f64,
+    /// Median loss of roasts at the same level, when there are enough to compare.
+    pub expected_loss_percent: Option<f64>,
+    pub outlier: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoastLossSummary {
+    /// The roast level, green coffee record id or `YYYY-MM` month.
+    pub key: String,
+    pub roasts: usize,
+    pub average_loss_percent: f64,
+    pub min_loss_percent: f64,
+    pub max_loss_percent: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoastLossReport {
+    pub roasts: Vec<RoastLoss>,
+    pub by_roast_level: Vec<RoastLossSummary>,
+    pub by_green_coffee: Vec<RoastLossSummary>,
+    pub by_month: Vec<RoastLossSummary>,
+    pub outliers: Vec<RoastLoss>,
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoastEventKind {
+    Charge,
+    TurningPoint,
+    DryEnd,
+    FirstCrack,
+    Drop,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoastEvent {
+    pub kind: RoastEventKind,
+    /// Seconds since charge.
+    pub time_seconds: f64,
+    pub bean_temp: Option<f64>,
+}
+
+/// One logger reading. Temperatures are `None` where the probe had no reading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileSample {
+    /// Seconds since charge; readings before charge are negative.
+    pub time_seconds: f64,
+    pub bean_temp: Option<f64>,
+    pub env_temp: Option<f64>,
+    /// Bean temperature rise in degrees per minute.
+    pub rate_of_rise: Option<f64>,
+}
+
+/// The temperature curve logged for a roast, one per roast.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoastProfile {
+    pub id: Option<Thing>,
+    pub roast: Thing,
+    /// "C" or "F", as logged.
+    pub temperature_unit: String,
+    pub samples: Vec<ProfileSample>,
+    pub events: Vec<RoastEvent>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl RoastProfile {
+    pub fn event(&self, kind: RoastEventKind) -> Option<&RoastEvent> {
+        self.events.iter().find(|event| event.kind == kind)
+    }
+
+    /// Share of the roast, from charge to drop, spent after first crack.
+    pub fn development_time_ratio(&self) -> Option<f64> {
+        let first_crack = self.event(RoastEventKind::FirstCrack)?.time_seconds;
+        let drop = self.event(RoastEventKind::Drop)?.time_seconds;
+        (drop > 0.0 && first_crack <= drop).then(|| (drop - first_crack) / drop)
+    }
+
+    /// Bean temperature at `time_seconds` after charge, interpolated between
+    /// the readings either side.
+    pub fn bean_temp_at(&self, time_seconds: f64) -> Option<f64> {
+        let readings: Vec<(f64, f64)> = self
+            .samples
+            .iter()
+            .filter_map(|s| Some((s.time_seconds, s.bean_temp?)))
+            .collect();
+        let after = readings.iter().position(|(t, _)| *t >= time_seconds)?;
+        let (t1, bt1) = readings[after];
+        if after == 0 {
+            return (t1 == time_seconds).then_some(bt1);
+        }
+        let (t0, bt0) = readings[after - 1];
+        Some(bt0 + (bt1 - bt0) * (time_seconds - t0) / (t1 - t0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileVerdict {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// How far a roast's curve strayed from the reference roast of its green.
+/// Temperature differences are in the roast's own unit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileComparison {
+    pub reference: Thing,
+    /// Root mean square difference in bean temperature, aligned by charge.
+    pub bean_temp_rmse: f64,
+    /// Development time ratio of the roast minus that of the reference.
+    pub development_time_ratio_diff: Option<f64>,
+    pub drop_temp_delta: Option<f64>,
+    pub verdict: ProfileVerdict,
+    #[serde(serialize_with = "crate::datetime::serialize")]
+    pub compared_at: DateTime<Utc>,
+}
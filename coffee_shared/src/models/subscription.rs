@@ -0,0 +1,120 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    Active,
+    Paused,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: Option<Thing>,
+    pub customer: Thing,
+    /// The product to send, or `None` for roaster's choice.
+    pub product: Option<Thing>,
+    /// Key of the product variant to send.
+    #[serde(default)]
+    pub variant: Option<String>,
+    pub quantity: i32,
+    pub interval_weeks: i32,
+    #[serde(serialize_with = "crate::datetime::serialize")]
+    pub next_run_at: DateTime<Utc>,
+    pub status: SubscriptionStatus,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl Subscription {
+    pub fn interval(&self) -> Duration {
+        Duration::weeks(self.interval_weeks.max(1).into())
+    }
+
+    /// Moves `next_run_at` to the first scheduled date after `now`. Missed
+    /// runs are skipped rather than queued up.
+    pub fn advance_past(&mut self, now: DateTime<Utc>) {
+        while self.next_run_at <= now {
+            self.next_run_at += self.interval();
+        }
+    }
+
+    /// Dates on which orders will be generated between `now` and `until`.
+    /// An overdue subscription counts once, at `now`.
+    pub fn runs_between(&self, now: DateTime<Utc>, until: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let mut runs = Vec::new();
+        let mut upcoming = self.clone();
+        if upcoming.next_run_at <= now {
+            runs.push(now);
+            upcoming.advance_past(now);
+        }
+        while upcoming.next_run_at < until {
+            runs.push(upcoming.next_run_at);
+            upcoming.next_run_at += upcoming.interval();
+        }
+        runs
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSubscriptionRequest {
+    pub customer: Thing,
+    pub product: Option<Thing>,
+    /// Required when the product has more than one variant.
+    pub variant: Option<String>,
+    pub quantity: i32,
+    pub interval_weeks: i32,
+    /// Defaults to now, so the first order goes out on the next scheduler run.
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSubscriptionRequest {
+    pub product: Option<Thing>,
+    pub variant: Option<String>,
+    /// Go back to the roaster's choice, dropping the product and variant.
+    #[serde(default)]
+    pub roasters_choice: bool,
+    pub quantity: Option<i32>,
+    pub interval_weeks: Option<i32>,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+impl From<CreateSubscriptionRequest> for Subscription {
+    fn from(req: CreateSubscriptionRequest) -> Self {
+        Self {
+            id: None,
+            customer: req.customer,
+            product: req.product,
+            variant: req.variant,
+            quantity: req.quantity,
+            interval_weeks: req.interval_weeks,
+            next_run_at: req.next_run_at.unwrap_or_else(Utc::now),
+            status: SubscriptionStatus::Active,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionForecastLine {
+    /// `None` groups the roaster's choice subscriptions.
+    pub product: Option<Thing>,
+    pub variant: Option<String>,
+    pub name: String,
+    pub bags: i32,
+    pub grams: Option<f64>,
+}
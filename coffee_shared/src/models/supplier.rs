@@ -0,0 +1,136 @@
+use crate::models::Address;
+use crate::money::Currency;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+/// Company suffixes that do not tell two suppliers apart.
+const LEGAL_SUFFIXES: [&str; 10] = [
+    "co", "corp", "gmbh", "inc", "limited", "llc", "ltd", "pty", "sa", "bv",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Supplier {
+    pub id: Option<Thing>,
+    pub name: String,
+    /// Other spellings of the name, such as those merged from free text.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    pub contact_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<Address>,
+    /// Typical days from placing an order to the coffee arriving.
+    pub lead_time_days: Option<i32>,
+    /// Currency the supplier invoices in.
+    pub currency: Option<Currency>,
+    pub payment_terms: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "crate::datetime::serialize_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSupplierRequest {
+    pub name: String,
+    pub aliases: Option<Vec<String>>,
+    pub contact_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<Address>,
+    pub lead_time_days: Option<i32>,
+    pub currency: Option<Currency>,
+    pub payment_terms: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSupplierRequest {
+    pub name: Option<String>,
+    pub aliases: Option<Vec<String>>,
+    pub contact_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<Address>,
+    pub lead_time_days: Option<i32>,
+    pub currency: Option<Currency>,
+    pub payment_terms: Option<String>,
+}
+
+impl From<CreateSupplierRequest> for Supplier {
+    fn from(req: CreateSupplierRequest) -> Self {
+        Self {
+            id: None,
+            name: req.name,
+            aliases: req.aliases.unwrap_or_default(),
+            contact_name: req.contact_name,
+            email: req.email,
+            phone: req.phone,
+            address: req.address,
+            lead_time_days: req.lead_time_days,
+            currency: req.currency,
+            payment_terms: req.payment_terms,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+}
+
+impl Supplier {
+    /// Whether `name` is this supplier's name or one of its aliases, once
+    /// both are normalized with [`supplier_key`].
+    pub fn answers_to(&self, name: &str) -> bool {
+        let key = supplier_key(name);
+        std::iter::once(&self.name)
+            .chain(&self.aliases)
+            .any(|known| supplier_key(known) == key)
+    }
+}
+
+/// Reduces a supplier name to a key that ignores case, accents,
+/// punctuation and company suffixes, so "Café Imports, Inc." and
+/// "cafe imports" match.
+pub fn supplier_key(name: &str) -> String {
+    let folded: String = name
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .to_lowercase();
+    let mut words: Vec<&str> = folded.split_whitespace().collect();
+    while words.len() > 1 && words.last().is_some_and(|w| LEGAL_SUFFIXES.contains(w)) {
+        words.pop();
+    }
+    words.join(" ")
+}
+
+/// Free-text supplier names that were merged into one supplier record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupplierMerge {
+    /// `None` in a dry run for suppliers that would be created.
+    pub supplier: Option<Thing>,
+    pub name: String,
+    /// Whether the record is new rather than an existing supplier.
+    pub created: bool,
+    /// Each distinct spelling found, including the chosen name.
+    pub merged_names: Vec<String>,
+    pub greens: Vec<Thing>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupplierMigrationReport {
+    pub dry_run: bool,
+    pub suppliers: Vec<SupplierMerge>,
+    /// Greens whose free-text supplier was blank and was simply cleared.
+    pub cleared: Vec<Thing>,
+}
@@ -0,0 +1,41 @@
+use crate::models::GreenCoffee;
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceDirection {
+    /// The record the trace started from.
+    Root,
+    /// Something the root was made from or bought through, towards the farm.
+    Upstream,
+    /// Something made from the root, towards the customer.
+    Downstream,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceNode {
+    pub id: Thing,
+    /// Name or reference of the record, where it has one.
+    pub name: Option<String>,
+    pub direction: TraceDirection,
+    /// Number of relations between this record and the root.
+    pub depth: usize,
+}
+
+/// One relation, always pointing upstream: a roast is `roasted_from` its green.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceEdge {
+    pub from: Thing,
+    pub relation: String,
+    pub to: Thing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceGraph {
+    pub root: Thing,
+    pub nodes: Vec<TraceNode>,
+    pub edges: Vec<TraceEdge>,
+    /// Origin details of every green lot in the graph.
+    pub origins: Vec<GreenCoffee>,
+}